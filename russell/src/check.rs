@@ -0,0 +1,111 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use russell_engine::{Engine, EngineError, Goal, Source, line_column};
+
+use crate::{Answer, Run, Status};
+
+/// Runs `russell check`: checks every goal in each source, read as a
+/// document with definitions, assumptions and includes. Each goal is a line
+/// of its own when there are several, named after where it is, and a source
+/// without any goals is an error.
+pub fn run(sources: &[Source], run: &mut Run) {
+    let engine = run.engine;
+
+    for source in sources {
+        let started = Instant::now();
+        let report = engine.check_document(source.clone(), load);
+
+        run.many = sources.len() > 1 || report.goals.len() > 1;
+
+        // errors aren't about any one goal, so they get the whole document's
+        // time
+        let elapsed = started.elapsed();
+
+        // an empty input is more likely a mistake than a vacuous success
+        if report.errors.is_empty() && report.goals.is_empty() {
+            run.fail(source, None, EngineError::NothingToCheck, elapsed);
+        }
+
+        for (index, error) in report.errors {
+            run.fail(&report.sources[index], None, error, elapsed);
+        }
+
+        for goal in &report.goals {
+            let source = &report.sources[goal.source];
+
+            let name = match report.goals.len() {
+                1 => source.name.clone(),
+                _ => {
+                    let (line, column) = line_column(&source.text, goal.span.start);
+                    format!("{}:{line}:{column}", source.name)
+                }
+            };
+
+            let started = Instant::now();
+            let answer = answer(engine, goal);
+            let elapsed = goal.elapsed + started.elapsed();
+
+            match answer {
+                Ok(answer) => run.answer(&name, Some(&goal.formula), answer, elapsed),
+                Err(error) => run.fail(source, Some(&goal.formula), error, elapsed),
+            }
+        }
+    }
+}
+
+/// What to say about `goal`: whether it's a tautology if there's nothing
+/// to assume, as for a lone formula, or else whether it follows
+fn answer(engine: &Engine, goal: &Goal) -> Result<Answer, EngineError> {
+    let Some(counterexample) = goal.counterexample.clone() else {
+        let verdict = match goal.assumptions.is_empty() {
+            true => "tautology",
+            false => "follows",
+        };
+
+        return Ok(Answer::new(Status::Holds, verdict).verdict(verdict));
+    };
+
+    let verdict = match goal.assumptions.is_empty() {
+        true if engine.check_satisfiable(&goal.formula)? => "contingent",
+        true => "contradiction",
+        false => "does_not_follow",
+    };
+
+    let text = format!(
+        "{}, counterexample: {counterexample}",
+        verdict.replace('_', " ")
+    );
+
+    Ok(Answer::new(Status::Fails, text)
+        .verdict(verdict)
+        .counterexample(Some(counterexample)))
+}
+
+/// Reads the file an `include` names, relative to the file it's in, or to
+/// the working directory for stdin and `-e`
+pub fn load(from: &Source, path: &str) -> Result<Source, String> {
+    let dir = match Path::new(&from.name).parent() {
+        Some(dir) if Path::new(&from.name).is_file() => dir.to_path_buf(),
+        _ => PathBuf::new(),
+    };
+
+    let path = dir.join(path);
+    let text = fs::read_to_string(&path).map_err(|error| error.to_string())?;
+
+    // the same file reached by different paths should have the same name,
+    // so includes going round in a circle are caught
+    let path = fs::canonicalize(&path).map_err(|error| error.to_string())?;
+    let name = env::current_dir()
+        .ok()
+        .and_then(|cwd| Some(path.strip_prefix(cwd).ok()?.to_path_buf()))
+        .unwrap_or(path);
+
+    Ok(Source {
+        name: name.display().to_string(),
+        text,
+    })
+}
@@ -0,0 +1,241 @@
+use clap::{
+    Args, Parser, Subcommand,
+    builder::{PossibleValuesParser, TypedValueParser},
+};
+use russell_engine::TableFormat;
+
+/// What the exit status means, shown at the end of `--help`
+const EXIT_CODES: &str = "\
+Exit status:
+  0  every formula has the property asked about (tautology, satisfiable,
+     true, equivalent), or the command just prints something
+  1  some formula doesn't have it
+  2  the command line couldn't be understood
+  3  some input couldn't be read, parsed or worked with";
+
+/// A propositional logic engine
+#[derive(Debug, Parser)]
+#[command(name = "russell", version, after_help = EXIT_CODES)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+
+    /// How to print results. `table` can also be given a layout.
+    #[arg(long, global = true, default_value = "text", value_parser = format_parser())]
+    pub format: Format,
+}
+
+/// How to print results
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Text,
+
+    /// A versioned document, laid out in [crate::json]
+    Json,
+
+    /// One of the layouts `table` can draw, `text` being
+    /// [TableFormat::Unicode]
+    Table(TableFormat),
+}
+
+fn format_parser() -> impl TypedValueParser<Value = Format> {
+    let names = ["text", "json"].iter().chain(TableFormat::NAMES).copied();
+
+    PossibleValuesParser::new(names).map(|name: String| match name.as_str() {
+        "text" => Format::Text,
+        "json" => Format::Json,
+        name => Format::Table(TableFormat::from_name(name).unwrap()),
+    })
+}
+
+/// Where to read inputs from. Each file, `-e` or stdin holds one formula,
+/// except for `check`, which reads each as a document: several goals along
+/// with `let`, `assume` and `include` statements.
+#[derive(Debug, Args)]
+pub struct Inputs {
+    /// Files holding a formula each, or globs matching them, like
+    /// `examples/*.rsl`. Stdin is read if there are no files or formulas,
+    /// or for `-`.
+    #[arg(value_name = "FILE")]
+    pub files: Vec<String>,
+
+    /// A formula to use as well as the files
+    #[arg(short = 'e', long = "expr", value_name = "FORMULA")]
+    pub exprs: Vec<String>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Checks whether each formula is a tautology, showing a counterexample
+    /// if it isn't. Inputs are documents that can hold several formulas,
+    /// along with `let` definitions, `assume` premises that later `prove`
+    /// goals must follow from, `include "file"` and `#`, `//` or `/* */`
+    /// comments.
+    #[command(
+        mut_arg("files", |arg| arg.help(
+            "Documents to check, or globs matching them, like `examples/*.rsl`. \
+             Stdin is read if there are no files or documents, or for `-`"
+        )),
+        mut_arg("exprs", |arg| arg
+            .help("A document to check as well as the files")
+            .value_name("DOCUMENT")),
+    )]
+    Check {
+        #[command(flatten)]
+        inputs: Inputs,
+    },
+
+    /// Evaluates each formula under the given assignments
+    Eval {
+        #[command(flatten)]
+        inputs: Inputs,
+
+        /// The value of a variable, like `p=true`. Every variable needs one.
+        #[arg(short, long = "assign", value_name = "NAME=VALUE", value_parser = parse_assignment)]
+        assign: Vec<(String, bool)>,
+    },
+
+    /// Prints the truth table of each formula
+    Table {
+        #[command(flatten)]
+        inputs: Inputs,
+
+        /// Writes 1 and 0 instead of T and F
+        #[arg(long)]
+        digits: bool,
+
+        /// Adds a column for every subformula
+        #[arg(long)]
+        subformulas: bool,
+
+        /// The variables to put first, in order
+        #[arg(long, value_name = "VARS", value_delimiter = ',')]
+        order: Vec<String>,
+    },
+
+    /// Looks for an assignment making each formula true
+    Sat {
+        #[command(flatten)]
+        inputs: Inputs,
+
+        /// Also counts the assignments making it true
+        #[arg(long)]
+        count: bool,
+    },
+
+    /// Checks whether two formulas are equivalent, showing an assignment
+    /// they differ under if they aren't
+    Equiv {
+        #[command(flatten)]
+        inputs: Inputs,
+
+        /// Shows how one rewrites into the other, if the rewriter can find a
+        /// way
+        #[arg(long)]
+        steps: bool,
+    },
+
+    /// Rewrites each formula into a simpler equivalent one
+    Simplify {
+        #[command(flatten)]
+        inputs: Inputs,
+
+        /// Shows each law used along the way
+        #[arg(long)]
+        steps: bool,
+    },
+
+    /// Converts each formula to negation normal form
+    Nnf {
+        #[command(flatten)]
+        inputs: Inputs,
+    },
+
+    /// Converts each formula to conjunctive normal form
+    Cnf {
+        #[command(flatten)]
+        inputs: Inputs,
+
+        /// Uses the Tseitin encoding, which is only equisatisfiable but
+        /// doesn't blow up
+        #[arg(long)]
+        tseitin: bool,
+    },
+
+    /// Converts each formula to disjunctive normal form
+    Dnf {
+        #[command(flatten)]
+        inputs: Inputs,
+    },
+
+    /// Finds the smallest sum of products equivalent to each formula
+    Minimize {
+        #[command(flatten)]
+        inputs: Inputs,
+
+        /// Reads PLA files instead of formulas, printing them minimized
+        #[arg(long)]
+        pla: bool,
+    },
+
+    /// Checks every goal in each `.rsl` file against the
+    /// `# expect: tautology`, `# expect: contingent` or
+    /// `# expect-model: p=true` lines in its comments. Files without any
+    /// should be tautologies, and files without goals fail.
+    Test {
+        /// Files, or directories to search for `.rsl` files
+        #[arg(value_name = "PATH", required = true)]
+        paths: Vec<String>,
+    },
+
+    /// Starts an interactive session, with named formulas and line editing
+    Repl,
+
+    /// Draws the Karnaugh map of each formula
+    Karnaugh {
+        #[command(flatten)]
+        inputs: Inputs,
+
+        /// Draws it as an SVG image instead of text
+        #[arg(long)]
+        svg: bool,
+    },
+}
+
+impl Command {
+    /// The subcommand's name, as typed
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Check { .. } => "check",
+            Command::Eval { .. } => "eval",
+            Command::Table { .. } => "table",
+            Command::Sat { .. } => "sat",
+            Command::Equiv { .. } => "equiv",
+            Command::Simplify { .. } => "simplify",
+            Command::Nnf { .. } => "nnf",
+            Command::Cnf { .. } => "cnf",
+            Command::Dnf { .. } => "dnf",
+            Command::Minimize { .. } => "minimize",
+            Command::Karnaugh { .. } => "karnaugh",
+            Command::Test { .. } => "test",
+            Command::Repl => "repl",
+        }
+    }
+}
+
+/// Reads `name=value`, where the value is `true` or `false`
+fn parse_assignment(arg: &str) -> Result<(String, bool), String> {
+    let Some((name, value)) = arg.split_once('=') else {
+        return Err(format!("expected NAME=VALUE, got `{arg}`"));
+    };
+
+    if name.is_empty() {
+        return Err("variable names can't be empty".to_string());
+    }
+
+    match value {
+        "true" => Ok((name.to_string(), true)),
+        "false" => Ok((name.to_string(), false)),
+        _ => Err(format!("`{value}` isn't a value (must be true or false)")),
+    }
+}
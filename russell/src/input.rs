@@ -0,0 +1,77 @@
+use std::{
+    fs,
+    io::{self, Read},
+};
+
+use anyhow::{Context, bail};
+
+pub use russell_engine::Source;
+
+use crate::cli::Inputs;
+
+/// What stdin is called in rendered diagnostics
+const STDIN_NAME: &str = "<stdin>";
+
+impl Inputs {
+    /// Reads every input: the files in the order given, each glob's matches
+    /// in sorted order, then the formulas given with `--expr`. Reads stdin
+    /// if there's nothing else.
+    pub fn read(&self) -> anyhow::Result<Vec<Source>> {
+        let mut sources = Vec::new();
+
+        for file in &self.files {
+            if file == "-" {
+                sources.push(read_stdin()?);
+            } else if file.contains(['*', '?', '[']) {
+                let paths = glob::glob(file).with_context(|| format!("bad glob `{file}`"))?;
+                let mut paths = paths.collect::<Result<Vec<_>, _>>()?;
+                paths.sort();
+
+                if paths.is_empty() {
+                    bail!("no files match `{file}`");
+                }
+
+                for path in paths {
+                    sources.push(read_file(&path.to_string_lossy())?);
+                }
+            } else {
+                sources.push(read_file(file)?);
+            }
+        }
+
+        for (i, expr) in self.exprs.iter().enumerate() {
+            sources.push(Source {
+                name: format!("<expr {}>", i + 1),
+                text: expr.clone(),
+            });
+        }
+
+        if sources.is_empty() {
+            sources.push(read_stdin()?);
+        }
+
+        Ok(sources)
+    }
+}
+
+fn read_file(path: &str) -> anyhow::Result<Source> {
+    let text = fs::read_to_string(path).with_context(|| format!("couldn't read `{path}`"))?;
+
+    Ok(Source {
+        name: path.to_string(),
+        text,
+    })
+}
+
+fn read_stdin() -> anyhow::Result<Source> {
+    let mut text = String::new();
+    io::stdin()
+        .lock()
+        .read_to_string(&mut text)
+        .context("couldn't read stdin")?;
+
+    Ok(Source {
+        name: STDIN_NAME.to_string(),
+        text,
+    })
+}
@@ -0,0 +1,174 @@
+//! The document `--format json` prints, one per run:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "command": "check",
+//!   "exit_code": 1,
+//!   "results": [
+//!     {
+//!       "source": "examples/implication.rsl",
+//!       "formula": "(p => q) == !p || q",
+//!       "variables": ["p", "q"],
+//!       "verdict": "tautology",
+//!       "counterexample": null,
+//!       "witness": null,
+//!       "output": null,
+//!       "time_ms": 0.02,
+//!       "diagnostics": []
+//!     }
+//!   ],
+//!   "diagnostics": []
+//! }
+//! ```
+//!
+//! Every key is always present, holding `null` or an empty array when it
+//! doesn't apply. `output` depends on the command:
+//!
+//! - `check`: null, with a result per goal whose source is
+//!   `file:line:column` when a file has several, and the verdict `follows`
+//!   or `does_not_follow` for goals with assumptions
+//! - `eval`: the value, as a boolean
+//! - `table`: `{"columns": [..], "rows": [[..], ..]}`, rows of booleans
+//! - `sat`: `{"models": n}`, with `n` null unless `--count` is given. Counts
+//!   can be past 2^64, and are written out in full, so read them with a
+//!   parser that keeps big integers exact
+//! - `equiv`: `{"steps": [..]}`, with the steps null unless `--steps` is
+//!   given and the rewriter finds a derivation
+//! - `simplify`: `{"result": formula, "steps": [..]}`
+//! - `nnf`, `cnf`, `dnf` and `minimize`: the formula, or the PLA text with
+//!   `--pla`
+//! - `karnaugh`: the map as text, or SVG with `--svg`
+//! - `test`: `{"failures": [..]}`, the verdict being `pass` or `fail`
+//!
+//! where each step is `{"law", "reversed", "position", "formula"}`.
+//! Diagnostics about an input are on its result, and the top level ones are
+//! about the run as a whole, like a file that couldn't be read. Any change
+//! to the meaning of an existing key bumps [VERSION].
+
+use std::collections::BTreeMap;
+
+use russell_engine::{Assignments, Derivation, EngineError, Level, Report, line_column};
+use serde::Serialize;
+use serde_json::{Value, json};
+
+/// The schema version, the document's `version` key
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Serialize)]
+pub struct Document {
+    pub version: u32,
+    pub command: &'static str,
+    pub exit_code: u8,
+    pub results: Vec<Outcome>,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// What a command made of one input
+#[derive(Debug, Default, Serialize)]
+pub struct Outcome {
+    pub source: String,
+
+    /// The parsed formula, printed back out
+    pub formula: Option<String>,
+    pub variables: Vec<String>,
+    pub verdict: Option<&'static str>,
+    pub counterexample: Option<BTreeMap<String, bool>>,
+    pub witness: Option<BTreeMap<String, bool>>,
+    pub output: Value,
+    pub time_ms: f64,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Diagnostic {
+    /// The input the diagnostic is about, if any
+    pub source: Option<String>,
+    pub severity: &'static str,
+    pub message: String,
+    pub span: Option<SourceSpan>,
+    pub label: Option<String>,
+    pub help: Option<String>,
+}
+
+/// Where a diagnostic points: a byte range, and the line and column (in
+/// characters) it starts at, both counted from 1
+#[derive(Debug, Serialize)]
+pub struct SourceSpan {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Diagnostic {
+    /// A diagnostic with no position
+    pub fn message(source: Option<&str>, message: String) -> Self {
+        Diagnostic {
+            source: source.map(str::to_string),
+            severity: "error",
+            message,
+            span: None,
+            label: None,
+            help: None,
+        }
+    }
+
+    fn from_report(source: &str, text: &str, report: Report) -> Self {
+        let (line, column) = line_column(text, report.span.start);
+
+        Diagnostic {
+            source: Some(source.to_string()),
+            severity: match report.level {
+                Level::Error => "error",
+                Level::Warning => "warning",
+            },
+            message: report.message,
+            span: Some(SourceSpan {
+                start: report.span.start,
+                end: report.span.end,
+                line,
+                column,
+            }),
+            label: report.label,
+            help: report.help,
+        }
+    }
+
+    /// The diagnostics for `error`, which came from `text`, the contents of
+    /// `source`
+    pub fn from_error(source: &str, text: &str, error: &EngineError) -> Vec<Self> {
+        let reports = error.reports();
+
+        if reports.is_empty() {
+            return vec![Diagnostic::message(Some(source), error.to_string())];
+        }
+
+        reports
+            .into_iter()
+            .map(|report| Diagnostic::from_report(source, text, report))
+            .collect()
+    }
+}
+
+pub fn assignments(assignments: &Assignments) -> BTreeMap<String, bool> {
+    assignments
+        .iter()
+        .map(|(var, value)| (var.as_str().to_string(), value))
+        .collect()
+}
+
+pub fn steps(derivation: &Derivation) -> Value {
+    derivation
+        .steps
+        .iter()
+        .map(|step| {
+            json!({
+                "law": step.law,
+                "reversed": step.reversed,
+                "position": step.position,
+                "formula": step.result.to_string(),
+            })
+        })
+        .collect()
+}
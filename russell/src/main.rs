@@ -1,78 +1,512 @@
+mod check;
+mod cli;
+mod input;
+mod json;
+mod repl;
+mod suite;
+
 use std::{
-    collections::{HashMap, HashSet},
-    env::args,
-    io::{self, Read},
+    process::ExitCode,
+    time::{Duration, Instant},
 };
 
 use anyhow::bail;
-use russell_engine::Engine;
+use clap::Parser;
+use cli::{Cli, Command, Format};
+use input::Source;
+use json::{Diagnostic, Document, Outcome};
+use russell_engine::{
+    ASTNode, Assignments, Cnf, Engine, EngineError, Pla, Symbol, Table, TableFormat, ValueStyle,
+    to_cnf, to_dnf, to_nnf,
+};
+use serde_json::{Value, json};
+
+/// How a run went, which becomes the exit status. When inputs disagree,
+/// the later variant wins.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Status {
+    /// The formula has the property asked about, or there was nothing to
+    /// check
+    #[default]
+    Holds = 0,
+
+    /// The formula doesn't have the property
+    Fails = 1,
+
+    /// An input couldn't be read, parsed or worked with. Status 2 is left
+    /// to clap, for command lines it can't parse.
+    Error = 3,
+}
+
+impl Status {
+    fn from_bool(holds: bool) -> Self {
+        match holds {
+            true => Status::Holds,
+            false => Status::Fails,
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let engine = Engine::default();
+    let mut run = Run {
+        engine: &engine,
+        command: cli.command.name(),
+        json: cli.format == Format::Json,
+        many: false,
+        results: Vec::new(),
+        worst: Status::default(),
+    };
+
+    let diagnostics = match execute(cli.command, cli.format, &mut run) {
+        Ok(()) => Vec::new(),
+        Err(error) => vec![Diagnostic::message(None, format!("{error:#}"))],
+    };
+
+    ExitCode::from(run.finish(diagnostics) as u8)
+}
+
+/// What a command made of one formula
+#[derive(Debug, Default)]
+struct Answer {
+    status: Status,
+
+    /// What to print in text mode
+    text: String,
+    verdict: Option<&'static str>,
+    counterexample: Option<Assignments>,
+    witness: Option<Assignments>,
+
+    /// The JSON output's `output` field
+    output: Value,
+}
+
+impl Answer {
+    fn new(status: Status, text: impl Into<String>) -> Self {
+        Answer {
+            status,
+            text: text.into(),
+            ..Answer::default()
+        }
+    }
+
+    fn verdict(mut self, verdict: &'static str) -> Self {
+        self.verdict = Some(verdict);
+        self
+    }
+
+    fn counterexample(mut self, counterexample: Option<Assignments>) -> Self {
+        self.counterexample = counterexample;
+        self
+    }
+
+    fn witness(mut self, witness: Option<Assignments>) -> Self {
+        self.witness = witness;
+        self
+    }
+
+    fn output(mut self, output: Value) -> Self {
+        self.output = output;
+        self
+    }
+}
+
+/// Collects what a command makes of each input. In text mode that's
+/// printed as it comes in, and in JSON mode as one document at the end.
+#[derive(Debug)]
+struct Run<'a> {
+    /// The engine every command shares
+    engine: &'a Engine,
+    command: &'static str,
+    json: bool,
+
+    /// Whether there are several inputs, so text output says which is which
+    many: bool,
+    results: Vec<Outcome>,
+    worst: Status,
+}
+
+impl Run<'_> {
+    /// Records `answer` for the input called `name`, whose formula is `expr`
+    fn answer(&mut self, name: &str, expr: Option<&ASTNode>, answer: Answer, elapsed: Duration) {
+        self.worst = self.worst.max(answer.status);
+
+        if !self.json {
+            let text = answer.text.trim_end();
+
+            match (self.many, text.contains('\n')) {
+                (false, _) => println!("{text}"),
+                (true, false) => println!("{name}: {text}"),
+                (true, true) => println!("==> {name} <==\n{text}\n"),
+            }
 
-fn main() -> anyhow::Result<()> {
-    // read input
-    let stdin = io::stdin();
-    let mut lock = stdin.lock();
+            return;
+        }
 
-    let mut buf = String::default();
+        let mut outcome = self.outcome(name, expr, elapsed);
+        outcome.verdict = answer.verdict;
+        outcome.counterexample = answer.counterexample.as_ref().map(json::assignments);
+        outcome.witness = answer.witness.as_ref().map(json::assignments);
+        outcome.output = answer.output;
 
-    lock.read_to_string(&mut buf)?;
+        self.results.push(outcome);
+    }
+
+    /// Records `error`, which came from `source`'s formula `expr` if it
+    /// parsed
+    fn fail(
+        &mut self,
+        source: &Source,
+        expr: Option<&ASTNode>,
+        error: EngineError,
+        elapsed: Duration,
+    ) {
+        self.worst = Status::Error;
+
+        if !self.json {
+            eprint!("{}", error.render(&source.text, &source.name));
+            return;
+        }
 
-    // read assignments from cli args (todo: don't do that...)
-    let mut assignments: HashMap<char, bool> = HashMap::default();
+        let mut outcome = self.outcome(&source.name, expr, elapsed);
+        outcome.diagnostics = Diagnostic::from_error(&source.name, &source.text, &error);
 
-    for arg in args().skip(1) {
-        let split: Vec<&str> = arg.split("=").collect();
+        self.results.push(outcome);
+    }
+
+    fn outcome(&self, name: &str, expr: Option<&ASTNode>, elapsed: Duration) -> Outcome {
+        let variables = expr.map_or_else(Vec::new, |expr| self.engine.collect_variables(expr));
 
-        let first = split.first().expect("bad kv pair!");
-        let last = split.last().expect("bad kv pair!");
+        Outcome {
+            source: name.to_string(),
+            formula: expr.map(ToString::to_string),
+            variables: variables
+                .iter()
+                .map(|var| var.as_str().to_string())
+                .collect(),
+            time_ms: elapsed.as_secs_f64() * 1000.0,
+            ..Outcome::default()
+        }
+    }
 
-        let key = if first.len() != 1 {
-            bail!("variable names must be 1 character long!");
-        } else {
-            first.chars().last().unwrap()
+    /// Prints the document in JSON mode, and `diagnostics` about the run as
+    /// a whole. Returns the exit status.
+    fn finish(self, diagnostics: Vec<Diagnostic>) -> Status {
+        let status = match diagnostics.is_empty() {
+            true => self.worst,
+            false => Status::Error,
         };
 
-        let value = match last {
-            &"true" => true,
-            &"false" => false,
+        if !self.json {
+            for diagnostic in &diagnostics {
+                eprintln!("error: {}", diagnostic.message);
+            }
 
-            _ => bail!("invalid value (must be true or false)"),
+            return status;
+        }
+
+        let document = Document {
+            version: json::VERSION,
+            command: self.command,
+            exit_code: status as u8,
+            results: self.results,
+            diagnostics,
         };
 
-        assignments.insert(key, value);
+        println!("{}", serde_json::to_string_pretty(&document).unwrap());
+
+        status
     }
 
-    // eval
-    let engine = Engine::default();
+    /// Runs `command` on every source's formula
+    fn each(
+        &mut self,
+        sources: &[Source],
+        mut command: impl FnMut(&ASTNode) -> Result<Answer, EngineError>,
+    ) {
+        let engine = self.engine;
+        self.many = sources.len() > 1;
 
-    let expr = engine.parse(buf)?;
-    let variables = engine.collect_variables(&expr);
+        for source in sources {
+            let started = Instant::now();
 
-    // check if contradiction / tautology
-    if assignments.len() == 0 {
-        dbg!(engine.check_tautology(expr)?);
+            let (expr, spans) = match engine.parse_formula(&source.text) {
+                Ok(parsed) => parsed,
+                Err(error) => {
+                    self.fail(source, None, error, started.elapsed());
+                    continue;
+                }
+            };
 
-        return Ok(());
+            match command(&expr) {
+                Ok(answer) => self.answer(&source.name, Some(&expr), answer, started.elapsed()),
+                Err(error) => {
+                    self.fail(
+                        source,
+                        Some(&expr),
+                        error.locate(&expr, &spans),
+                        started.elapsed(),
+                    );
+                }
+            }
+        }
     }
+}
+
+fn execute(command: Command, format: Format, run: &mut Run) -> anyhow::Result<()> {
+    let engine = run.engine;
+    let limit = Engine::MAX_NORMAL_FORM_TERMS;
+
+    let layout = match (format, &command) {
+        (Format::Table(layout), Command::Table { .. }) => layout,
+        (Format::Table(_), _) => bail!("only `table` can use that --format"),
+        _ => TableFormat::default(),
+    };
 
-    // if we simply don't have enough assignments as we have variables
-    if assignments.len() != variables.len() {
-        let unassigned_vars = variables
-            .iter()
-            .filter(|symbol| !assignments.contains_key(*symbol))
-            .collect::<HashSet<_>>()
-            .iter()
-            .map(|x| x.to_string())
-            .collect::<Vec<_>>()
-            .join(", ");
-
-        bail!(
-            "not enough assignments! ({} are unassigned)",
-            unassigned_vars
-        );
+    if format == Format::Json && matches!(command, Command::Repl) {
+        bail!("the repl only prints text");
     }
 
-    // we have enough assignments, and we can simply evaluate the expression
-    println!("{}", engine.eval(expr, &assignments)?);
+    match command {
+        Command::Check { inputs } => check::run(&inputs.read()?, run),
+
+        Command::Eval { inputs, assign } => {
+            let assignments = assign
+                .into_iter()
+                .map(|(name, value)| (Symbol::intern(&name), value))
+                .collect::<Assignments>();
+
+            run.each(&inputs.read()?, |expr| {
+                let result = engine.eval(expr, &assignments)?;
+
+                Ok(Answer::new(Status::from_bool(result), result.to_string())
+                    .verdict(if result { "true" } else { "false" })
+                    .output(json!(result)))
+            })
+        }
+
+        Command::Table {
+            inputs,
+            digits,
+            subformulas,
+            order,
+        } => {
+            let style = match digits {
+                true => ValueStyle::Digits,
+                false => ValueStyle::Letters,
+            };
+
+            run.each(&inputs.read()?, |expr| {
+                let table = Table::builder(expr)
+                    .variables(order.iter().map(|name| Symbol::intern(name)))
+                    .subformulas(subformulas)
+                    .build()?;
+
+                let columns = table
+                    .variables()
+                    .iter()
+                    .map(|var| var.as_str().to_string())
+                    .chain(table.formulas().iter().map(ToString::to_string))
+                    .collect::<Vec<_>>();
+                let rows = (0..table.rows())
+                    .map(|row| {
+                        let variables =
+                            (0..table.variables().len()).map(|i| table.variable(row, i));
+                        let formulas = (0..table.formulas().len()).map(|i| table.value(row, i));
+
+                        variables.chain(formulas).collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>();
+
+                Ok(Answer::new(Status::Holds, table.render(layout, style))
+                    .output(json!({ "columns": columns, "rows": rows })))
+            })
+        }
+
+        Command::Sat { inputs, count } => run.each(&inputs.read()?, |expr| {
+            let models = match count {
+                true => Some(engine.count_models(expr)?),
+                false => None,
+            };
+            let output = json!({ "models": models });
+
+            let Some(witness) = engine.find_witness(expr)? else {
+                return Ok(Answer::new(Status::Fails, "unsatisfiable")
+                    .verdict("unsatisfiable")
+                    .output(output));
+            };
+
+            let mut text = match witness.is_empty() {
+                true => "satisfiable".to_string(),
+                false => format!("satisfiable, witness: {witness}"),
+            };
+
+            if let Some(models) = models {
+                text += &format!(" ({models} models)");
+            }
+
+            Ok(Answer::new(Status::Holds, text)
+                .verdict("satisfiable")
+                .witness(Some(witness))
+                .output(output))
+        }),
+
+        Command::Equiv { inputs, steps } => {
+            let sources = inputs.read()?;
+
+            let [p, q] = sources.as_slice() else {
+                bail!("equiv needs exactly two formulas, got {}", sources.len());
+            };
+
+            let started = Instant::now();
+
+            let parsed = (engine.parse_formula(&p.text), engine.parse_formula(&q.text));
+
+            let (p_expr, q_expr) = match parsed {
+                (Ok((p_expr, _)), Ok((q_expr, _))) => (p_expr, q_expr),
+                (p_parsed, q_parsed) => {
+                    for (source, parsed) in [(p, p_parsed), (q, q_parsed)] {
+                        if let Err(error) = parsed {
+                            run.fail(source, None, error, started.elapsed());
+                        }
+                    }
+
+                    return Ok(());
+                }
+            };
+
+            // neither source is to blame for errors about the combined
+            // formula, so they're reported without one
+            let combined = Source {
+                name: format!("{}, {}", p.name, q.name),
+                text: String::new(),
+            };
+            let equivalence =
+                ASTNode::Equivalent(Box::new(p_expr.clone()), Box::new(q_expr.clone()));
+
+            let counterexample = match engine.find_counterexample(&equivalence) {
+                Ok(counterexample) => counterexample,
+                Err(error) => {
+                    run.fail(&combined, Some(&equivalence), error, started.elapsed());
+                    return Ok(());
+                }
+            };
+
+            let answer = match counterexample {
+                Some(counterexample) => {
+                    let text = format!("not equivalent, they differ under: {counterexample}");
+
+                    Answer::new(Status::Fails, text)
+                        .verdict("not_equivalent")
+                        .counterexample(Some(counterexample))
+                        .output(json!({ "steps": null }))
+                }
+
+                None => {
+                    let derivation = match steps {
+                        true => engine.prove_equivalent(&p_expr, &q_expr),
+                        false => None,
+                    };
+
+                    let text = match (&derivation, steps) {
+                        (Some(derivation), _) => format!("equivalent\n{}", derivation.to_text()),
+                        (None, true) => {
+                            "equivalent\n(the rewriter found no derivation)".to_string()
+                        }
+                        (None, false) => "equivalent".to_string(),
+                    };
+
+                    Answer::new(Status::Holds, text)
+                        .verdict("equivalent")
+                        .output(json!({ "steps": derivation.as_ref().map(json::steps) }))
+                }
+            };
+
+            run.answer(
+                &combined.name,
+                Some(&equivalence),
+                answer,
+                started.elapsed(),
+            );
+        }
+
+        Command::Simplify { inputs, steps } => run.each(&inputs.read()?, |expr| {
+            let derivation = engine.derive(expr);
+            let result = derivation.result().to_string();
+
+            let text = match steps {
+                true => derivation.to_text(),
+                false => result.clone(),
+            };
+
+            Ok(Answer::new(Status::Holds, text).output(json!({
+                "result": result,
+                "steps": json::steps(&derivation),
+            })))
+        }),
+
+        Command::Nnf { inputs } => run.each(&inputs.read()?, |expr| Ok(formula(to_nnf(expr)))),
+
+        Command::Cnf { inputs, tseitin } => run.each(&inputs.read()?, |expr| {
+            let cnf = match tseitin {
+                true => Cnf::tseitin(expr, true).to_ast(),
+                false => to_cnf(expr, limit)?.to_ast(),
+            };
+
+            Ok(formula(cnf))
+        }),
+
+        Command::Dnf { inputs } => run.each(&inputs.read()?, |expr| {
+            Ok(formula(to_dnf(expr, limit)?.to_ast()))
+        }),
+
+        Command::Minimize { inputs, pla: true } => {
+            let sources = inputs.read()?;
+            run.many = sources.len() > 1;
+
+            for source in &sources {
+                let started = Instant::now();
+
+                match Pla::parse(&source.text) {
+                    Ok(pla) => {
+                        let text = pla.minimize().to_string();
+                        let answer = Answer::new(Status::Holds, &text).output(json!(text));
+
+                        run.answer(&source.name, None, answer, started.elapsed());
+                    }
+
+                    Err(error) => run.fail(source, None, error, started.elapsed()),
+                }
+            }
+        }
+
+        Command::Minimize { inputs, pla: false } => {
+            run.each(&inputs.read()?, |expr| Ok(formula(engine.minimize(expr)?)))
+        }
+
+        Command::Karnaugh { inputs, svg } => run.each(&inputs.read()?, |expr| {
+            let map = engine.karnaugh_map(expr)?;
+
+            let text = match svg {
+                true => map.to_svg(),
+                false => map.to_ascii(),
+            };
+
+            Ok(Answer::new(Status::Holds, &text).output(json!(text)))
+        }),
+
+        Command::Test { paths } => suite::run(&paths, run)?,
+        Command::Repl => repl::run()?,
+    }
 
     Ok(())
 }
+
+/// The answer of commands printing a converted formula
+fn formula(converted: ASTNode) -> Answer {
+    let converted = converted.to_string();
+
+    Answer::new(Status::Holds, &converted).output(json!(converted))
+}
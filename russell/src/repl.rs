@@ -0,0 +1,343 @@
+use std::{collections::HashMap, env, path::PathBuf};
+
+use anyhow::Context;
+use russell_engine::{
+    ASTNode, Assignments, Engine, EngineError, Symbol, Table, TableFormat, ValueStyle, to_cnf,
+    to_dnf, to_nnf,
+};
+use rustyline::{DefaultEditor, error::ReadlineError};
+
+/// What the session's input is called in rendered diagnostics
+const INPUT_NAME: &str = "<repl>";
+
+const HELP: &str = "\
+Enter a formula to make it the current one and see what it is.
+
+  let NAME = FORMULA   names a formula, to use in later ones
+  :set p=true q=false  gives variables values, plugged into every formula
+  :unset [p q]         takes values away again, or all of them
+  :table [FORMULA]     prints the truth table, with a column per subformula
+  :taut [FORMULA]      checks whether it's a tautology
+  :sat [FORMULA]       looks for an assignment making it true
+  :simp [FORMULA]      simplifies it, showing each law used
+  :cnf, :dnf, :nnf     converts it to a normal form
+  :min [FORMULA]       finds the smallest sum of products
+  :defs, :vars         lists the names and values set so far
+  :help, :quit
+
+Commands work on the current formula unless given one.";
+
+/// Runs `russell repl` until end of input or `:quit`, keeping history in
+/// `~/.russell_history`
+pub fn run() -> anyhow::Result<()> {
+    let mut editor = DefaultEditor::new().context("couldn't start the line editor")?;
+    let history = env::var_os("HOME").map(|home| PathBuf::from(home).join(".russell_history"));
+
+    if let Some(history) = &history {
+        // there's no history the first time round
+        let _ = editor.load_history(history);
+    }
+
+    println!("russell {} (:help for help)", env!("CARGO_PKG_VERSION"));
+
+    let mut session = Session::default();
+
+    loop {
+        let line = match editor.readline("russell> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(error) => return Err(error.into()),
+        };
+
+        let line = line.trim();
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let _ = editor.add_history_entry(line);
+
+        if matches!(line, ":quit" | ":q") {
+            break;
+        }
+
+        match session.handle(line) {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => println!("{}", output.trim_end()),
+            Err(error) => eprintln!("{}", error.trim_end()),
+        }
+    }
+
+    if let Some(history) = &history {
+        editor
+            .save_history(history)
+            .with_context(|| format!("couldn't save history to {}", history.display()))?;
+    }
+
+    Ok(())
+}
+
+/// What a REPL session remembers from line to line. Errors are returned
+/// rendered, ready to print.
+#[derive(Debug, Default)]
+pub struct Session {
+    engine: Engine,
+    definitions: HashMap<Symbol, ASTNode>,
+    assignments: Assignments,
+
+    /// The formula commands work on when they aren't given one
+    current: Option<ASTNode>,
+}
+
+impl Session {
+    /// Runs one line of input, returning what to print
+    pub fn handle(&mut self, line: &str) -> Result<String, String> {
+        let render = |error: EngineError| error.render(line, INPUT_NAME);
+
+        if let Some(definition) = line.strip_prefix("let ") {
+            return self.define(definition);
+        }
+
+        let Some(command) = line.strip_prefix(':') else {
+            let expr = self.formula(line)?;
+            return self.check(&expr).map_err(render);
+        };
+
+        let (command, arg) = command
+            .split_once(char::is_whitespace)
+            .map_or((command, ""), |(command, arg)| (command, arg.trim()));
+
+        match command {
+            "set" => self.set(arg),
+            "unset" => self.unset(arg),
+
+            "table" => {
+                let table = Table::builder(&self.formula(arg)?)
+                    .subformulas(true)
+                    .build()
+                    .map_err(render)?;
+
+                Ok(table.render(TableFormat::Unicode, ValueStyle::Letters))
+            }
+
+            "taut" => {
+                let expr = self.formula(arg)?;
+                self.check(&expr).map_err(render)
+            }
+
+            "sat" => {
+                let expr = self.formula(arg)?;
+
+                match self.engine.find_witness(&expr) {
+                    Ok(Some(witness)) if witness.is_empty() => Ok("satisfiable".to_string()),
+                    Ok(Some(witness)) => Ok(format!("satisfiable, witness: {witness}")),
+                    Ok(None) => Ok("unsatisfiable".to_string()),
+                    Err(error) => Err(render(error)),
+                }
+            }
+
+            "simp" => {
+                let expr = self.formula(arg)?;
+                Ok(self.engine.derive(&expr).to_text())
+            }
+
+            "cnf" => to_cnf(&self.formula(arg)?, Engine::MAX_NORMAL_FORM_TERMS)
+                .map(|cnf| cnf.to_ast().to_string())
+                .map_err(render),
+
+            "dnf" => to_dnf(&self.formula(arg)?, Engine::MAX_NORMAL_FORM_TERMS)
+                .map(|dnf| dnf.to_ast().to_string())
+                .map_err(render),
+
+            "nnf" => Ok(to_nnf(&self.formula(arg)?).to_string()),
+
+            "min" => {
+                let expr = self.formula(arg)?;
+
+                self.engine
+                    .minimize(&expr)
+                    .map(|min| min.to_string())
+                    .map_err(render)
+            }
+
+            "defs" => {
+                let mut definitions = self
+                    .definitions
+                    .iter()
+                    .map(|(name, expr)| format!("{name} = {expr}"))
+                    .collect::<Vec<_>>();
+                definitions.sort();
+
+                Ok(definitions.join("\n"))
+            }
+
+            "vars" => Ok(self.assignments.to_string()),
+            "help" => Ok(HELP.to_string()),
+
+            _ => Err(format!("unknown command `:{command}` (:help lists them)")),
+        }
+    }
+
+    /// `let NAME = FORMULA`, without the `let`
+    fn define(&mut self, definition: &str) -> Result<String, String> {
+        let Some((name, formula)) = definition.split_once('=') else {
+            return Err("expected `let NAME = FORMULA`".to_string());
+        };
+
+        let name = name.trim();
+        let is_identifier = name.starts_with(|c: char| c.is_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+
+        if !is_identifier || matches!(name, "true" | "false") {
+            return Err(format!("`{name}` can't be used as a name"));
+        }
+
+        // later definitions can't change what this one means
+        let expr = self.parse(formula.trim())?;
+
+        self.definitions.insert(Symbol::intern(name), expr.clone());
+        self.current = Some(expr);
+
+        Ok(String::new())
+    }
+
+    /// `p=true q=false`
+    fn set(&mut self, arg: &str) -> Result<String, String> {
+        let mut assignments = Vec::new();
+
+        for pair in arg.split_whitespace() {
+            let value = match pair.split_once('=') {
+                Some((name, "true")) if !name.is_empty() => (name, true),
+                Some((name, "false")) if !name.is_empty() => (name, false),
+                _ => return Err(format!("expected NAME=true or NAME=false, got `{pair}`")),
+            };
+
+            assignments.push(value);
+        }
+
+        for (name, value) in assignments {
+            self.assignments.insert(Symbol::intern(name), value);
+        }
+
+        Ok(String::new())
+    }
+
+    /// `p q`, or nothing to clear every assignment
+    fn unset(&mut self, arg: &str) -> Result<String, String> {
+        if arg.is_empty() {
+            self.assignments = Assignments::default();
+        }
+
+        for name in arg.split_whitespace() {
+            self.assignments.remove(Symbol::intern(name));
+        }
+
+        Ok(String::new())
+    }
+
+    /// Parses `input`, with definitions expanded
+    fn parse(&self, input: &str) -> Result<ASTNode, String> {
+        let expr = self
+            .engine
+            .parse(input)
+            .map_err(|errors| errors.render(input, INPUT_NAME))?;
+
+        Ok(expr.substitute(&self.definitions))
+    }
+
+    /// The formula a command should work on: `input` if there is one, which
+    /// becomes the current formula, or else the current one. Assigned
+    /// variables are replaced by their values.
+    fn formula(&mut self, input: &str) -> Result<ASTNode, String> {
+        if !input.is_empty() {
+            self.current = Some(self.parse(input)?);
+        }
+
+        let Some(current) = &self.current else {
+            return Err("there's no formula yet, enter one first".to_string());
+        };
+
+        let values = self
+            .assignments
+            .iter()
+            .map(|(var, value)| (var, ASTNode::Literal(value)))
+            .collect();
+
+        Ok(current.substitute(&values))
+    }
+
+    /// The value of `expr` if it has no variables left, or otherwise
+    /// whether it's a tautology
+    fn check(&self, expr: &ASTNode) -> Result<String, EngineError> {
+        let engine = &self.engine;
+
+        if engine.collect_variables(expr).is_empty() {
+            return engine
+                .eval(expr, &Assignments::default())
+                .map(|value| value.to_string());
+        }
+
+        let Some(counterexample) = engine.find_counterexample(expr)? else {
+            return Ok("tautology".to_string());
+        };
+
+        let kind = match engine.check_satisfiable(expr)? {
+            true => "contingent",
+            false => "contradiction",
+        };
+
+        Ok(format!("{kind}, counterexample: {counterexample}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use russell_engine::testing;
+
+    #[test]
+    fn test_definitions() {
+        let mut session = Session::default();
+
+        assert_eq!(session.handle("let dm = !(p && q)"), Ok(String::new()));
+        assert_eq!(session.handle("dm == !p || !q").as_deref(), Ok("tautology"));
+        assert_eq!(session.handle(":nnf dm").as_deref(), Ok("!p || !q"));
+
+        // the current formula is the last one entered
+        assert_eq!(session.handle(":cnf").as_deref(), Ok("!p || !q"));
+
+        assert!(session.handle("let true = p").is_err());
+        assert!(session.handle("p &&").unwrap_err().contains("<repl>"));
+    }
+
+    #[test]
+    fn test_assignments() {
+        let mut session = Session::default();
+
+        session.handle("p => q").unwrap();
+        assert_eq!(
+            session.handle(":taut").as_deref(),
+            Ok("contingent, counterexample: p = true, q = false")
+        );
+
+        session.handle(":set p=false").unwrap();
+        assert_eq!(session.handle(":taut").as_deref(), Ok("tautology"));
+
+        session.handle(":set p=true q=false").unwrap();
+        assert_eq!(session.handle("p => q").as_deref(), Ok("false"));
+
+        session.handle(":unset q").unwrap();
+        assert_eq!(session.handle(":vars").as_deref(), Ok("p = true"));
+
+        let wide = testing::joined(40, "&&");
+        session.handle(&format!("let wide = {wide}")).unwrap();
+        assert_eq!(
+            session.handle(":table wide"),
+            Err("error: expression has 40 variables, but at most 16 are supported\n".to_string())
+        );
+
+        assert!(session.handle(":set p=maybe").is_err());
+        assert!(session.handle(":frobnicate").is_err());
+    }
+}
@@ -0,0 +1,520 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use anyhow::{Context, bail};
+use russell_engine::{
+    ASTNode, Assignments, Engine, EngineError, Goal, Source, Symbol, line_column,
+};
+use serde_json::json;
+
+use crate::{Answer, Run, Status, check};
+
+/// What a test file says its formula should be, on a `# expect:` line
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Tautology,
+    Contingent,
+    Contradiction,
+    Satisfiable,
+    Unsatisfiable,
+}
+
+impl Kind {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tautology" => Some(Kind::Tautology),
+            "contingent" => Some(Kind::Contingent),
+            "contradiction" => Some(Kind::Contradiction),
+            "satisfiable" => Some(Kind::Satisfiable),
+            "unsatisfiable" => Some(Kind::Unsatisfiable),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expectation {
+    /// `# expect: tautology` and so on
+    Is(Kind),
+
+    /// `# expect-model: p=true q=false`, meaning some assignment making the
+    /// formula true gives the variables these values
+    Model(Vec<(Symbol, bool)>),
+}
+
+/// How one test file went
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Outcome {
+    /// Why the test failed, empty if it passed
+    failures: Vec<String>,
+
+    /// An assignment showing what went wrong, if there is one
+    counterexample: Option<Assignments>,
+
+    /// The formula, if the file has just the one goal
+    formula: Option<ASTNode>,
+}
+
+/// Runs `russell test`: checks every goal of each `.rsl` file in `paths`,
+/// looking into directories recursively, against the expectations in the
+/// comments of the file each goal is in. Files without any expect their
+/// goals to be tautologies, like the laws in `examples/`, and files without
+/// goals fail.
+pub fn run(paths: &[String], run: &mut Run) -> anyhow::Result<()> {
+    let files = collect(paths)?;
+    let engine = run.engine;
+    let suite_started = Instant::now();
+
+    let mut passed = Vec::new();
+    let mut failed = Vec::new();
+
+    if !run.json {
+        let plural = if files.len() == 1 { "" } else { "s" };
+        println!("\nrunning {} test{plural}", files.len());
+    }
+
+    for path in files {
+        let started = Instant::now();
+        let name = path.display().to_string();
+        let text = fs::read_to_string(&path).with_context(|| format!("couldn't read `{name}`"))?;
+        let source = Source { name, text };
+
+        let outcome = match test(engine, &source) {
+            Ok(tested) => tested,
+            Err(errors) => {
+                if !run.json {
+                    println!("test {} ... error", source.name);
+                }
+
+                for (source, error) in errors {
+                    run.fail(&source, None, error, started.elapsed());
+                }
+
+                failed.push((source.name, None));
+                continue;
+            }
+        };
+
+        let status = Status::from_bool(outcome.failures.is_empty());
+
+        if run.json {
+            let verdict = match status {
+                Status::Holds => "pass",
+                _ => "fail",
+            };
+            let answer = Answer::new(status, "")
+                .verdict(verdict)
+                .counterexample(outcome.counterexample)
+                .output(json!({ "failures": outcome.failures }));
+
+            run.answer(
+                &source.name,
+                outcome.formula.as_ref(),
+                answer,
+                started.elapsed(),
+            );
+            continue;
+        }
+
+        run.worst = run.worst.max(status);
+
+        match status {
+            Status::Holds => {
+                println!("test {} ... ok", source.name);
+                passed.push(source.name);
+            }
+
+            _ => {
+                println!("test {} ... FAILED", source.name);
+                failed.push((source.name, Some(outcome)));
+            }
+        }
+    }
+
+    if run.json {
+        return Ok(());
+    }
+
+    let reported = failed
+        .iter()
+        .filter_map(|(name, outcome)| Some((name, outcome.as_ref()?)))
+        .collect::<Vec<_>>();
+
+    if !reported.is_empty() {
+        println!("\nfailures:");
+
+        for (name, outcome) in reported {
+            println!("\n---- {name} ----");
+
+            for failure in &outcome.failures {
+                println!("{failure}");
+            }
+
+            if let Some(counterexample) = &outcome.counterexample {
+                println!("counterexample: {counterexample}");
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        println!("\nfailures:");
+
+        for (name, _) in &failed {
+            println!("    {name}");
+        }
+    }
+
+    let result = match failed.is_empty() {
+        true => "ok",
+        false => "FAILED",
+    };
+
+    println!(
+        "\ntest result: {result}. {} passed; {} failed; finished in {:.2}s\n",
+        passed.len(),
+        failed.len(),
+        suite_started.elapsed().as_secs_f64()
+    );
+
+    Ok(())
+}
+
+/// Every `.rsl` file in `paths`, sorted within each directory
+fn collect(paths: &[String]) -> anyhow::Result<Vec<PathBuf>> {
+    fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+        let mut entries = fs::read_dir(dir)
+            .with_context(|| format!("couldn't read `{}`", dir.display()))?
+            .map(|entry| Ok(entry?.path()))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        entries.sort();
+
+        for path in entries {
+            if path.is_dir() {
+                walk(&path, files)?;
+            } else if path.extension().is_some_and(|ext| ext == "rsl") {
+                files.push(path);
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+
+    for path in paths {
+        let path = Path::new(path);
+
+        if path.is_dir() {
+            walk(path, &mut files)?;
+        } else if path.exists() {
+            files.push(path.to_path_buf());
+        } else {
+            bail!("no such file or directory `{}`", path.display());
+        }
+    }
+
+    Ok(files)
+}
+
+/// Checks every goal in `source`, and in the files it includes, against the
+/// expectations in the comments of the file the goal is in. Errors come with
+/// the source they're in, which may be an include.
+fn test(engine: &Engine, source: &Source) -> Result<Outcome, Vec<(Source, EngineError)>> {
+    let mut outcome = Outcome::default();
+    let report = engine.check_document(source.clone(), check::load);
+
+    if !report.errors.is_empty() {
+        return Err(report
+            .errors
+            .into_iter()
+            .map(|(index, error)| (report.sources[index].clone(), error))
+            .collect());
+    }
+
+    // a file whose laws are all commented out mustn't pass
+    if report.goals.is_empty() {
+        outcome.failures.push("file has no goals".to_string());
+    }
+
+    let expectations = report
+        .sources
+        .iter()
+        .map(|source| expectations(engine, source, &mut outcome.failures))
+        .collect::<Vec<_>>();
+
+    for goal in &report.goals {
+        let goal_source = &report.sources[goal.source];
+
+        // say which goal went wrong when there are several
+        let location = match report.goals.len() {
+            1 => String::new(),
+            _ => {
+                let (line, column) = line_column(&goal_source.text, goal.span.start);
+                format!("{}:{line}:{column}: ", goal_source.name)
+            }
+        };
+
+        for expectation in &expectations[goal.source] {
+            check(engine, goal, expectation, &location, &mut outcome)
+                .map_err(|error| vec![(goal_source.clone(), error)])?;
+        }
+    }
+
+    if let [goal] = &report.goals[..] {
+        outcome.formula = Some(goal.formula.clone());
+    }
+
+    Ok(outcome)
+}
+
+/// Checks `goal` against `expectation`, which is about the assignments
+/// making its assumptions true: a tautology is true under all of them, and
+/// so follows from the assumptions, and a satisfiable goal under some
+fn check(
+    engine: &Engine,
+    goal: &Goal,
+    expectation: &Expectation,
+    location: &str,
+    outcome: &mut Outcome,
+) -> Result<(), EngineError> {
+    // the goal along with its assumptions, true where both are
+    let restricted = goal
+        .assumptions
+        .iter()
+        .chain([&goal.formula])
+        .cloned()
+        .reduce(|p, q| ASTNode::And(Box::new(p), Box::new(q)))
+        .expect("there's always the goal itself");
+
+    let kind = match expectation {
+        Expectation::Is(kind) => *kind,
+
+        Expectation::Model(values) => {
+            let bindings = values
+                .iter()
+                .map(|(var, value)| (*var, ASTNode::Literal(*value)))
+                .collect();
+
+            if engine.check_satisfiable(&restricted.substitute(&bindings))? {
+                return Ok(());
+            }
+
+            let values = values
+                .iter()
+                .map(|(var, value)| format!("{var} = {value}"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            outcome.failures.push(format!(
+                "{location}expected a model with {values}, but there isn't one"
+            ));
+
+            return Ok(());
+        }
+    };
+
+    let counterexample = goal.counterexample.clone();
+    let witness = engine.find_witness(&restricted)?;
+
+    let actual = match (&counterexample, &witness) {
+        (None, _) => "a tautology",
+        (Some(_), Some(_)) => "contingent",
+        (Some(_), None) => "a contradiction",
+    };
+
+    // the assignment to show when the expectation doesn't hold
+    let (holds, shown, expected) = match kind {
+        Kind::Tautology => (counterexample.is_none(), counterexample, "a tautology"),
+        Kind::Contradiction => (witness.is_none(), witness, "a contradiction"),
+        Kind::Contingent => (
+            counterexample.is_some() && witness.is_some(),
+            None,
+            "contingent",
+        ),
+        Kind::Satisfiable => (witness.is_some(), None, "satisfiable"),
+        Kind::Unsatisfiable => (witness.is_none(), witness, "unsatisfiable"),
+    };
+
+    if !holds {
+        outcome
+            .failures
+            .push(format!("{location}expected {expected}, but it's {actual}"));
+        outcome.counterexample = outcome.counterexample.take().or(shown);
+    }
+
+    Ok(())
+}
+
+/// The expectations in the comments of `source`, or that its goals are
+/// tautologies if there aren't any. Ones that can't be read are failures.
+fn expectations(engine: &Engine, source: &Source, failures: &mut Vec<String>) -> Vec<Expectation> {
+    // the source was checked without errors, so it parses
+    let comments = engine
+        .parse_document(&source.text)
+        .map(|document| document.comments)
+        .unwrap_or_default();
+
+    let mut expectations = Vec::new();
+
+    for comment in comments {
+        let (first_line, _) = line_column(&source.text, comment.span.start);
+
+        for (offset, line) in comment.text.lines().enumerate() {
+            match parse_directive(line) {
+                Ok(Some(expectation)) => expectations.push(expectation),
+                Ok(None) => {}
+                Err(reason) => failures.push(format!(
+                    "{}:{}: bad expectation: {reason}",
+                    source.name,
+                    first_line + offset
+                )),
+            }
+        }
+    }
+
+    if expectations.is_empty() {
+        expectations.push(Expectation::Is(Kind::Tautology));
+    }
+
+    expectations
+}
+
+/// Reads the expectation on a comment line, or [None] if it's an ordinary
+/// comment
+fn parse_directive(comment: &str) -> Result<Option<Expectation>, String> {
+    let Some((key, value)) = comment.split_once(':') else {
+        return Ok(None);
+    };
+
+    let value = value.trim();
+
+    match key.trim() {
+        "expect" => match Kind::from_name(value) {
+            Some(kind) => Ok(Some(Expectation::Is(kind))),
+            None => Err(format!(
+                "`{value}` isn't tautology, contingent, contradiction, satisfiable or unsatisfiable"
+            )),
+        },
+
+        "expect-model" => {
+            let values = value
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| match pair.split_once('=') {
+                    Some((name, "true")) if !name.is_empty() => Ok((Symbol::intern(name), true)),
+                    Some((name, "false")) if !name.is_empty() => Ok((Symbol::intern(name), false)),
+                    _ => Err(format!("expected NAME=true or NAME=false, got `{pair}`")),
+                })
+                .collect::<Result<_, _>>()?;
+
+            Ok(Some(Expectation::Model(values)))
+        }
+
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_text(text: &str) -> Outcome {
+        let source = Source {
+            name: "test.rsl".to_string(),
+            text: text.to_string(),
+        };
+
+        test(&Engine::default(), &source).unwrap()
+    }
+
+    #[test]
+    fn test_expectations() {
+        // no expectation means a tautology
+        assert!(test_text("p || !p").failures.is_empty());
+
+        let outcome = test_text("# expect: tautology\np => q");
+        assert_eq!(
+            outcome.failures,
+            ["expected a tautology, but it's contingent"]
+        );
+        assert!(outcome.counterexample.is_some());
+
+        assert!(
+            test_text("# expect: contingent\np => q")
+                .failures
+                .is_empty()
+        );
+        assert!(
+            test_text("p && !p // expect: contradiction")
+                .failures
+                .is_empty()
+        );
+
+        let outcome = test_text("# expect-model: p=true, q=false\np => q");
+        assert_eq!(
+            outcome.failures,
+            ["expected a model with p = true, q = false, but there isn't one"]
+        );
+
+        let outcome = test_text("# expect: valid\np");
+        assert_eq!(outcome.failures.len(), 2);
+    }
+
+    #[test]
+    fn test_documents() {
+        let modus_tollens = "let rule = p => q\nassume rule\nassume !q\nprove !p";
+        assert!(test_text(modus_tollens).failures.is_empty());
+
+        // expectations apply to every goal, under its assumptions
+        let outcome = test_text("# expect: satisfiable\nassume p\nprove q\nprove !p");
+        assert_eq!(
+            outcome.failures,
+            ["test.rsl:4:7: expected satisfiable, but it's a contradiction"]
+        );
+
+        let source = Source {
+            name: "test.rsl".to_string(),
+            text: "/* a law */\np &&\n".to_string(),
+        };
+        let Err(errors) = test(&Engine::default(), &source) else {
+            panic!("expected a parse error");
+        };
+        let [(_, EngineError::Parse(errors))] = &errors[..] else {
+            panic!("expected a parse error, got {errors:?}");
+        };
+        assert_eq!(errors.0[0].span.start, source.text.len());
+
+        // with its law commented out, a file has nothing to pass
+        let outcome = test_text("# expect: tautology\n# p || !p");
+        assert_eq!(outcome.failures, ["file has no goals"]);
+
+        // a line that's been commented out in a block isn't an expectation
+        let outcome = test_text("/*\n# expect: contradiction\n*/\np || !p");
+        assert!(outcome.failures.is_empty());
+    }
+
+    #[test]
+    fn test_expectations_stay_in_their_file() {
+        let dir = std::env::temp_dir().join(format!("russell-suite-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let main = dir.join("main.rsl");
+        fs::write(
+            &main,
+            "# expect: contradiction\ninclude \"law.rsl\"\np && !p",
+        )
+        .unwrap();
+        fs::write(dir.join("law.rsl"), "p || !p").unwrap();
+
+        let source = Source {
+            name: main.display().to_string(),
+            text: fs::read_to_string(&main).unwrap(),
+        };
+        let outcome = test(&Engine::default(), &source).unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(outcome.failures.is_empty(), "{:?}", outcome.failures);
+    }
+}
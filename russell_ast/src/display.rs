@@ -0,0 +1,216 @@
+use std::fmt;
+
+use crate::{ASTNode, Symbol};
+
+/// How to write constants, operators and variables when printing an
+/// expression. Operators include any spacing around them.
+#[derive(Debug, Clone, Copy)]
+pub struct Notation {
+    pub literals: [&'static str; 2],
+    pub not: &'static str,
+    pub and: &'static str,
+    pub or: &'static str,
+    pub implies: &'static str,
+    pub equivalent: &'static str,
+    pub open: &'static str,
+    pub close: &'static str,
+    pub variable: fn(&mut fmt::Formatter<'_>, Symbol) -> fmt::Result,
+}
+
+impl Notation {
+    /// The syntax the parser reads
+    pub const RUSSELL: Notation = Notation {
+        literals: ["false", "true"],
+        not: "!",
+        and: " && ",
+        or: " || ",
+        implies: " => ",
+        equivalent: " == ",
+        open: "(",
+        close: ")",
+        variable: |f, var| f.write_str(var.as_str()),
+    };
+
+    /// The usual logical symbols
+    pub const UNICODE: Notation = Notation {
+        literals: ["⊥", "⊤"],
+        not: "¬",
+        and: " ∧ ",
+        or: " ∨ ",
+        implies: " → ",
+        equivalent: " ↔ ",
+        ..Notation::RUSSELL
+    };
+
+    /// LaTeX math mode, with multi-letter names in italics
+    pub const LATEX: Notation = Notation {
+        literals: ["\\bot", "\\top"],
+        not: "\\neg ",
+        and: " \\land ",
+        or: " \\lor ",
+        implies: " \\Rightarrow ",
+        equivalent: " \\Leftrightarrow ",
+        open: "(",
+        close: ")",
+        variable: |f, var| match var.as_str() {
+            name if name.chars().count() == 1 => f.write_str(name),
+            name => write!(f, "\\mathit{{{}}}", name.replace('_', "\\_")),
+        },
+    };
+}
+
+impl ASTNode {
+    /// How tightly the node's operator binds, higher binding tighter. Matches
+    /// the parser: `!` binds tightest, then `&&`, `||`, `=>` and `==`.
+    fn precedence(&self) -> u8 {
+        match self {
+            ASTNode::Equivalent(..) => 1,
+            ASTNode::Implies(..) => 2,
+            ASTNode::Or(..) => 3,
+            ASTNode::And(..) => 4,
+            ASTNode::Not(_) => 5,
+            ASTNode::Variable(_) | ASTNode::Literal(_) | ASTNode::Paren(_) => 6,
+        }
+    }
+
+    /// Prints the expression in another notation
+    pub fn display<'a>(&'a self, notation: &'a Notation) -> impl fmt::Display + 'a {
+        Displayed {
+            node: self,
+            notation,
+        }
+    }
+}
+
+struct Displayed<'a> {
+    node: &'a ASTNode,
+    notation: &'a Notation,
+}
+
+impl Displayed<'_> {
+    /// Writes `node`, in parentheses if `parenthesize` is set
+    fn child(&self, f: &mut fmt::Formatter<'_>, node: &ASTNode, parenthesize: bool) -> fmt::Result {
+        let node = node.display(self.notation);
+
+        if parenthesize {
+            write!(f, "{}{node}{}", self.notation.open, self.notation.close)
+        } else {
+            write!(f, "{node}")
+        }
+    }
+}
+
+// adds only the parentheses needed to parse back to the same tree.
+// Parentheses that were in the source are kept, since they're nodes of their
+// own.
+impl fmt::Display for Displayed<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let notation = self.notation;
+        let precedence = self.node.precedence();
+
+        let (op, p, q) = match self.node {
+            ASTNode::Variable(symbol) => return (notation.variable)(f, *symbol),
+            ASTNode::Literal(value) => return f.write_str(notation.literals[*value as usize]),
+            ASTNode::Paren(inner) => return self.child(f, inner, true),
+            ASTNode::Not(inner) => {
+                f.write_str(notation.not)?;
+                return self.child(f, inner, inner.precedence() < precedence);
+            }
+
+            ASTNode::And(p, q) => (notation.and, p, q),
+            ASTNode::Or(p, q) => (notation.or, p, q),
+            ASTNode::Implies(p, q) => (notation.implies, p, q),
+            ASTNode::Equivalent(p, q) => (notation.equivalent, p, q),
+        };
+
+        // `=>` groups to the right and everything else to the left, so an
+        // operand on the other side needs parentheses even at the same
+        // precedence
+        let right_associative = matches!(self.node, ASTNode::Implies(..));
+
+        self.child(
+            f,
+            p,
+            p.precedence() < precedence || (right_associative && p.precedence() == precedence),
+        )?;
+        f.write_str(op)?;
+        self.child(
+            f,
+            q,
+            q.precedence() < precedence || (!right_associative && q.precedence() == precedence),
+        )
+    }
+}
+
+impl fmt::Display for ASTNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.display(&Notation::RUSSELL))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ASTNode, Notation, Symbol};
+
+    fn var(name: &str) -> Box<ASTNode> {
+        Box::new(ASTNode::Variable(Symbol::intern(name)))
+    }
+
+    #[test]
+    fn test_minimal_parentheses() {
+        // (p || q) && !r
+        let expr = ASTNode::And(
+            Box::new(ASTNode::Or(var("p"), var("q"))),
+            Box::new(ASTNode::Not(var("r"))),
+        );
+        assert_eq!(expr.to_string(), "(p || q) && !r");
+
+        // p && q || r needs none
+        let expr = ASTNode::Or(Box::new(ASTNode::And(var("p"), var("q"))), var("r"));
+        assert_eq!(expr.to_string(), "p && q || r");
+
+        // !(p && q)
+        let expr = ASTNode::Not(Box::new(ASTNode::And(var("p"), var("q"))));
+        assert_eq!(expr.to_string(), "!(p && q)");
+    }
+
+    #[test]
+    fn test_associativity() {
+        let expr = ASTNode::Implies(var("p"), Box::new(ASTNode::Implies(var("q"), var("r"))));
+        assert_eq!(expr.to_string(), "p => q => r");
+
+        let expr = ASTNode::Implies(Box::new(ASTNode::Implies(var("p"), var("q"))), var("r"));
+        assert_eq!(expr.to_string(), "(p => q) => r");
+
+        let expr = ASTNode::And(var("p"), Box::new(ASTNode::And(var("q"), var("r"))));
+        assert_eq!(expr.to_string(), "p && (q && r)");
+    }
+
+    #[test]
+    fn test_source_parentheses_kept() {
+        let expr = ASTNode::Paren(Box::new(ASTNode::Not(Box::new(ASTNode::Literal(true)))));
+
+        assert_eq!(expr.to_string(), "(!true)");
+    }
+
+    #[test]
+    fn test_notations() {
+        // !(door_open && p) => false
+        let expr = ASTNode::Implies(
+            Box::new(ASTNode::Not(Box::new(ASTNode::And(
+                var("door_open"),
+                var("p"),
+            )))),
+            Box::new(ASTNode::Literal(false)),
+        );
+
+        assert_eq!(
+            expr.display(&Notation::UNICODE).to_string(),
+            "¬(door_open ∧ p) → ⊥"
+        );
+        assert_eq!(
+            expr.display(&Notation::LATEX).to_string(),
+            "\\neg (\\mathit{door\\_open} \\land p) \\Rightarrow \\bot"
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{ASTNode, Span, SpanTable, Symbol};
+
+/// A formula within a [Document], with the spans of its nodes relative to
+/// the whole document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Formula {
+    pub expr: ASTNode,
+    pub spans: SpanTable,
+}
+
+impl Formula {
+    /// The span of the whole formula
+    pub fn span(&self) -> Option<Span> {
+        self.spans.root()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StatementKind {
+    /// `let name = formula`, after which `name` stands for the formula
+    Let { name: Symbol, formula: Formula },
+
+    /// `assume formula`, a premise for every goal after it
+    Assume(Formula),
+
+    /// `prove formula`, a goal that should follow from the assumptions
+    Prove(Formula),
+
+    /// `include "path"`, which brings in another document's statements
+    Include { path: String },
+
+    /// A formula on its own, which is a goal just like `prove`. A document
+    /// holding only this is the same as a lone expression.
+    Formula(Formula),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Statement {
+    pub kind: StatementKind,
+    pub span: Span,
+}
+
+/// A `#`, `//` or `/* */` comment in a [Document]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comment {
+    /// What's inside the comment, without the `#`, `//`, `/*` or `*/`
+    pub text: String,
+    pub span: Span,
+}
+
+/// A parsed `.rsl` file: a series of statements, each on a line of its own
+/// or separated by semicolons, with `#` or `//` line comments and `/* */`
+/// block comments anywhere whitespace can go:
+///
+/// ```text
+/// # the premises of modus tollens
+/// let rule = p => q
+/// assume rule
+/// assume !q
+///
+/// prove !p
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Document {
+    pub statements: Vec<Statement>,
+
+    /// Every comment, in order, for tools that read directives from them
+    pub comments: Vec<Comment>,
+}
@@ -1,6 +1,18 @@
-#[derive(Debug, PartialEq, Eq, Clone)]
+use std::collections::HashMap;
+
+mod display;
+mod document;
+mod span;
+mod symbol;
+
+pub use display::Notation;
+pub use document::{Comment, Document, Formula, Statement, StatementKind};
+pub use span::{NodeId, Span, SpanTable};
+pub use symbol::Symbol;
+
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub enum ASTNode {
-    Variable(char),
+    Variable(Symbol),
     Literal(bool),
 
     Not(Box<ASTNode>),
@@ -11,3 +23,38 @@ pub enum ASTNode {
 
     Paren(Box<ASTNode>),
 }
+
+impl ASTNode {
+    /// The same expression without any [ASTNode::Paren] nodes, which only
+    /// record where the source had parentheses
+    pub fn strip_parens(&self) -> ASTNode {
+        let strip = |node: &ASTNode| Box::new(node.strip_parens());
+
+        match self {
+            ASTNode::Variable(_) | ASTNode::Literal(_) => self.clone(),
+            ASTNode::Paren(inner) => inner.strip_parens(),
+            ASTNode::Not(inner) => ASTNode::Not(strip(inner)),
+            ASTNode::And(p, q) => ASTNode::And(strip(p), strip(q)),
+            ASTNode::Or(p, q) => ASTNode::Or(strip(p), strip(q)),
+            ASTNode::Implies(p, q) => ASTNode::Implies(strip(p), strip(q)),
+            ASTNode::Equivalent(p, q) => ASTNode::Equivalent(strip(p), strip(q)),
+        }
+    }
+
+    /// The same expression with every variable bound in `bindings` replaced
+    /// by what it's bound to. Variables that aren't bound are left alone.
+    pub fn substitute(&self, bindings: &HashMap<Symbol, ASTNode>) -> ASTNode {
+        let sub = |node: &ASTNode| Box::new(node.substitute(bindings));
+
+        match self {
+            ASTNode::Variable(var) => bindings.get(var).unwrap_or(self).clone(),
+            ASTNode::Literal(_) => self.clone(),
+            ASTNode::Not(inner) => ASTNode::Not(sub(inner)),
+            ASTNode::And(p, q) => ASTNode::And(sub(p), sub(q)),
+            ASTNode::Or(p, q) => ASTNode::Or(sub(p), sub(q)),
+            ASTNode::Implies(p, q) => ASTNode::Implies(sub(p), sub(q)),
+            ASTNode::Equivalent(p, q) => ASTNode::Equivalent(sub(p), sub(q)),
+            ASTNode::Paren(inner) => ASTNode::Paren(sub(inner)),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use std::ops::Range;
+
+use crate::ASTNode;
+
+/// A byte range into the source text a node was parsed from
+pub type Span = Range<usize>;
+
+/// Identifies a node within a tree by its position in a preorder traversal,
+/// so the root is always `NodeId(0)`. See [ASTNode::preorder].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(pub usize);
+
+/// Side table holding the [Span] of every node in a tree, indexed by
+/// [NodeId]. Keeping spans out of [ASTNode] means trees built by hand (or by
+/// the engine) don't have to make positions up.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SpanTable(Vec<Span>);
+
+impl SpanTable {
+    pub fn get(&self, id: NodeId) -> Option<Span> {
+        self.0.get(id.0).cloned()
+    }
+
+    /// The span of the whole tree
+    pub fn root(&self) -> Option<Span> {
+        self.get(NodeId(0))
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, Span)> + '_ {
+        self.0
+            .iter()
+            .enumerate()
+            .map(|(id, span)| (NodeId(id), span.clone()))
+    }
+}
+
+impl From<Vec<Span>> for SpanTable {
+    fn from(spans: Vec<Span>) -> Self {
+        SpanTable(spans)
+    }
+}
+
+impl ASTNode {
+    /// The direct children of this node, left to right
+    pub fn children(&self) -> Vec<&ASTNode> {
+        match self {
+            ASTNode::Variable(_) | ASTNode::Literal(_) => vec![],
+
+            ASTNode::Not(node) | ASTNode::Paren(node) => vec![node],
+
+            ASTNode::And(p, q)
+            | ASTNode::Or(p, q)
+            | ASTNode::Implies(p, q)
+            | ASTNode::Equivalent(p, q) => vec![p, q],
+        }
+    }
+
+    /// Every node in the tree in preorder, which is the order [NodeId]s are
+    /// assigned in
+    pub fn preorder(&self) -> Vec<&ASTNode> {
+        let mut nodes = Vec::new();
+        let mut stack = vec![self];
+
+        while let Some(node) = stack.pop() {
+            nodes.push(node);
+
+            // push in reverse so the leftmost child is visited first
+            stack.extend(node.children().into_iter().rev());
+        }
+
+        nodes
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&ASTNode> {
+        self.preorder().get(id.0).copied()
+    }
+
+    /// Finds the id of every node matching `predicate`
+    pub fn find_all(&self, predicate: impl Fn(&ASTNode) -> bool) -> Vec<NodeId> {
+        self.preorder()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, node)| predicate(node))
+            .map(|(id, _)| NodeId(id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preorder() {
+        // !(p && q) || r
+        let p = ASTNode::Variable("p".into());
+        let q = ASTNode::Variable("q".into());
+        let r = ASTNode::Variable("r".into());
+
+        let and = ASTNode::And(Box::new(p.clone()), Box::new(q.clone()));
+        let not = ASTNode::Not(Box::new(and.clone()));
+        let or = ASTNode::Or(Box::new(not.clone()), Box::new(r.clone()));
+
+        assert_eq!(or.preorder(), vec![&or, &not, &and, &p, &q, &r]);
+        assert_eq!(or.node(NodeId(3)), Some(&p));
+        assert_eq!(or.node(NodeId(6)), None);
+
+        assert_eq!(
+            or.find_all(|node| matches!(node, ASTNode::Variable(_))),
+            vec![NodeId(3), NodeId(4), NodeId(5)]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt,
+    sync::{LazyLock, Mutex, OnceLock},
+};
+
+/// An interned identifier, such as the name of a variable.
+///
+/// Symbols are just indices into a global table, so copying, comparing and
+/// hashing them is as cheap as doing so with an integer. The name can be
+/// recovered with [Symbol::as_str].
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Symbol(u32);
+
+/// The names, by ordinal. Slots are only ever filled in once, so names can
+/// be read without taking the interner's lock: bucket `n` holds the next
+/// `FIRST_BUCKET << n` names, and is allocated when the first of them is
+/// interned.
+static NAMES: [OnceLock<Box<[OnceLock<&'static str>]>>; BUCKETS] =
+    [const { OnceLock::new() }; BUCKETS];
+
+const FIRST_BUCKET: usize = 32;
+const BUCKETS: usize = 28;
+
+/// Maps names to symbols, which only interning needs
+static INTERNER: LazyLock<Mutex<HashMap<&'static str, Symbol>>> = LazyLock::new(Mutex::default);
+
+/// The bucket holding the name with `ordinal`, its size, and the name's
+/// index within it
+fn locate(ordinal: usize) -> (usize, usize, usize) {
+    let position = ordinal + FIRST_BUCKET;
+    let bucket = (position.ilog2() - FIRST_BUCKET.ilog2()) as usize;
+    let size = FIRST_BUCKET << bucket;
+
+    (bucket, size, position - size)
+}
+
+/// The name with `ordinal`, if it's been interned
+fn name(ordinal: usize) -> Option<&'static str> {
+    let (bucket, _, index) = locate(ordinal);
+
+    NAMES.get(bucket)?.get()?[index].get().copied()
+}
+
+impl Symbol {
+    /// Returns the symbol for `name`, adding it to the table if it hasn't
+    /// been seen before
+    pub fn intern(name: &str) -> Symbol {
+        let mut ids = INTERNER.lock().unwrap();
+
+        if let Some(symbol) = ids.get(name) {
+            return *symbol;
+        }
+
+        // names are never removed from the table, so leaking them is fine
+        let name: &'static str = Box::leak(name.to_owned().into_boxed_str());
+        let symbol = Symbol(ids.len() as u32);
+
+        let (bucket, size, index) = locate(symbol.ordinal());
+        let names = NAMES[bucket].get_or_init(|| (0..size).map(|_| OnceLock::new()).collect());
+
+        names[index].set(name).expect("each slot is filled in once");
+        ids.insert(name, symbol);
+
+        symbol
+    }
+
+    pub fn as_str(self) -> &'static str {
+        name(self.ordinal()).expect("symbols are only made by interning")
+    }
+
+    /// Looks a symbol up by its [ordinal](Symbol::ordinal), returning [None]
+    /// if nothing has been interned at that position yet
+    pub fn from_ordinal(ordinal: usize) -> Option<Symbol> {
+        let ordinal = u32::try_from(ordinal).ok()?;
+
+        name(ordinal as usize).map(|_| Symbol(ordinal))
+    }
+
+    /// The position of this symbol in the table. Ordinals are dense, starting
+    /// from zero, in the order the symbols were first interned.
+    pub fn ordinal(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(name: &str) -> Self {
+        Symbol::intern(name)
+    }
+}
+
+// symbols are ordered by name rather than by ordinal, so that sorting a list
+// of variables gives the same result regardless of interning order
+impl Ord for Symbol {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self == other {
+            return Ordering::Equal;
+        }
+
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Symbol({:?})", self.as_str())
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interning_is_idempotent() {
+        let a = Symbol::intern("door_open");
+        let b = Symbol::intern("door_open");
+
+        assert_eq!(a, b);
+        assert_eq!(a.as_str(), "door_open");
+    }
+
+    #[test]
+    fn test_distinct_names() {
+        assert_ne!(Symbol::intern("alarm_armed"), Symbol::intern("alarm"));
+    }
+
+    #[test]
+    fn test_from_ordinal() {
+        let symbol = Symbol::intern("ordinal_lookup");
+
+        assert_eq!(Symbol::from_ordinal(symbol.ordinal()), Some(symbol));
+        assert_eq!(Symbol::from_ordinal(u32::MAX as usize), None);
+    }
+
+    #[test]
+    fn test_names_across_buckets() {
+        let symbols = (0..500)
+            .map(|i| Symbol::intern(&format!("bucket{i}")))
+            .collect::<Vec<_>>();
+
+        for (i, symbol) in symbols.iter().enumerate() {
+            assert_eq!(symbol.as_str(), format!("bucket{i}"));
+        }
+
+        assert_eq!(Symbol::from_ordinal(usize::MAX), None);
+    }
+
+    #[test]
+    fn test_ordered_by_name() {
+        // intern in reverse order so ordinals disagree with names
+        let z = Symbol::intern("zz_ordering");
+        let a = Symbol::intern("aa_ordering");
+
+        assert!(a < z);
+    }
+}
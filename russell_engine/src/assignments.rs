@@ -0,0 +1,254 @@
+use std::fmt;
+
+use russell_ast::Symbol;
+
+/// Truth values for a set of variables.
+///
+/// Values are stored as a bitset indexed by [Symbol::ordinal], alongside a
+/// mask of which variables are assigned at all, so lookups and updates are
+/// O(1) and cloning is a couple of small copies. Trailing words that are all
+/// zero are trimmed, so two equal sets of assignments always have the same
+/// representation, and the derived `Hash` and `Eq` agree with each other.
+#[derive(Default, Clone, PartialEq, Eq, Hash)]
+pub struct Assignments {
+    /// Bit `n` is the value of the variable with ordinal `n`. Always a
+    /// subset of `mask`.
+    values: Vec<u64>,
+
+    /// Bit `n` is set if the variable with ordinal `n` has a value
+    mask: Vec<u64>,
+}
+
+/// Splits an ordinal into the index of its word and the bit within it
+fn locate(symbol: Symbol) -> (usize, u64) {
+    let ordinal = symbol.ordinal();
+
+    (ordinal / 64, 1 << (ordinal % 64))
+}
+
+impl Assignments {
+    pub fn get(&self, symbol: Symbol) -> Option<bool> {
+        let (word, bit) = locate(symbol);
+
+        if self.mask.get(word)? & bit == 0 {
+            return None;
+        }
+
+        Some(self.values[word] & bit != 0)
+    }
+
+    /// Assigns `value` to `symbol`, returning its previous value
+    pub fn insert(&mut self, symbol: Symbol, value: bool) -> Option<bool> {
+        let previous = self.get(symbol);
+        let (word, bit) = locate(symbol);
+
+        if word >= self.mask.len() {
+            self.mask.resize(word + 1, 0);
+            self.values.resize(word + 1, 0);
+        }
+
+        self.mask[word] |= bit;
+
+        if value {
+            self.values[word] |= bit;
+        } else {
+            self.values[word] &= !bit;
+        }
+
+        previous
+    }
+
+    /// Unassigns `symbol`, returning its previous value
+    pub fn remove(&mut self, symbol: Symbol) -> Option<bool> {
+        let previous = self.get(symbol)?;
+        let (word, bit) = locate(symbol);
+
+        self.mask[word] &= !bit;
+        self.values[word] &= !bit;
+
+        self.trim();
+
+        Some(previous)
+    }
+
+    pub fn contains(&self, symbol: Symbol) -> bool {
+        self.get(symbol).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.mask
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mask.is_empty()
+    }
+
+    /// Every assigned variable and its value, in ordinal order
+    pub fn iter(&self) -> impl Iterator<Item = (Symbol, bool)> + '_ {
+        self.mask
+            .iter()
+            .zip(&self.values)
+            .enumerate()
+            .flat_map(|(index, (&mask, &values))| {
+                BitIter(mask).map(move |bit| {
+                    let symbol = Symbol::from_ordinal(index * 64 + bit)
+                        .expect("assigned symbols must have been interned");
+
+                    (symbol, values & (1 << bit) != 0)
+                })
+            })
+    }
+
+    /// Drops trailing words with nothing assigned, which keeps the
+    /// representation canonical
+    fn trim(&mut self) {
+        while self.mask.last() == Some(&0) {
+            self.mask.pop();
+            self.values.pop();
+        }
+    }
+}
+
+/// Iterates over the positions of the set bits of a word
+struct BitIter(u64);
+
+impl Iterator for BitIter {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let bit = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+
+        Some(bit)
+    }
+}
+
+// entries are sorted by name, since ordinal order depends on which names
+// happened to be interned first
+impl fmt::Display for Assignments {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut entries = self.iter().collect::<Vec<_>>();
+        entries.sort();
+
+        let entries = entries
+            .into_iter()
+            .map(|(symbol, value)| format!("{symbol} = {value}"))
+            .collect::<Vec<_>>();
+
+        f.write_str(&entries.join(", "))
+    }
+}
+
+impl fmt::Debug for Assignments {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.iter().map(|(symbol, value)| (symbol.as_str(), value)))
+            .finish()
+    }
+}
+
+impl FromIterator<(Symbol, bool)> for Assignments {
+    fn from_iter<T: IntoIterator<Item = (Symbol, bool)>>(iter: T) -> Self {
+        let mut assignments = Assignments::default();
+
+        for (symbol, value) in iter {
+            assignments.insert(symbol, value);
+        }
+
+        assignments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashSet,
+        hash::{DefaultHasher, Hash, Hasher},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_insert_get_remove() {
+        let p = Symbol::intern("p");
+        let q = Symbol::intern("q");
+
+        let mut assignments = Assignments::default();
+
+        assert_eq!(assignments.insert(p, true), None);
+        assert_eq!(assignments.insert(q, false), None);
+        assert_eq!(assignments.insert(p, false), Some(true));
+
+        assert_eq!(assignments.get(p), Some(false));
+        assert_eq!(assignments.get(q), Some(false));
+        assert_eq!(assignments.get(Symbol::intern("unassigned")), None);
+        assert_eq!(assignments.len(), 2);
+
+        assert_eq!(assignments.remove(p), Some(false));
+        assert_eq!(assignments.remove(p), None);
+        assert_eq!(assignments.len(), 1);
+    }
+
+    #[test]
+    fn test_equality_ignores_history() {
+        let symbols = (0..200)
+            .map(|i| Symbol::intern(&format!("history{i}")))
+            .collect::<Vec<_>>();
+
+        let mut a = Assignments::default();
+        a.insert(symbols[0], true);
+
+        // growing and shrinking again must give the same representation
+        let mut b = Assignments::default();
+        b.insert(symbols[199], false);
+        b.insert(symbols[0], true);
+        b.remove(symbols[199]);
+
+        assert_eq!(a, b);
+        assert_eq!(HashSet::from([a.clone()]).len(), 1);
+        assert!(HashSet::from([a]).contains(&b));
+    }
+
+    #[test]
+    fn test_assignments_hashing() {
+        let letters = ('a'..='z')
+            .map(|c| Symbol::intern(&c.to_string()))
+            .collect::<Vec<_>>();
+
+        let mut hashes: HashSet<u64> = HashSet::default();
+        let mut assignments_seen: HashSet<Assignments> = HashSet::default();
+
+        for (i, a) in letters.iter().enumerate() {
+            for (j, b) in letters.iter().enumerate().skip(i + 1) {
+                for c in letters.iter().skip(j + 1) {
+                    for values in 0..8 {
+                        // populate truth table
+                        let assignments: Assignments = [
+                            (*a, values & 1 != 0),
+                            (*b, values & 2 != 0),
+                            (*c, values & 4 != 0),
+                        ]
+                        .into_iter()
+                        .collect();
+
+                        // hash truth table
+                        let mut hasher = DefaultHasher::default();
+                        assignments.hash(&mut hasher);
+
+                        // ensure that the hash is unique (since the truth
+                        // table is unique too)
+                        assert!(hashes.insert(hasher.finish()));
+                        assert!(assignments_seen.insert(assignments));
+                    }
+                }
+            }
+        }
+    }
+}
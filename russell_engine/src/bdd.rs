@@ -0,0 +1,523 @@
+use std::collections::HashMap;
+
+use russell_ast::{ASTNode, Symbol};
+
+use crate::{Assignments, Instruction, Program};
+
+/// A boolean function, as the root of a BDD owned by a [BddManager].
+///
+/// Nodes are shared and never duplicated, so two references are equal
+/// exactly when they represent the same function. Only compare references
+/// from the same manager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BddRef(u32);
+
+impl BddRef {
+    pub const FALSE: BddRef = BddRef(0);
+    pub const TRUE: BddRef = BddRef(1);
+
+    pub fn constant(value: bool) -> BddRef {
+        if value { BddRef::TRUE } else { BddRef::FALSE }
+    }
+
+    pub fn is_constant(self) -> bool {
+        self.0 <= 1
+    }
+
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// The binary operators [BddManager::apply] supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOp {
+    And,
+    Or,
+    Implies,
+    Equivalent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Node {
+    /// The position of the node's variable in the order. The terminals
+    /// come after every variable.
+    level: u32,
+    low: BddRef,
+    high: BddRef,
+}
+
+const TERMINAL_LEVEL: u32 = u32::MAX;
+
+/// Builds and operates on reduced ordered binary decision diagrams.
+///
+/// Every node lives in a unique table keyed by its variable and children,
+/// so equal subgraphs are always the same node and each function has exactly
+/// one representation for a given variable order. That makes checking
+/// whether a BDD is a tautology, or whether two are equivalent, a comparison
+/// of two [BddRef]s. Every operation is built on [BddManager::ite], whose
+/// results are cached.
+///
+/// Variables are ordered by when they're first used, which for
+/// [BddManager::from_ast] is the order they first appear in the expression.
+#[derive(Debug, Clone)]
+pub struct BddManager {
+    nodes: Vec<Node>,
+    unique: HashMap<Node, BddRef>,
+    ite_cache: HashMap<(BddRef, BddRef, BddRef), BddRef>,
+
+    /// The variable at each level
+    variables: Vec<Symbol>,
+    levels: HashMap<Symbol, u32>,
+}
+
+impl Default for BddManager {
+    fn default() -> Self {
+        let terminal = |value| Node {
+            level: TERMINAL_LEVEL,
+            low: BddRef::constant(value),
+            high: BddRef::constant(value),
+        };
+
+        BddManager {
+            nodes: vec![terminal(false), terminal(true)],
+            unique: HashMap::new(),
+            ite_cache: HashMap::new(),
+            variables: Vec::new(),
+            levels: HashMap::new(),
+        }
+    }
+}
+
+impl BddManager {
+    pub fn new() -> Self {
+        BddManager::default()
+    }
+
+    /// The variables in order, from the root down
+    pub fn variables(&self) -> &[Symbol] {
+        &self.variables
+    }
+
+    /// How many nodes have been created, including the two terminals
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// The function that's true exactly when `var` is, adding `var` to the
+    /// end of the order if it's new
+    pub fn var(&mut self, var: Symbol) -> BddRef {
+        let level = self.level_of(var);
+
+        self.node(level, BddRef::FALSE, BddRef::TRUE)
+    }
+
+    /// Builds the BDD of `expr`
+    pub fn from_ast(&mut self, expr: &ASTNode) -> BddRef {
+        self.from_program(&Program::compile(expr))
+    }
+
+    /// Builds the BDD of a compiled expression. Programs are already flat,
+    /// so this needs no recursion however deeply the expression nests.
+    pub fn from_program(&mut self, program: &Program) -> BddRef {
+        let vars = program
+            .variables()
+            .iter()
+            .map(|var| self.var(*var))
+            .collect::<Vec<_>>();
+
+        let mut stack = Vec::new();
+
+        for instruction in program.instructions() {
+            let result = match *instruction {
+                Instruction::Load(slot) => vars[slot as usize],
+                Instruction::Const(value) => BddRef::constant(value),
+                Instruction::Not => {
+                    let f = stack.pop().unwrap();
+                    self.not(f)
+                }
+                binary => {
+                    let g = stack.pop().unwrap();
+                    let f = stack.pop().unwrap();
+
+                    let op = match binary {
+                        Instruction::And => BinaryOp::And,
+                        Instruction::Or => BinaryOp::Or,
+                        Instruction::Implies => BinaryOp::Implies,
+                        Instruction::Equivalent => BinaryOp::Equivalent,
+                        _ => unreachable!(),
+                    };
+
+                    self.apply(op, f, g)
+                }
+            };
+
+            stack.push(result);
+        }
+
+        stack.pop().unwrap()
+    }
+
+    /// If `f` then `g` else `h`
+    pub fn ite(&mut self, f: BddRef, g: BddRef, h: BddRef) -> BddRef {
+        match (f, g, h) {
+            (BddRef::TRUE, _, _) => return g,
+            (BddRef::FALSE, _, _) => return h,
+            _ if g == h => return g,
+            (_, BddRef::TRUE, BddRef::FALSE) => return f,
+            _ => {}
+        }
+
+        if let Some(result) = self.ite_cache.get(&(f, g, h)) {
+            return *result;
+        }
+
+        // split on whichever variable comes first
+        let level = [f, g, h]
+            .into_iter()
+            .map(|r| self.nodes[r.index()].level)
+            .min()
+            .unwrap();
+
+        let (f0, f1) = self.cofactors(f, level);
+        let (g0, g1) = self.cofactors(g, level);
+        let (h0, h1) = self.cofactors(h, level);
+
+        let low = self.ite(f0, g0, h0);
+        let high = self.ite(f1, g1, h1);
+        let result = self.node(level, low, high);
+
+        self.ite_cache.insert((f, g, h), result);
+
+        result
+    }
+
+    pub fn not(&mut self, f: BddRef) -> BddRef {
+        self.ite(f, BddRef::FALSE, BddRef::TRUE)
+    }
+
+    pub fn apply(&mut self, op: BinaryOp, f: BddRef, g: BddRef) -> BddRef {
+        match op {
+            BinaryOp::And => self.ite(f, g, BddRef::FALSE),
+            BinaryOp::Or => self.ite(f, BddRef::TRUE, g),
+            BinaryOp::Implies => self.ite(f, g, BddRef::TRUE),
+            BinaryOp::Equivalent => {
+                let not_g = self.not(g);
+                self.ite(f, g, not_g)
+            }
+        }
+    }
+
+    /// `f` with `var` fixed to `value`
+    pub fn restrict(&mut self, f: BddRef, var: Symbol, value: bool) -> BddRef {
+        match self.levels.get(&var) {
+            Some(&level) => self.restrict_level(f, level, value, &mut HashMap::new()),
+            None => f,
+        }
+    }
+
+    /// `f` with `g` substituted for `var`
+    pub fn compose(&mut self, f: BddRef, var: Symbol, g: BddRef) -> BddRef {
+        let high = self.restrict(f, var, true);
+        let low = self.restrict(f, var, false);
+
+        self.ite(g, high, low)
+    }
+
+    /// Whether `f` can be true for some value of `var`
+    pub fn exists(&mut self, f: BddRef, var: Symbol) -> BddRef {
+        let high = self.restrict(f, var, true);
+        let low = self.restrict(f, var, false);
+
+        self.apply(BinaryOp::Or, low, high)
+    }
+
+    /// Whether `f` is true for both values of `var`
+    pub fn forall(&mut self, f: BddRef, var: Symbol) -> BddRef {
+        let high = self.restrict(f, var, true);
+        let low = self.restrict(f, var, false);
+
+        self.apply(BinaryOp::And, low, high)
+    }
+
+    /// How many assignments of every variable in the manager make `f` true.
+    /// Takes time linear in the size of `f`. Returns [None] if the count
+    /// doesn't fit in a `u128`.
+    pub fn count_models(&self, f: BddRef) -> Option<u128> {
+        // variables above the root can be anything
+        shl(self.count_below(f, &mut HashMap::new())?, self.level(f))
+    }
+
+    /// An assignment of every variable in the manager that makes `f` true,
+    /// if there is one. Variables that don't matter are made false.
+    pub fn any_model(&self, f: BddRef) -> Option<Assignments> {
+        if f == BddRef::FALSE {
+            return None;
+        }
+
+        let mut assignments: Assignments = self.variables.iter().map(|var| (*var, false)).collect();
+        let mut current = f;
+
+        while !current.is_constant() {
+            let node = self.nodes[current.index()];
+            let var = self.variables[node.level as usize];
+
+            // in a reduced BDD every node other than false has a path to true,
+            // so we only have to avoid stepping straight onto false
+            if node.low == BddRef::FALSE {
+                assignments.insert(var, true);
+                current = node.high;
+            } else {
+                current = node.low;
+            }
+        }
+
+        Some(assignments)
+    }
+
+    /// How many nodes are reachable from `f`, including terminals
+    pub fn size(&self, f: BddRef) -> usize {
+        let mut seen = vec![false; self.nodes.len()];
+        let mut stack = vec![f];
+        let mut size = 0;
+
+        while let Some(r) = stack.pop() {
+            if seen[r.index()] {
+                continue;
+            }
+
+            seen[r.index()] = true;
+            size += 1;
+
+            if !r.is_constant() {
+                let node = self.nodes[r.index()];
+                stack.push(node.low);
+                stack.push(node.high);
+            }
+        }
+
+        size
+    }
+
+    /// The level of `f`'s variable, or the number of variables for terminals
+    fn level(&self, f: BddRef) -> u32 {
+        self.nodes[f.index()].level.min(self.variables.len() as u32)
+    }
+
+    /// The number of assignments of the variables from `f`'s level down that
+    /// reach the true terminal
+    fn count_below(&self, f: BddRef, memo: &mut HashMap<BddRef, u128>) -> Option<u128> {
+        if f.is_constant() {
+            return Some((f == BddRef::TRUE) as u128);
+        }
+
+        if let Some(result) = memo.get(&f) {
+            return Some(*result);
+        }
+
+        let node = self.nodes[f.index()];
+        let mut total = 0u128;
+
+        for child in [node.low, node.high] {
+            // variables skipped between the node and its child can be
+            // anything
+            let skipped = self.level(child) - node.level - 1;
+            let models = shl(self.count_below(child, memo)?, skipped)?;
+
+            total = total.checked_add(models)?;
+        }
+
+        memo.insert(f, total);
+
+        Some(total)
+    }
+
+    fn level_of(&mut self, var: Symbol) -> u32 {
+        *self.levels.entry(var).or_insert_with(|| {
+            self.variables.push(var);
+            self.variables.len() as u32 - 1
+        })
+    }
+
+    /// Finds or creates the node for `level` with the given children,
+    /// skipping it entirely when both children are the same
+    fn node(&mut self, level: u32, low: BddRef, high: BddRef) -> BddRef {
+        if low == high {
+            return low;
+        }
+
+        let node = Node { level, low, high };
+
+        *self.unique.entry(node).or_insert_with(|| {
+            self.nodes.push(node);
+            BddRef(self.nodes.len() as u32 - 1)
+        })
+    }
+
+    /// `f` with the variable at `level` set to false and to true
+    fn cofactors(&self, f: BddRef, level: u32) -> (BddRef, BddRef) {
+        let node = self.nodes[f.index()];
+
+        if node.level == level {
+            (node.low, node.high)
+        } else {
+            (f, f)
+        }
+    }
+
+    fn restrict_level(
+        &mut self,
+        f: BddRef,
+        level: u32,
+        value: bool,
+        memo: &mut HashMap<BddRef, BddRef>,
+    ) -> BddRef {
+        let node = self.nodes[f.index()];
+
+        // everything below the variable's level is unaffected
+        if node.level > level {
+            return f;
+        }
+
+        if node.level == level {
+            return if value { node.high } else { node.low };
+        }
+
+        if let Some(result) = memo.get(&f) {
+            return *result;
+        }
+
+        let low = self.restrict_level(node.low, level, value, memo);
+        let high = self.restrict_level(node.high, level, value, memo);
+        let result = self.node(node.level, low, high);
+
+        memo.insert(f, result);
+
+        result
+    }
+}
+
+/// Multiplies `value` by `2^shift`, unless that overflows
+fn shl(value: u128, shift: u32) -> Option<u128> {
+    match value {
+        0 => Some(0),
+        _ if value.leading_zeros() >= shift => Some(value << shift),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Engine, testing};
+
+    fn build(manager: &mut BddManager, input: &str) -> BddRef {
+        manager.from_ast(&Engine::default().parse(input).unwrap())
+    }
+
+    #[test]
+    fn test_canonical() {
+        let mut manager = BddManager::new();
+
+        let f = build(&mut manager, "!(p && q)");
+        let g = build(&mut manager, "!p || !q");
+        assert_eq!(f, g);
+
+        let h = build(&mut manager, "p || q");
+        assert_ne!(f, h);
+
+        assert_eq!(build(&mut manager, "(p => q) == (!q => !p)"), BddRef::TRUE);
+        assert_eq!(build(&mut manager, "p && !p"), BddRef::FALSE);
+    }
+
+    #[test]
+    fn test_laws_are_tautologies() {
+        let engine = Engine::default();
+
+        for entry in std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/../examples")).unwrap()
+        {
+            let input = std::fs::read_to_string(entry.unwrap().path()).unwrap();
+            let expr = engine.parse(&input).unwrap();
+
+            assert_eq!(BddManager::new().from_ast(&expr), BddRef::TRUE, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_count_models() {
+        let mut manager = BddManager::new();
+
+        // the order is p, r, q, so q is skipped on the first path
+        let f = build(&mut manager, "p && r || !p && q && r || (p == q) && false");
+        assert_eq!(manager.count_models(f), Some(3));
+
+        assert_eq!(manager.count_models(BddRef::TRUE), Some(8));
+        assert_eq!(manager.count_models(BddRef::FALSE), Some(0));
+
+        // 2^129 - 1 doesn't fit
+        let input = testing::joined(129, "||");
+        let mut manager = BddManager::new();
+        let f = build(&mut manager, &input);
+
+        assert_eq!(manager.count_models(f), None);
+
+        let f = manager.not(f);
+        assert_eq!(manager.count_models(f), Some(1));
+    }
+
+    #[test]
+    fn test_restrict_and_compose() {
+        let mut manager = BddManager::new();
+
+        let f = build(&mut manager, "p && q || r");
+        let p = Symbol::intern("p");
+
+        let restricted = manager.restrict(f, p, false);
+        assert_eq!(restricted, build(&mut manager, "r"));
+
+        let restricted = manager.restrict(f, p, true);
+        assert_eq!(restricted, build(&mut manager, "q || r"));
+
+        // substituting !q for p means p && q can never hold
+        let not_q = build(&mut manager, "!q");
+        assert_eq!(manager.compose(f, p, not_q), build(&mut manager, "r"));
+    }
+
+    #[test]
+    fn test_quantifiers() {
+        let mut manager = BddManager::new();
+
+        let f = build(&mut manager, "p && q");
+        let p = Symbol::intern("p");
+
+        assert_eq!(manager.exists(f, p), build(&mut manager, "q"));
+        assert_eq!(manager.forall(f, p), BddRef::FALSE);
+
+        let g = build(&mut manager, "p || q");
+        assert_eq!(manager.forall(g, p), build(&mut manager, "q"));
+        assert_eq!(manager.exists(g, p), BddRef::TRUE);
+    }
+
+    #[test]
+    fn test_any_model() {
+        let engine = Engine::default();
+        let mut manager = BddManager::new();
+
+        let expr = engine.parse("!p && (q == r) && r").unwrap();
+        let f = manager.from_ast(&expr);
+        let model = manager.any_model(f).unwrap();
+
+        assert_eq!(model.to_string(), "p = false, q = true, r = true");
+        assert_eq!(manager.any_model(BddRef::FALSE), None);
+    }
+
+    #[test]
+    fn test_size() {
+        let mut manager = BddManager::new();
+
+        // a chain of n nodes plus the two terminals
+        let f = build(&mut manager, "a && b && c && d");
+        assert_eq!(manager.size(f), 6);
+        assert_eq!(manager.size(BddRef::TRUE), 1);
+    }
+}
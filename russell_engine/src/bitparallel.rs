@@ -0,0 +1,249 @@
+use russell_ast::Symbol;
+
+use crate::{Assignments, bytecode::Program};
+
+/// The number of truth table rows evaluated at once
+pub const BLOCK_ROWS: u64 = u64::BITS as u64;
+
+/// The values of the first six variables across a block of 64 rows. Bit `i`
+/// of `PATTERNS[k]` is bit `k` of `i`, so together they count from 0 to 63.
+const PATTERNS: [u64; 6] = [
+    0xAAAA_AAAA_AAAA_AAAA,
+    0xCCCC_CCCC_CCCC_CCCC,
+    0xF0F0_F0F0_F0F0_F0F0,
+    0xFF00_FF00_FF00_FF00,
+    0xFFFF_0000_FFFF_0000,
+    0xFFFF_FFFF_0000_0000,
+];
+
+/// Evaluates an expression over 64 rows of its truth table at a time.
+///
+/// Rows are numbered in [binary order](crate::Order::Binary), and block `b`
+/// holds rows `64 * b` to `64 * b + 63`. Every variable becomes a 64 bit word
+/// holding its value in each of those rows, so the formula can be evaluated
+/// with a handful of bitwise operations per node instead of once per row.
+/// The formula itself is run as a compiled [Program].
+#[derive(Debug, Clone)]
+pub struct BlockEvaluator {
+    program: Program,
+
+    /// The current block's word for each slot of the program
+    words: Vec<u64>,
+    stack: Vec<u64>,
+}
+
+impl BlockEvaluator {
+    /// `program` should have at most [crate::Engine::MAX_VARIABLES] variables
+    pub fn new(program: Program) -> Self {
+        BlockEvaluator {
+            words: vec![0; program.variables().len()],
+            stack: Vec::new(),
+            program,
+        }
+    }
+
+    /// The variables of the table, with the first one changing fastest
+    pub fn variables(&self) -> &[Symbol] {
+        self.program.variables()
+    }
+
+    /// How many rows the truth table has
+    pub fn rows(&self) -> u64 {
+        1 << self.variables().len()
+    }
+
+    /// How many blocks it takes to cover every row
+    pub fn blocks(&self) -> u64 {
+        self.rows().div_ceil(BLOCK_ROWS)
+    }
+
+    /// Which bits of a block's result are actual rows. Only tables with fewer
+    /// than 64 rows have any bits that aren't.
+    pub fn valid_mask(&self) -> u64 {
+        match self.rows() {
+            rows if rows >= BLOCK_ROWS => u64::MAX,
+            rows => (1 << rows) - 1,
+        }
+    }
+
+    /// Evaluates every row of `block`, returning a word where bit `i` is the
+    /// result in row `64 * block + i`. Bits outside the
+    /// [valid mask](Self::valid_mask) are meaningless.
+    pub fn eval(&mut self, block: u64) -> u64 {
+        for (pos, word) in self.words.iter_mut().enumerate() {
+            *word = match pos {
+                // these change from row to row within the block
+                0..6 => PATTERNS[pos],
+
+                // these are the same for every row, and come from the bits of
+                // the block number
+                _ if (block >> (pos - 6)) & 1 == 1 => u64::MAX,
+                _ => 0,
+            };
+        }
+
+        self.program.run(&self.words, &mut self.stack)
+    }
+
+    /// The assignments of row `row` of the truth table
+    pub fn assignments(&self, row: u64) -> Assignments {
+        self.variables()
+            .iter()
+            .enumerate()
+            .map(|(pos, var)| (*var, (row >> pos) & 1 == 1))
+            .collect()
+    }
+
+    /// Finds the first row where the result is `value`
+    pub fn find_row(&mut self, value: bool) -> Option<u64> {
+        let valid = self.valid_mask();
+
+        for block in 0..self.blocks() {
+            let result = self.eval(block);
+            let hits = if value { result } else { !result } & valid;
+
+            if hits != 0 {
+                return Some(block * BLOCK_ROWS + hits.trailing_zeros() as u64);
+            }
+        }
+
+        None
+    }
+
+    /// Counts the rows where the result is `value`
+    pub fn count_rows(&mut self, value: bool) -> u64 {
+        let valid = self.valid_mask();
+
+        (0..self.blocks())
+            .map(|block| {
+                let result = self.eval(block);
+                let hits = if value { result } else { !result } & valid;
+
+                hits.count_ones() as u64
+            })
+            .sum()
+    }
+}
+
+/// Iterates over the rows of a truth table in binary order, along with the
+/// value of the expression in each. Results are computed a block at a time.
+#[derive(Debug, Clone)]
+pub struct TruthTableIter {
+    evaluator: BlockEvaluator,
+    row: u64,
+
+    /// The results of the block `row` is in
+    results: u64,
+}
+
+impl TruthTableIter {
+    pub fn new(evaluator: BlockEvaluator) -> Self {
+        TruthTableIter {
+            evaluator,
+            row: 0,
+            results: 0,
+        }
+    }
+
+    pub fn variables(&self) -> &[Symbol] {
+        self.evaluator.variables()
+    }
+}
+
+impl Iterator for TruthTableIter {
+    type Item = (Assignments, bool);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.row >= self.evaluator.rows() {
+            return None;
+        }
+
+        if self.row.is_multiple_of(BLOCK_ROWS) {
+            self.results = self.evaluator.eval(self.row / BLOCK_ROWS);
+        }
+
+        let assignments = self.evaluator.assignments(self.row);
+        let result = (self.results >> (self.row % BLOCK_ROWS)) & 1 == 1;
+
+        self.row += 1;
+
+        Some((assignments, result))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = usize::try_from(self.evaluator.rows() - self.row).ok();
+
+        (remaining.unwrap_or(usize::MAX), remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Engine, Order, testing::FORMULAS};
+
+    #[test]
+    fn test_matches_scalar_eval() {
+        let engine = Engine::default();
+
+        for formula in FORMULAS {
+            let expr = engine.parse(formula).unwrap();
+            let variables = engine.collect_variables(&expr);
+
+            let expected = engine
+                .enumerate_assignments(variables.clone(), Order::Binary)
+                .unwrap()
+                .map(|assignments| {
+                    let result = engine.eval(&expr, &assignments).unwrap();
+                    (assignments, result)
+                })
+                .collect::<Vec<_>>();
+
+            let actual = TruthTableIter::new(BlockEvaluator::new(Program::compile(&expr)))
+                .collect::<Vec<_>>();
+
+            assert_eq!(actual, expected, "{formula}");
+        }
+    }
+
+    #[test]
+    fn test_find_row() {
+        let engine = Engine::default();
+
+        // only true in the very last row, which is in the last block
+        let expr = engine
+            .parse("a && b && c && d && e && f && g && h")
+            .unwrap();
+        let mut evaluator = BlockEvaluator::new(Program::compile(&expr));
+
+        assert_eq!(evaluator.blocks(), 4);
+        assert_eq!(evaluator.find_row(true), Some(255));
+        assert_eq!(evaluator.find_row(false), Some(0));
+    }
+
+    #[test]
+    fn test_count_rows() {
+        let engine = Engine::default();
+
+        // true in 3 of every 4 rows
+        let expr = engine
+            .parse("a => b || c && d && e && f && g && h")
+            .unwrap();
+        let mut evaluator = BlockEvaluator::new(Program::compile(&expr));
+
+        assert_eq!(evaluator.count_rows(true), 192 + 1);
+        assert_eq!(evaluator.count_rows(false), 64 - 1);
+    }
+
+    #[test]
+    fn test_small_tables_ignore_padding() {
+        let engine = Engine::default();
+
+        // a tautology with a 4 row table, so 60 bits of the block are padding
+        let expr = engine.parse("p || !p || q").unwrap();
+        let mut evaluator = BlockEvaluator::new(Program::compile(&expr));
+
+        assert_eq!(evaluator.valid_mask(), 0b1111);
+        assert_eq!(evaluator.find_row(false), None);
+    }
+}
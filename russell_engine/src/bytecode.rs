@@ -0,0 +1,329 @@
+use std::{
+    collections::HashMap,
+    ops::{BitAnd, BitOr, BitXor, Not},
+};
+
+use russell_ast::{ASTNode, Symbol};
+
+use crate::{Assignments, EngineError, Result};
+
+/// A single step of a [Program]. Operands are popped off the stack and the
+/// result is pushed back on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    /// Pushes the value of the variable in the given slot
+    Load(u32),
+    Const(bool),
+
+    Not,
+    And,
+    Or,
+    Implies,
+    Equivalent,
+}
+
+/// Something a [Program] can be evaluated over: either a single `bool`, or
+/// a `u64` holding 64 independent rows, one per bit.
+pub trait Word:
+    Copy + Not<Output = Self> + BitAnd<Output = Self> + BitOr<Output = Self> + BitXor<Output = Self>
+{
+    /// A word with every row set to `value`
+    fn splat(value: bool) -> Self;
+}
+
+impl Word for bool {
+    fn splat(value: bool) -> Self {
+        value
+    }
+}
+
+impl Word for u64 {
+    fn splat(value: bool) -> Self {
+        if value { u64::MAX } else { 0 }
+    }
+}
+
+/// An expression compiled to a flat, stack based instruction sequence.
+///
+/// Variables are resolved to slot indices at compile time and parentheses
+/// disappear, so evaluating is a single loop over the instructions with no
+/// recursion, lookups or error checks. Compile once and evaluate many times.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    instructions: Vec<Instruction>,
+
+    /// The variable in each slot
+    variables: Vec<Symbol>,
+
+    /// How deep the stack gets while evaluating
+    max_stack: usize,
+}
+
+impl Program {
+    /// Compiles `expr`, giving its variables slots in the order they first
+    /// appear
+    pub fn compile(expr: &ASTNode) -> Program {
+        let mut compiler = Compiler {
+            new_slots: true,
+            ..Compiler::default()
+        };
+
+        compiler.compile(expr);
+        compiler.finish()
+    }
+
+    /// Compiles `expr` with `variables` in slots `0..variables.len()`, in
+    /// that order. Fails if `expr` uses a variable not in the list.
+    pub fn compile_with(expr: &ASTNode, variables: &[Symbol]) -> Result<Program> {
+        let mut compiler = Compiler::default();
+
+        for var in variables {
+            compiler.slot(*var);
+        }
+
+        compiler.compile(expr);
+
+        match compiler.unbound {
+            Some(name) => Err(EngineError::UnboundVariable { name, span: None }),
+            None => Ok(compiler.finish()),
+        }
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// The variable in each slot
+    pub fn variables(&self) -> &[Symbol] {
+        &self.variables
+    }
+
+    /// Evaluates the program with `slots[i]` as the value of the `i`th
+    /// variable. `stack` is scratch space, which can be reused across calls
+    /// to avoid allocating.
+    ///
+    /// Panics if there are fewer slots than variables.
+    pub fn run<W: Word>(&self, slots: &[W], stack: &mut Vec<W>) -> W {
+        assert!(slots.len() >= self.variables.len(), "not enough slots");
+
+        stack.clear();
+        stack.reserve(self.max_stack);
+
+        for instruction in &self.instructions {
+            let value = match *instruction {
+                Instruction::Load(slot) => slots[slot as usize],
+                Instruction::Const(value) => W::splat(value),
+
+                Instruction::Not => !stack.pop().unwrap(),
+
+                binary => {
+                    let q = stack.pop().unwrap();
+                    let p = stack.pop().unwrap();
+
+                    match binary {
+                        Instruction::And => p & q,
+                        Instruction::Or => p | q,
+                        Instruction::Implies => !p | q,
+                        Instruction::Equivalent => !(p ^ q),
+                        _ => unreachable!(),
+                    }
+                }
+            };
+
+            stack.push(value);
+        }
+
+        stack.pop().unwrap()
+    }
+
+    /// Evaluates the program against named assignments
+    pub fn eval(&self, assignments: &Assignments) -> Result<bool> {
+        let slots = self
+            .variables
+            .iter()
+            .map(|var| {
+                assignments.get(*var).ok_or(EngineError::UnboundVariable {
+                    name: *var,
+                    span: None,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(self.run(&slots, &mut Vec::new()))
+    }
+}
+
+#[derive(Default)]
+struct Compiler {
+    instructions: Vec<Instruction>,
+    variables: Vec<Symbol>,
+    slots: HashMap<Symbol, u32>,
+
+    depth: usize,
+    max_stack: usize,
+
+    /// Whether variables without a slot get one, or are reported as unbound
+    new_slots: bool,
+
+    /// The first variable we had no slot for, when new slots aren't allowed
+    unbound: Option<Symbol>,
+}
+
+impl Compiler {
+    fn slot(&mut self, var: Symbol) -> u32 {
+        *self.slots.entry(var).or_insert_with(|| {
+            self.variables.push(var);
+            (self.variables.len() - 1) as u32
+        })
+    }
+
+    fn emit(&mut self, instruction: Instruction) {
+        // loads push a value, unary operators keep the depth the same and
+        // binary ones pop two values and push one
+        match instruction {
+            Instruction::Load(_) | Instruction::Const(_) => self.depth += 1,
+            Instruction::Not => {}
+            _ => self.depth -= 1,
+        }
+
+        self.max_stack = self.max_stack.max(self.depth);
+        self.instructions.push(instruction);
+    }
+
+    fn compile(&mut self, expr: &ASTNode) {
+        match expr {
+            ASTNode::Variable(var) => {
+                if !self.new_slots && !self.slots.contains_key(var) {
+                    self.unbound.get_or_insert(*var);
+                }
+
+                let slot = self.slot(*var);
+                self.emit(Instruction::Load(slot));
+            }
+            ASTNode::Literal(value) => self.emit(Instruction::Const(*value)),
+            ASTNode::Not(node) => {
+                self.compile(node);
+                self.emit(Instruction::Not);
+            }
+            ASTNode::And(p, q) => self.compile_binary(Instruction::And, p, q),
+            ASTNode::Or(p, q) => self.compile_binary(Instruction::Or, p, q),
+            ASTNode::Implies(p, q) => self.compile_binary(Instruction::Implies, p, q),
+            ASTNode::Equivalent(p, q) => self.compile_binary(Instruction::Equivalent, p, q),
+
+            // parentheses only matter to the parser
+            ASTNode::Paren(inner) => self.compile(inner),
+        }
+    }
+
+    fn compile_binary(&mut self, op: Instruction, p: &ASTNode, q: &ASTNode) {
+        self.compile(p);
+        self.compile(q);
+        self.emit(op);
+    }
+
+    fn finish(self) -> Program {
+        Program {
+            instructions: self.instructions,
+            variables: self.variables,
+            max_stack: self.max_stack,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Engine, Order};
+
+    #[test]
+    fn test_compile() {
+        let engine = Engine::default();
+        let program = Program::compile(&engine.parse("!(p && q) || p").unwrap());
+
+        assert_eq!(
+            program.instructions(),
+            &[
+                Instruction::Load(0),
+                Instruction::Load(1),
+                Instruction::And,
+                Instruction::Not,
+                Instruction::Load(0),
+                Instruction::Or,
+            ]
+        );
+        assert_eq!(
+            program.variables(),
+            &[Symbol::intern("p"), Symbol::intern("q")]
+        );
+        assert_eq!(program.max_stack, 2);
+    }
+
+    #[test]
+    fn test_compile_with() {
+        let engine = Engine::default();
+        let expr = engine.parse("p => q").unwrap();
+
+        let q = Symbol::intern("q");
+        let p = Symbol::intern("p");
+
+        let program = Program::compile_with(&expr, &[q, p]).unwrap();
+        assert_eq!(
+            program.instructions(),
+            &[
+                Instruction::Load(1),
+                Instruction::Load(0),
+                Instruction::Implies
+            ]
+        );
+
+        assert_eq!(
+            Program::compile_with(&expr, &[p]),
+            Err(EngineError::UnboundVariable {
+                name: q,
+                span: None
+            })
+        );
+    }
+
+    #[test]
+    fn test_matches_tree_eval() {
+        let engine = Engine::default();
+
+        for formula in [
+            "true",
+            "!!p == p",
+            "p || (q && r) == (p || q) && (p || r)",
+            "(a => b) => c == !(d || a) && (true => b)",
+        ] {
+            let expr = engine.parse(formula).unwrap();
+            let program = Program::compile(&expr);
+
+            for assignments in engine
+                .enumerate_assignments(program.variables().to_vec(), Order::Gray)
+                .unwrap()
+            {
+                assert_eq!(
+                    program.eval(&assignments).unwrap(),
+                    engine.eval(&expr, &assignments).unwrap(),
+                    "{formula} with {assignments}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_unbound_variable() {
+        let engine = Engine::default();
+        let program = Program::compile(&engine.parse("p && q").unwrap());
+
+        let assignments: Assignments = [(Symbol::intern("p"), true)].into_iter().collect();
+
+        assert_eq!(
+            program.eval(&assignments),
+            Err(EngineError::UnboundVariable {
+                name: Symbol::intern("q"),
+                span: None,
+            })
+        );
+    }
+}
@@ -0,0 +1,143 @@
+use russell_ast::Symbol;
+
+use crate::Literal;
+
+/// A conjunction of literals over numbered variables. Bit `i` of `mask` is
+/// set when the cube mentions variable `i`, in which case bit `i` of `bits`
+/// is the value it requires. A cube covers the truth table rows agreeing
+/// with it on every variable it mentions, so one leaving out `k` variables
+/// covers `2^k` rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Cube {
+    pub mask: u64,
+    pub bits: u64,
+}
+
+impl Cube {
+    /// The cube mentioning no variables, which covers every row
+    pub const UNIVERSE: Cube = Cube { mask: 0, bits: 0 };
+
+    /// The cube covering just `row` of a table over `variables` variables
+    pub fn row(row: u64, variables: usize) -> Cube {
+        let mask = match variables {
+            64.. => u64::MAX,
+            n => (1 << n) - 1,
+        };
+
+        Cube {
+            mask,
+            bits: row & mask,
+        }
+    }
+
+    pub fn covers(&self, row: u64) -> bool {
+        row & self.mask == self.bits
+    }
+
+    /// Whether every row `other` covers is covered by this cube too
+    pub fn contains(&self, other: &Cube) -> bool {
+        self.mask & !other.mask == 0 && other.bits & self.mask == self.bits
+    }
+
+    /// Whether some row is covered by both cubes
+    pub fn intersects(&self, other: &Cube) -> bool {
+        (self.bits ^ other.bits) & self.mask & other.mask == 0
+    }
+
+    /// The smallest cube containing both
+    pub fn supercube(&self, other: &Cube) -> Cube {
+        let mask = self.mask & other.mask & !(self.bits ^ other.bits);
+
+        Cube {
+            mask,
+            bits: self.bits & mask,
+        }
+    }
+
+    /// The cube restricted to the rows `by` covers, with the variables `by`
+    /// mentions left out, or [None] if they don't intersect
+    pub fn cofactor(&self, by: &Cube) -> Option<Cube> {
+        self.intersects(by).then_some(Cube {
+            mask: self.mask & !by.mask,
+            bits: self.bits & !by.mask,
+        })
+    }
+
+    pub fn literal_count(&self) -> u32 {
+        self.mask.count_ones()
+    }
+
+    /// The rows covered in a table over `variables` variables, in
+    /// increasing order
+    pub fn rows(&self, variables: usize) -> impl Iterator<Item = u64> + use<> {
+        let free = !self.mask & Cube::row(u64::MAX, variables).mask;
+        let bits = self.bits;
+
+        // counts through the subsets of `free` in increasing order
+        let mut next = Some(0u64);
+
+        std::iter::from_fn(move || {
+            let subset = next?;
+            next = (subset != free).then(|| subset.wrapping_sub(free) & free);
+
+            Some(bits | subset)
+        })
+    }
+
+    /// The cube's literals, with variable `i` called `variables[i]`
+    pub fn literals(&self, variables: &[Symbol]) -> Vec<Literal> {
+        variables
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| (self.mask >> i) & 1 == 1)
+            .map(|(i, var)| Literal {
+                var: *var,
+                positive: (self.bits >> i) & 1 == 1,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows() {
+        // x1 && !x3 over four variables
+        let cube = Cube {
+            mask: 0b1010,
+            bits: 0b0010,
+        };
+
+        assert_eq!(cube.rows(4).collect::<Vec<_>>(), [2, 3, 6, 7]);
+        assert!(cube.rows(4).all(|row| cube.covers(row)));
+        assert_eq!(Cube::UNIVERSE.rows(2).collect::<Vec<_>>(), [0, 1, 2, 3]);
+        assert_eq!(Cube::row(5, 3).rows(3).collect::<Vec<_>>(), [5]);
+
+        assert!(cube.contains(&Cube::row(6, 4)));
+        assert!(!cube.contains(&Cube::row(8, 4)));
+        assert!(Cube::UNIVERSE.contains(&cube));
+
+        let other = Cube {
+            mask: 0b0011,
+            bits: 0b0010,
+        };
+        assert!(cube.intersects(&other));
+        assert_eq!(
+            cube.supercube(&Cube::row(0b1110, 4)),
+            Cube {
+                mask: 0b0010,
+                bits: 0b0010
+            }
+        );
+        assert_eq!(
+            cube.cofactor(&other),
+            Some(Cube {
+                mask: 0b1000,
+                bits: 0
+            })
+        );
+        assert_eq!(cube.cofactor(&Cube::row(0, 4)), None);
+    }
+}
@@ -0,0 +1,307 @@
+use std::fmt;
+
+use russell_ast::ASTNode;
+
+use crate::{Assignments, BddManager, BlockEvaluator, Cnf, Engine, EngineError, Program, Result};
+
+#[cfg(feature = "parallel")]
+use crate::parallel;
+
+/// A procedure for deciding properties of expressions, which an [Engine] is
+/// configured with. Everything the engine decides comes down to finding an
+/// assignment giving an expression a particular value: a tautology is an
+/// expression that can't be false, a contradiction one that can't be true.
+pub trait Decider: fmt::Debug + Send + Sync {
+    /// A short name for the procedure, for diagnostics
+    fn name(&self) -> &'static str;
+
+    /// Finds an assignment under which `expr` is `value`, or [None] if
+    /// there isn't one
+    fn find_assignment(&self, expr: &ASTNode, value: bool) -> Result<Option<Assignments>>;
+
+    /// Counts the assignments under which `expr` is true. Procedures that
+    /// can't count any better than enumerating every assignment leave this
+    /// to [TruthTable].
+    fn count_models(&self, expr: &ASTNode) -> Result<u128> {
+        TruthTable.count_models(expr)
+    }
+}
+
+/// Decides by evaluating every row of the truth table, 64 rows at a time.
+/// Fast for a handful of variables, but limited to
+/// [Engine::MAX_VARIABLES] of them.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TruthTable;
+
+impl TruthTable {
+    /// Compiles `expr`, provided its truth table isn't too big to enumerate
+    pub fn evaluator(expr: &ASTNode) -> Result<BlockEvaluator> {
+        Self::evaluator_for(Program::compile(expr))
+    }
+
+    fn evaluator_for(program: Program) -> Result<BlockEvaluator> {
+        let count = program.variables().len();
+
+        if count > Engine::MAX_VARIABLES {
+            return Err(EngineError::TooManyVariables {
+                count,
+                limit: Engine::MAX_VARIABLES,
+            });
+        }
+
+        Ok(BlockEvaluator::new(program))
+    }
+}
+
+impl Decider for TruthTable {
+    fn name(&self) -> &'static str {
+        "truth-table"
+    }
+
+    fn find_assignment(&self, expr: &ASTNode, value: bool) -> Result<Option<Assignments>> {
+        let mut evaluator = Self::evaluator(expr)?;
+
+        // rows are checked 64 at a time, see BlockEvaluator
+        #[cfg(feature = "parallel")]
+        let row = if evaluator.blocks() >= parallel::MIN_PARALLEL_BLOCKS {
+            parallel::find_row(&evaluator, value)
+        } else {
+            evaluator.find_row(value)
+        };
+
+        #[cfg(not(feature = "parallel"))]
+        let row = evaluator.find_row(value);
+
+        Ok(row.map(|row| evaluator.assignments(row)))
+    }
+
+    fn count_models(&self, expr: &ASTNode) -> Result<u128> {
+        let mut evaluator = Self::evaluator(expr)?;
+
+        #[cfg(feature = "parallel")]
+        if evaluator.blocks() >= parallel::MIN_PARALLEL_BLOCKS {
+            return Ok(parallel::count_rows(&evaluator, true).into());
+        }
+
+        Ok(evaluator.count_rows(true).into())
+    }
+}
+
+/// Decides by handing the [Tseitin encoding](Cnf::tseitin) of an expression
+/// to the SAT [Solver](crate::Solver). A tautology is then just an
+/// expression whose negation is unsatisfiable. Scales to thousands of
+/// variables.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Sat;
+
+impl Decider for Sat {
+    fn name(&self) -> &'static str {
+        "sat"
+    }
+
+    fn find_assignment(&self, expr: &ASTNode, value: bool) -> Result<Option<Assignments>> {
+        let cnf = Cnf::tseitin(expr, value);
+
+        Ok(cnf.solver().solve().map(|model| cnf.assignments(&model)))
+    }
+}
+
+/// Decides by building the [BDD](BddManager) of an expression, after which
+/// tautologies and contradictions are just the constants. Counts models in
+/// time linear in the size of the BDD, though for some expressions the BDD
+/// itself grows exponentially.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Bdd;
+
+impl Decider for Bdd {
+    fn name(&self) -> &'static str {
+        "bdd"
+    }
+
+    fn find_assignment(&self, expr: &ASTNode, value: bool) -> Result<Option<Assignments>> {
+        let mut manager = BddManager::new();
+        let mut f = manager.from_ast(expr);
+
+        if !value {
+            f = manager.not(f);
+        }
+
+        Ok(manager.any_model(f))
+    }
+
+    fn count_models(&self, expr: &ASTNode) -> Result<u128> {
+        let mut manager = BddManager::new();
+        let f = manager.from_ast(expr);
+
+        manager.count_models(f).ok_or(EngineError::CountOverflow {
+            variables: manager.variables().len(),
+        })
+    }
+}
+
+/// Picks a procedure for each expression based on how much work its truth
+/// table would be, which depends on both how many variables it has and how
+/// big it is. Small tables are enumerated, anything else goes to the SAT
+/// solver, or to a BDD when counting models.
+#[derive(Debug, Clone, Copy)]
+pub struct Auto {
+    /// The most word operations a truth table is allowed to take
+    pub truth_table_budget: u64,
+}
+
+impl Auto {
+    pub const DEFAULT_TRUTH_TABLE_BUDGET: u64 = 1 << 24;
+
+    /// The procedure used to find assignments for `expr`
+    pub fn choose(&self, expr: &ASTNode) -> &'static dyn Decider {
+        if self.fits_truth_table(expr) {
+            &TruthTable
+        } else {
+            &Sat
+        }
+    }
+
+    /// The procedure used to count the models of `expr`
+    pub fn choose_counter(&self, expr: &ASTNode) -> &'static dyn Decider {
+        if self.fits_truth_table(expr) {
+            &TruthTable
+        } else {
+            &Bdd
+        }
+    }
+
+    fn fits_truth_table(&self, expr: &ASTNode) -> bool {
+        let program = Program::compile(expr);
+        let variables = program.variables().len();
+
+        if variables > Engine::MAX_VARIABLES {
+            return false;
+        }
+
+        // every block of 64 rows runs the whole program once
+        let blocks = (1u64 << variables).div_ceil(64);
+        let cost = blocks.saturating_mul(program.instructions().len() as u64);
+
+        cost <= self.truth_table_budget
+    }
+}
+
+impl Default for Auto {
+    fn default() -> Self {
+        Auto {
+            truth_table_budget: Self::DEFAULT_TRUTH_TABLE_BUDGET,
+        }
+    }
+}
+
+impl Decider for Auto {
+    fn name(&self) -> &'static str {
+        "auto"
+    }
+
+    fn find_assignment(&self, expr: &ASTNode, value: bool) -> Result<Option<Assignments>> {
+        self.choose(expr).find_assignment(expr, value)
+    }
+
+    fn count_models(&self, expr: &ASTNode) -> Result<u128> {
+        self.choose_counter(expr).count_models(expr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{self, FORMULAS};
+
+    #[test]
+    fn test_deciders_agree() {
+        let engine = Engine::default();
+        let deciders: [&dyn Decider; 4] = [&TruthTable, &Sat, &Bdd, &Auto::default()];
+
+        for formula in FORMULAS {
+            let expr = engine.parse(formula).unwrap();
+
+            for value in [true, false] {
+                let expected = TruthTable.find_assignment(&expr, value).unwrap().is_some();
+
+                for decider in deciders {
+                    let found = decider.find_assignment(&expr, value).unwrap();
+
+                    assert_eq!(found.is_some(), expected, "{} on {formula}", decider.name());
+
+                    if let Some(assignments) = found {
+                        assert_eq!(engine.eval(&expr, &assignments), Ok(value));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_auto_choice() {
+        let engine = Engine::default();
+        let auto = Auto::default();
+
+        let small = engine.parse("p && q || r").unwrap();
+        assert_eq!(auto.choose(&small).name(), "truth-table");
+
+        let wide = testing::joined(40, "||");
+        let wide = engine.parse(&wide).unwrap();
+        assert_eq!(auto.choose(&wide).name(), "sat");
+        assert_eq!(auto.choose_counter(&wide).name(), "bdd");
+
+        // few enough variables for a truth table, but too many rows for a
+        // budget this small
+        let auto = Auto {
+            truth_table_budget: 10,
+        };
+        let medium = engine.parse("a && b && c && d && e && f && g").unwrap();
+        assert_eq!(auto.choose(&medium).name(), "sat");
+    }
+
+    #[test]
+    fn test_counts_agree() {
+        let engine = Engine::default();
+
+        for formula in FORMULAS {
+            let expr = engine.parse(formula).unwrap();
+
+            assert_eq!(
+                Bdd.count_models(&expr),
+                TruthTable.count_models(&expr),
+                "{formula}"
+            );
+        }
+
+        // far past what a truth table can count
+        let wide = testing::joined(100, "||");
+
+        assert_eq!(
+            Auto::default().count_models(&engine.parse(&wide).unwrap()),
+            Ok((1 << 100) - 1)
+        );
+    }
+
+    #[test]
+    fn test_count_overflow() {
+        let engine = Engine::default();
+
+        // a tautology over 128 variables has 2^128 models
+        let widest = (0..128)
+            .map(|i| format!("v{i} || !v{i}"))
+            .collect::<Vec<_>>()
+            .join(" || ");
+
+        assert_eq!(
+            Bdd.count_models(&engine.parse(&widest).unwrap()),
+            Err(EngineError::CountOverflow { variables: 128 })
+        );
+    }
+
+    #[test]
+    fn test_sat_counts_with_truth_table() {
+        let engine = Engine::default();
+
+        assert_eq!(Sat.count_models(&engine.parse("p || q").unwrap()), Ok(3));
+    }
+}
@@ -0,0 +1,204 @@
+use std::{borrow::Cow, fmt::Write};
+
+use russell_ast::{ASTNode, Notation};
+
+/// One rewrite in a [Derivation]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// The name of the law applied, such as "De Morgan"
+    pub law: Cow<'static, str>,
+
+    /// Whether the law was applied right to left
+    pub reversed: bool,
+
+    /// Where the rewritten subterm is, as child indices from the root: `[]`
+    /// is the whole formula, `[1, 0]` the left operand of its right operand
+    pub position: Vec<usize>,
+
+    /// The whole formula after the step
+    pub result: ASTNode,
+}
+
+impl Step {
+    /// The law and where it was applied, like `De Morgan at 1.0`
+    pub fn justification(&self) -> String {
+        let mut text = self.law.to_string();
+
+        if self.reversed {
+            text.push_str(" (reversed)");
+        }
+
+        if self.position.is_empty() {
+            text.push_str(" at root");
+        } else {
+            let position = self
+                .position
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>();
+
+            write!(text, " at {}", position.join(".")).unwrap();
+        }
+
+        text
+    }
+}
+
+/// A chain of formulas, each following from the one before by a named law
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Derivation {
+    pub start: ASTNode,
+    pub steps: Vec<Step>,
+}
+
+impl Derivation {
+    /// The last formula in the chain
+    pub fn result(&self) -> &ASTNode {
+        self.steps.last().map_or(&self.start, |step| &step.result)
+    }
+
+    /// One formula per line, with the law justifying each step beside it:
+    ///
+    /// ```text
+    ///   !(p && q) || p
+    /// = !p || !q || p   De Morgan at 0
+    /// ```
+    pub fn to_text(&self) -> String {
+        let formulas = self.rendered(&Notation::RUSSELL);
+        let width = formulas
+            .iter()
+            .map(|f| f.chars().count())
+            .max()
+            .unwrap_or(0);
+
+        let mut text = format!("  {}\n", formulas[0]);
+
+        for (step, formula) in self.steps.iter().zip(&formulas[1..]) {
+            writeln!(text, "= {formula:width$}   {}", step.justification()).unwrap();
+        }
+
+        text
+    }
+
+    /// A Markdown table with a row per step
+    pub fn to_markdown(&self) -> String {
+        // pipes would end the table cell, even inside code
+        let cell = |formula: &str| format!("`{}`", formula.replace('|', "\\|"));
+        let formulas = self.rendered(&Notation::RUSSELL);
+
+        let mut text = String::from("| Step | Formula | Justification |\n|---:|---|---|\n");
+        writeln!(text, "| 0 | {} | |", cell(&formulas[0])).unwrap();
+
+        for (i, (step, formula)) in self.steps.iter().zip(&formulas[1..]).enumerate() {
+            writeln!(
+                text,
+                "| {} | {} | {} |",
+                i + 1,
+                cell(formula),
+                step.justification()
+            )
+            .unwrap();
+        }
+
+        text
+    }
+
+    /// A LaTeX `align*` environment, with the formulas lined up on `\equiv`
+    pub fn to_latex(&self) -> String {
+        let formulas = self.rendered(&Notation::LATEX);
+
+        let mut lines = vec![format!("  & {}", formulas[0])];
+
+        for (step, formula) in self.steps.iter().zip(&formulas[1..]) {
+            lines.push(format!(
+                "  \\equiv {{}} & {formula} && \\text{{{}}}",
+                step.justification()
+            ));
+        }
+
+        format!(
+            "\\begin{{align*}}\n{}\n\\end{{align*}}\n",
+            lines.join(" \\\\\n")
+        )
+    }
+
+    /// Every formula in the chain, starting with [Derivation::start]
+    pub fn formulas(&self) -> impl Iterator<Item = &ASTNode> {
+        std::iter::once(&self.start).chain(self.steps.iter().map(|step| &step.result))
+    }
+
+    fn rendered(&self, notation: &Notation) -> Vec<String> {
+        self.formulas()
+            .map(|formula| formula.display(notation).to_string())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Rewriter;
+    use russell_parser::parse;
+
+    fn derivation() -> Derivation {
+        Rewriter::builtin().derive(&parse("q || !!(p && !p)").unwrap())
+    }
+
+    #[test]
+    fn test_steps() {
+        let derivation = derivation();
+
+        let laws = derivation
+            .steps
+            .iter()
+            .map(|step| step.justification())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            laws,
+            [
+                "Double negation at 1",
+                "Complement at 1",
+                "Identity at root"
+            ]
+        );
+        assert_eq!(derivation.result().to_string(), "q");
+    }
+
+    #[test]
+    fn test_text() {
+        assert_eq!(
+            derivation().to_text(),
+            "  q || !!(p && !p)\n\
+             = q || p && !p       Double negation at 1\n\
+             = q || false         Complement at 1\n\
+             = q                  Identity at root\n"
+        );
+    }
+
+    #[test]
+    fn test_markdown() {
+        assert_eq!(
+            derivation().to_markdown(),
+            "| Step | Formula | Justification |\n\
+             |---:|---|---|\n\
+             | 0 | `q \\|\\| !!(p && !p)` | |\n\
+             | 1 | `q \\|\\| p && !p` | Double negation at 1 |\n\
+             | 2 | `q \\|\\| false` | Complement at 1 |\n\
+             | 3 | `q` | Identity at root |\n"
+        );
+    }
+
+    #[test]
+    fn test_latex() {
+        assert_eq!(
+            derivation().to_latex(),
+            "\\begin{align*}\n  \
+             & q \\lor \\neg \\neg (p \\land \\neg p) \\\\\n  \
+             \\equiv {} & q \\lor p \\land \\neg p && \\text{Double negation at 1} \\\\\n  \
+             \\equiv {} & q \\lor \\bot && \\text{Complement at 1} \\\\\n  \
+             \\equiv {} & q && \\text{Identity at root}\n\
+             \\end{align*}\n"
+        );
+    }
+}
@@ -0,0 +1,333 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
+
+use russell_ast::{ASTNode, Document, Formula, Span, StatementKind, Symbol};
+
+use crate::{Assignments, Engine, EngineError};
+
+/// The text of a document, or of one it includes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Source {
+    /// What the source is called in diagnostics, and how includes of it are
+    /// told apart, so usually a path
+    pub name: String,
+    pub text: String,
+}
+
+/// A `prove` statement, or a formula on its own, after checking
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Goal {
+    /// The index of the source the goal is in, in [DocumentReport::sources]
+    pub source: usize,
+
+    /// Where the formula is in its source
+    pub span: Span,
+
+    /// The formula, with definitions expanded
+    pub formula: ASTNode,
+
+    /// The assumptions in force at the goal, with definitions expanded
+    pub assumptions: Vec<ASTNode>,
+
+    /// An assignment making every assumption true and the formula false, or
+    /// [None] if the formula follows from them
+    pub counterexample: Option<Assignments>,
+
+    /// How long checking the goal took
+    pub elapsed: Duration,
+}
+
+impl Goal {
+    pub fn proved(&self) -> bool {
+        self.counterexample.is_none()
+    }
+}
+
+/// What [Engine::check_document] made of a document and everything it
+/// includes
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DocumentReport {
+    /// The document, then each include in the order they were read
+    pub sources: Vec<Source>,
+
+    /// Every goal that could be checked, in order
+    pub goals: Vec<Goal>,
+
+    /// The errors, with the index of the source each is in
+    pub errors: Vec<(usize, EngineError)>,
+}
+
+impl DocumentReport {
+    /// Whether every goal was checked and proved
+    pub fn proved(&self) -> bool {
+        self.errors.is_empty() && self.goals.iter().all(Goal::proved)
+    }
+}
+
+/// The state carried through a document and its includes, which all share
+/// definitions and assumptions as if they were one file
+struct Checker<'a, F> {
+    engine: &'a Engine,
+    load: F,
+    report: DocumentReport,
+    definitions: HashMap<Symbol, ASTNode>,
+    assumptions: Vec<ASTNode>,
+
+    /// The names of the sources being checked, to catch includes that go
+    /// round in a circle
+    including: Vec<String>,
+
+    /// The names of the sources checked already, which aren't read twice
+    included: HashSet<String>,
+}
+
+impl<F> Checker<'_, F>
+where
+    F: FnMut(&Source, &str) -> Result<Source, String>,
+{
+    fn check(&mut self, source: Source) {
+        let index = self.report.sources.len();
+
+        self.including.push(source.name.clone());
+        self.report.sources.push(source);
+
+        match self.engine.parse_document(&self.report.sources[index].text) {
+            Ok(document) => self.statements(index, document),
+            Err(errors) => self.report.errors.push((index, errors.into())),
+        }
+
+        if let Some(name) = self.including.pop() {
+            self.included.insert(name);
+        }
+    }
+
+    fn statements(&mut self, index: usize, document: Document) {
+        for statement in document.statements {
+            let checked = match statement.kind {
+                StatementKind::Let { name, formula } => self.define(name, formula, statement.span),
+
+                StatementKind::Assume(formula) => {
+                    let expr = formula.expr.substitute(&self.definitions);
+                    self.assumptions.push(expr);
+                    Ok(())
+                }
+
+                StatementKind::Prove(formula) | StatementKind::Formula(formula) => {
+                    self.prove(index, formula, statement.span)
+                }
+
+                StatementKind::Include { path } => self.include(index, path, statement.span),
+            };
+
+            if let Err(error) = checked {
+                self.report.errors.push((index, error));
+            }
+        }
+    }
+
+    fn define(&mut self, name: Symbol, formula: Formula, span: Span) -> Result<(), EngineError> {
+        if self.definitions.contains_key(&name) {
+            return Err(EngineError::Redefined {
+                name,
+                span: Some(span),
+            });
+        }
+
+        // later definitions can't change what this one means
+        let expr = formula.expr.substitute(&self.definitions);
+        self.definitions.insert(name, expr);
+
+        Ok(())
+    }
+
+    fn prove(&mut self, index: usize, formula: Formula, span: Span) -> Result<(), EngineError> {
+        let started = Instant::now();
+        let expr = formula.expr.substitute(&self.definitions);
+
+        let premises = self
+            .assumptions
+            .iter()
+            .cloned()
+            .reduce(|p, q| ASTNode::And(Box::new(p), Box::new(q)));
+
+        let implication = match premises {
+            Some(premises) => ASTNode::Implies(Box::new(premises), Box::new(expr.clone())),
+            None => expr.clone(),
+        };
+
+        let counterexample = self.engine.find_counterexample(&implication)?;
+
+        self.report.goals.push(Goal {
+            source: index,
+            span: formula.span().unwrap_or(span),
+            formula: expr,
+            assumptions: self.assumptions.clone(),
+            counterexample,
+            elapsed: started.elapsed(),
+        });
+
+        Ok(())
+    }
+
+    fn include(&mut self, index: usize, path: String, span: Span) -> Result<(), EngineError> {
+        let error = |reason: String| EngineError::Include {
+            path: path.clone(),
+            reason,
+            span: Some(span.clone()),
+        };
+
+        let source = (self.load)(&self.report.sources[index], &path).map_err(error)?;
+
+        if self.including.contains(&source.name) {
+            return Err(error(format!(
+                "`{}` is already being included, so this never ends",
+                source.name
+            )));
+        }
+
+        if !self.included.contains(&source.name) {
+            self.check(source);
+        }
+
+        Ok(())
+    }
+}
+
+impl Engine {
+    /// Checks every goal in `source`, a document as described in
+    /// [Document], and in the documents it includes. `load` reads the
+    /// source an `include` names, given the source the `include` is in and
+    /// the path as written, or explains why it can't.
+    ///
+    /// Each goal is checked against the assumptions before it. A file
+    /// included a second time is skipped, so its definitions aren't made
+    /// twice.
+    pub fn check_document<F>(&self, source: Source, load: F) -> DocumentReport
+    where
+        F: FnMut(&Source, &str) -> Result<Source, String>,
+    {
+        let mut checker = Checker {
+            engine: self,
+            load,
+            report: DocumentReport::default(),
+            definitions: HashMap::new(),
+            assumptions: Vec::new(),
+            including: Vec::new(),
+            included: HashSet::new(),
+        };
+
+        checker.check(source);
+        checker.report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(name: &str, text: &str) -> Source {
+        Source {
+            name: name.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    fn check(text: &str) -> DocumentReport {
+        Engine::default().check_document(source("main.rsl", text), |_, path| {
+            Err(format!("no file `{path}`"))
+        })
+    }
+
+    #[test]
+    fn test_goals_follow_from_assumptions() {
+        let report = check(
+            "
+            # modus tollens
+            let rule = p => q
+            assume rule; assume !q
+            prove !p
+            prove q
+            ",
+        );
+
+        assert!(report.errors.is_empty());
+        assert_eq!(report.goals.len(), 2);
+
+        let [tollens, wrong] = &report.goals[..] else {
+            unreachable!();
+        };
+        assert!(tollens.proved());
+        assert_eq!(tollens.assumptions.len(), 2);
+        assert_eq!(&report.sources[0].text[tollens.span.clone()], "!p");
+
+        assert!(!wrong.proved());
+        assert_eq!(
+            wrong.counterexample.as_ref().unwrap().to_string(),
+            "p = false, q = false"
+        );
+        assert!(!report.proved());
+    }
+
+    #[test]
+    fn test_definitions() {
+        let report = check("let a = p && q\nlet b = a || r\nb == p && q || r");
+
+        assert!(report.proved());
+        assert_eq!(
+            report.goals[0].formula.to_string(),
+            "p && q || r == p && q || r"
+        );
+
+        let report = check("let a = p\nlet a = q\na");
+        assert_eq!(
+            report.errors,
+            [(
+                0,
+                EngineError::Redefined {
+                    name: Symbol::intern("a"),
+                    span: Some(10..19)
+                }
+            )]
+        );
+    }
+
+    #[test]
+    fn test_includes() {
+        let files = [
+            (
+                "main.rsl",
+                "include \"defs.rsl\"\ninclude \"defs.rsl\"\nprove d",
+            ),
+            ("defs.rsl", "let d = p || !p"),
+            ("loop.rsl", "include \"loop.rsl\""),
+        ];
+
+        let load = |_: &Source, path: &str| {
+            files
+                .iter()
+                .find(|(name, _)| *name == path)
+                .map(|(name, text)| source(name, text))
+                .ok_or_else(|| format!("no file `{path}`"))
+        };
+
+        let engine = Engine::default();
+
+        let report = engine.check_document(source("main.rsl", files[0].1), load);
+        assert!(report.proved());
+        assert_eq!(report.sources.len(), 2);
+
+        let report = engine.check_document(source("loop.rsl", files[2].1), load);
+        assert!(matches!(
+            &report.errors[..],
+            [(0, EngineError::Include { path, .. })] if path == "loop.rsl"
+        ));
+
+        let report = check("include \"missing.rsl\"");
+        assert_eq!(
+            report.errors[0].1.to_string(),
+            "couldn't include `missing.rsl`: no file `missing.rsl`"
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use russell_ast::Symbol;
+
+use crate::Assignments;
+
+/// The order [AssignmentIter] visits assignments in
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    /// Counting in binary, with the first variable as the least significant
+    /// bit. This is the order truth tables are usually written in.
+    Binary,
+
+    /// Reflected binary Gray code, where consecutive assignments differ in
+    /// exactly one variable
+    #[default]
+    Gray,
+}
+
+impl Order {
+    /// The bit pattern of row `i`, where bit `n` is the value of the `n`th
+    /// variable
+    fn code(self, i: u64) -> u64 {
+        match self {
+            Order::Binary => i,
+            Order::Gray => i ^ (i >> 1),
+        }
+    }
+}
+
+/// Lazily iterates over every assignment of a set of variables.
+///
+/// Only the current assignment is kept around, and moving to the next one
+/// only touches the variables that change, so memory use doesn't depend on
+/// how many rows there are and stopping early skips the rest entirely.
+#[derive(Debug, Clone)]
+pub struct AssignmentIter {
+    variables: Vec<Symbol>,
+    order: Order,
+    current: Assignments,
+
+    /// Index of the row `current` holds
+    row: u64,
+    rows: u64,
+}
+
+impl AssignmentIter {
+    /// Panics if there are 64 or more variables, as the rows can't be
+    /// counted. [crate::Engine::enumerate_assignments] checks for this.
+    pub fn new(variables: Vec<Symbol>, order: Order) -> Self {
+        assert!(variables.len() < 64, "too many variables to enumerate");
+
+        // every row starts out with all variables false
+        let current = variables.iter().map(|var| (*var, false)).collect();
+
+        AssignmentIter {
+            rows: 1 << variables.len(),
+            variables,
+            order,
+            current,
+            row: 0,
+        }
+    }
+
+    pub fn variables(&self) -> &[Symbol] {
+        &self.variables
+    }
+}
+
+impl Iterator for AssignmentIter {
+    type Item = Assignments;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.row >= self.rows {
+            return None;
+        }
+
+        let assignments = self.current.clone();
+
+        self.row += 1;
+
+        if self.row < self.rows {
+            // only update the variables whose bit differs between the rows
+            let mut changed = self.order.code(self.row - 1) ^ self.order.code(self.row);
+
+            while changed != 0 {
+                let pos = changed.trailing_zeros() as usize;
+                let var = self.variables[pos];
+
+                let value = self.current.get(var) == Some(true);
+                self.current.insert(var, !value);
+
+                changed &= changed - 1;
+            }
+        }
+
+        Some(assignments)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = usize::try_from(self.rows - self.row).ok();
+
+        (remaining.unwrap_or(usize::MAX), remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(order: Order) -> Vec<String> {
+        let variables = vec![Symbol::intern("p"), Symbol::intern("q")];
+
+        AssignmentIter::new(variables, order)
+            .map(|a| a.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_binary_order() {
+        assert_eq!(
+            rows(Order::Binary),
+            vec![
+                "p = false, q = false",
+                "p = true, q = false",
+                "p = false, q = true",
+                "p = true, q = true",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_gray_order() {
+        assert_eq!(
+            rows(Order::Gray),
+            vec![
+                "p = false, q = false",
+                "p = true, q = false",
+                "p = true, q = true",
+                "p = false, q = true",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_every_row_is_visited_once() {
+        let variables = ["a", "b", "c", "d", "e"].map(Symbol::intern).to_vec();
+
+        for order in [Order::Binary, Order::Gray] {
+            let mut seen = AssignmentIter::new(variables.clone(), order)
+                .map(|a| a.to_string())
+                .collect::<Vec<_>>();
+
+            assert_eq!(seen.len(), 32);
+
+            seen.sort();
+            seen.dedup();
+
+            assert_eq!(seen.len(), 32);
+        }
+    }
+
+    #[test]
+    fn test_no_variables() {
+        let rows = AssignmentIter::new(vec![], Order::Gray).collect::<Vec<_>>();
+
+        assert_eq!(rows, vec![Assignments::default()]);
+    }
+
+    #[test]
+    fn test_size_hint() {
+        let variables = ["a", "b", "c"].map(Symbol::intern).to_vec();
+        let mut iter = AssignmentIter::new(variables, Order::Gray);
+
+        iter.next();
+
+        assert_eq!(iter.size_hint(), (7, Some(7)));
+    }
+}
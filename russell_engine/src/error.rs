@@ -0,0 +1,166 @@
+use russell_ast::{ASTNode, Span, SpanTable, Symbol};
+use russell_parser::{ParseErrors, Report};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum EngineError {
+    /// A variable was evaluated without being given a value. `span` points at
+    /// its first occurrence, if the expression came with a [SpanTable].
+    #[error("variable `{name}` has no value assigned")]
+    UnboundVariable { name: Symbol, span: Option<Span> },
+
+    /// The expression has more variables than we're willing to build a truth
+    /// table for
+    #[error("expression has {count} variables, but at most {limit} are supported")]
+    TooManyVariables { count: usize, limit: usize },
+
+    /// An expression with so many variables that its models can't be
+    /// counted in a `u128`
+    #[error("expression has {variables} variables, too many to count its models")]
+    CountOverflow { variables: usize },
+
+    /// Converting to CNF or DNF by distribution would give more terms than
+    /// we're willing to build
+    #[error("normal form would have more than {limit} terms")]
+    NormalFormTooLarge { limit: usize },
+
+    /// A rewrite rule that can't be used, such as one whose right hand side
+    /// has pattern variables its left hand side doesn't bind
+    #[error("invalid rule `{name}`: {reason}")]
+    InvalidRule { name: String, reason: String },
+
+    /// A truth table row number past the end of the table
+    #[error("row {row} is out of range for a table with {rows} rows")]
+    RowOutOfRange { row: u64, rows: u64 },
+
+    /// A [Pla](crate::Pla) with more outputs than fit in a term
+    #[error("PLA has {count} outputs, but at most {limit} are supported")]
+    TooManyOutputs { count: usize, limit: usize },
+
+    /// A PLA file that couldn't be read
+    #[error("invalid PLA at line {line}: {reason}")]
+    InvalidPla { line: usize, reason: String },
+
+    /// An `include` in a document whose file couldn't be read, or which
+    /// would include a file inside itself
+    #[error("couldn't include `{path}`: {reason}")]
+    Include {
+        path: String,
+        reason: String,
+        span: Option<Span>,
+    },
+
+    /// A document with statements where a single formula was expected.
+    /// `span` points at the first statement that isn't part of it.
+    #[error("expected a single formula, but this is a document with statements")]
+    NotAFormula { span: Option<Span> },
+
+    /// A document with no formula or `prove` statement, where there should
+    /// be something to check
+    #[error("no formula to check")]
+    NothingToCheck,
+
+    /// A document defining a name it has defined already
+    #[error("`{name}` is already defined")]
+    Redefined { name: Symbol, span: Option<Span> },
+
+    #[error(transparent)]
+    Parse(#[from] ParseErrors),
+}
+
+pub type Result<T, E = EngineError> = std::result::Result<T, E>;
+
+impl EngineError {
+    /// A short name for the kind of error, such as `unbound_variable`, for
+    /// callers that can't match on the enum itself
+    pub fn kind(&self) -> &'static str {
+        match self {
+            EngineError::UnboundVariable { .. } => "unbound_variable",
+            EngineError::TooManyVariables { .. } => "too_many_variables",
+            EngineError::CountOverflow { .. } => "count_overflow",
+            EngineError::NormalFormTooLarge { .. } => "normal_form_too_large",
+            EngineError::InvalidRule { .. } => "invalid_rule",
+            EngineError::RowOutOfRange { .. } => "row_out_of_range",
+            EngineError::TooManyOutputs { .. } => "too_many_outputs",
+            EngineError::InvalidPla { .. } => "invalid_pla",
+            EngineError::Include { .. } => "include",
+            EngineError::NotAFormula { .. } => "not_a_formula",
+            EngineError::NothingToCheck => "nothing_to_check",
+            EngineError::Redefined { .. } => "redefined",
+            EngineError::Parse(_) => "parse",
+        }
+    }
+
+    /// Fills in spans the error is missing, using the spans of the
+    /// expression it was produced from
+    pub fn locate(self, expr: &ASTNode, spans: &SpanTable) -> Self {
+        match self {
+            EngineError::UnboundVariable { name, span: None } => {
+                let span = expr
+                    .find_all(|node| *node == ASTNode::Variable(name))
+                    .first()
+                    .and_then(|id| spans.get(*id));
+
+                EngineError::UnboundVariable { name, span }
+            }
+
+            other => other,
+        }
+    }
+
+    /// The error as [Report]s on the parts of the source at fault, or none
+    /// if we don't know which part that is
+    pub fn reports(&self) -> Vec<Report> {
+        match self {
+            EngineError::Parse(errors) => errors.iter().map(Report::from).collect(),
+
+            EngineError::UnboundVariable {
+                span: Some(span), ..
+            } => vec![
+                Report::error(self.to_string(), span.clone())
+                    .with_label("used here")
+                    .with_help(Some(
+                        "give it a value, or check the name for typos".to_string(),
+                    )),
+            ],
+
+            EngineError::Include {
+                span: Some(span), ..
+            } => vec![Report::error(self.to_string(), span.clone()).with_label("included here")],
+
+            EngineError::NotAFormula { span: Some(span) } => vec![
+                Report::error(self.to_string(), span.clone())
+                    .with_label("this isn't part of the formula")
+                    .with_help(Some("check it as a whole document instead".to_string())),
+            ],
+
+            EngineError::Redefined {
+                span: Some(span), ..
+            } => vec![
+                Report::error(self.to_string(), span.clone())
+                    .with_label("defined again here")
+                    .with_help(Some(
+                        "definitions can't change, so pick a new name".to_string(),
+                    )),
+            ],
+
+            _ => Vec::new(),
+        }
+    }
+
+    /// Renders the error as annotated snippets of `source` where we know
+    /// which part of it is at fault, or as a plain message otherwise. `name`
+    /// identifies the source (usually a file path) in the output.
+    pub fn render(&self, source: &str, name: &str) -> String {
+        let reports = self.reports();
+
+        if reports.is_empty() {
+            return format!("error: {self}\n");
+        }
+
+        reports
+            .iter()
+            .map(|report| report.render(source, name))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
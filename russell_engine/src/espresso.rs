@@ -0,0 +1,584 @@
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
+
+use crate::{Cube, PlaTerm};
+
+/// Heuristic two-level minimization of a function with `outputs` outputs,
+/// after Espresso. Returns a cover of `on` that stays within `on` and
+/// `dont_care`.
+///
+/// Every cube is first expanded as far as it can go without covering a row
+/// of the off-set, swallowing the cubes it comes to contain, and then cubes
+/// covered by the rest are dropped. From there, each round shrinks every
+/// cube to the part only it covers, which frees the next expansion to go in
+/// other directions, until a round fails to make the cover any cheaper.
+pub(crate) fn minimize(on: &[PlaTerm], dont_care: &[PlaTerm], outputs: usize) -> Vec<PlaTerm> {
+    let all_outputs = match outputs {
+        64.. => u64::MAX,
+        n => (1 << n) - 1,
+    };
+
+    let off = off_set(on, dont_care, outputs);
+
+    let on = on.iter().filter(|term| term.outputs != 0).copied();
+    let mut cover = merge(on);
+    cover = expand(cover, &off, all_outputs);
+    cover = irredundant(cover, dont_care);
+
+    loop {
+        let reduced = reduce(cover.clone(), dont_care);
+        let next = irredundant(expand(reduced, &off, all_outputs), dont_care);
+
+        if cost(&next) >= cost(&cover) {
+            break;
+        }
+
+        cover = next;
+    }
+
+    cover.sort_by_key(|term| (term.input.bits, term.input.mask, term.outputs));
+    cover
+}
+
+/// Fewest cubes first, then fewest literals, then fewest connections to
+/// outputs
+fn cost(cover: &[PlaTerm]) -> (usize, u32, u32) {
+    let literals = cover.iter().map(|term| term.input.literal_count()).sum();
+    let connections = cover.iter().map(|term| term.outputs.count_ones()).sum();
+
+    (cover.len(), literals, connections)
+}
+
+/// Combines terms with the same input cube, keeping the first's position
+fn merge(terms: impl IntoIterator<Item = PlaTerm>) -> Vec<PlaTerm> {
+    let mut merged: Vec<PlaTerm> = Vec::new();
+    let mut positions: HashMap<Cube, usize> = HashMap::new();
+
+    for term in terms {
+        match positions.get(&term.input) {
+            Some(&pos) => merged[pos].outputs |= term.outputs,
+            None => {
+                positions.insert(term.input, merged.len());
+                merged.push(term);
+            }
+        }
+    }
+
+    merged
+}
+
+/// The input cubes of the terms that are part of output `output`
+fn output_cubes<'a>(
+    terms: impl IntoIterator<Item = &'a PlaTerm>,
+    output: usize,
+) -> impl Iterator<Item = Cube> {
+    terms
+        .into_iter()
+        .filter(move |term| (term.outputs >> output) & 1 == 1)
+        .map(|term| term.input)
+}
+
+/// The rows where each output is false
+fn off_set(on: &[PlaTerm], dont_care: &[PlaTerm], outputs: usize) -> Vec<PlaTerm> {
+    let off = (0..outputs).flat_map(|output| {
+        let cubes = output_cubes(on.iter().chain(dont_care), output).collect::<Vec<_>>();
+
+        complement(&cubes).into_iter().map(move |input| PlaTerm {
+            input,
+            outputs: 1 << output,
+        })
+    });
+
+    merge(off)
+}
+
+fn expand(cover: Vec<PlaTerm>, off: &[PlaTerm], all_outputs: u64) -> Vec<PlaTerm> {
+    let blocked = |input: &Cube, outputs: u64| {
+        off.iter()
+            .any(|term| term.outputs & outputs != 0 && term.input.intersects(input))
+    };
+
+    // the biggest cubes go first, since they have the best chance of
+    // swallowing the rest. Popping takes from the end.
+    let mut pending = cover;
+    pending.sort_by_key(|term| Reverse(term.input.literal_count()));
+
+    let mut expanded = Vec::with_capacity(pending.len());
+
+    while let Some(term) = pending.pop() {
+        let mut input = term.input;
+
+        // raise the literals other cubes disagree with first, since those
+        // are what stops this cube from containing them
+        let mut literals = (0..64)
+            .filter(|i| (input.mask >> i) & 1 == 1)
+            .collect::<Vec<_>>();
+
+        literals.sort_by_key(|i| {
+            let disagreeing = pending
+                .iter()
+                .chain(&expanded)
+                .filter(|other| {
+                    let differs = other.input.mask & (other.input.bits ^ input.bits);
+                    (differs >> i) & 1 == 1
+                })
+                .count();
+
+            Reverse(disagreeing)
+        });
+
+        for i in literals {
+            let raised = Cube {
+                mask: input.mask & !(1 << i),
+                bits: input.bits & !(1 << i),
+            };
+
+            if !blocked(&raised, term.outputs) {
+                input = raised;
+            }
+        }
+
+        // then connect it to any other output it's allowed to be part of
+        let mut outputs = term.outputs;
+
+        for j in 0..64 {
+            let bit = 1 << j;
+
+            if all_outputs & bit != 0 && outputs & bit == 0 && !blocked(&input, bit) {
+                outputs |= bit;
+            }
+        }
+
+        let term = PlaTerm { input, outputs };
+        pending.retain(|other| !term.contains(other));
+        expanded.push(term);
+    }
+
+    expanded
+}
+
+/// Disconnects cubes from outputs the rest of the cover already covers them
+/// for, and drops cubes left with no outputs
+fn irredundant(mut cover: Vec<PlaTerm>, dont_care: &[PlaTerm]) -> Vec<PlaTerm> {
+    // the smallest cubes, the likeliest to be redundant, go first
+    let mut order = (0..cover.len()).collect::<Vec<_>>();
+    order.sort_by_key(|i| Reverse(cover[*i].input.literal_count()));
+
+    for i in order {
+        let term = cover[i];
+
+        for j in (0..64).filter(|j| (term.outputs >> j) & 1 == 1) {
+            let others = cover
+                .iter()
+                .enumerate()
+                .filter(|(k, _)| *k != i)
+                .map(|(_, other)| other)
+                .chain(dont_care);
+
+            if covers(output_cubes(others, j), &term.input) {
+                cover[i].outputs &= !(1 << j);
+            }
+        }
+    }
+
+    cover.retain(|term| term.outputs != 0);
+    cover
+}
+
+/// Shrinks each cube to the smallest one holding everything only it covers
+fn reduce(mut cover: Vec<PlaTerm>, dont_care: &[PlaTerm]) -> Vec<PlaTerm> {
+    let mut order = (0..cover.len()).collect::<Vec<_>>();
+    order.sort_by_key(|i| cover[*i].input.literal_count());
+
+    for i in order {
+        let term = cover[i];
+        let mut outputs = term.outputs;
+        let mut reduced: Option<Cube> = None;
+
+        for j in (0..64).filter(|j| (term.outputs >> j) & 1 == 1) {
+            let others = cover
+                .iter()
+                .enumerate()
+                .filter(|(k, _)| *k != i)
+                .map(|(_, other)| other)
+                .chain(dont_care);
+
+            let cofactors = output_cubes(others, j)
+                .filter_map(|cube| cube.cofactor(&term.input))
+                .collect::<Vec<_>>();
+
+            // what's left of the cube once the rest of the cover is taken
+            // away, over the variables the cube doesn't mention
+            let Some(left) = complement_supercube(&cofactors) else {
+                outputs &= !(1 << j);
+                continue;
+            };
+
+            let part = Cube {
+                mask: left.mask | term.input.mask,
+                bits: left.bits | term.input.bits,
+            };
+
+            reduced = Some(reduced.map_or(part, |cube| cube.supercube(&part)));
+        }
+
+        cover[i] = PlaTerm {
+            input: reduced.unwrap_or(term.input),
+            outputs,
+        };
+    }
+
+    cover.retain(|term| term.outputs != 0);
+    cover
+}
+
+/// Whether `cubes` cover every row of `cube`
+fn covers(cubes: impl Iterator<Item = Cube>, cube: &Cube) -> bool {
+    let cofactors = cubes
+        .filter_map(|other| other.cofactor(cube))
+        .collect::<Vec<_>>();
+
+    tautology(&cofactors)
+}
+
+/// The variable to split a cover on: the one appearing most often in both
+/// polarities, and whether it does appear in both. [None] if no cube
+/// mentions any variable.
+fn split_variable(cubes: &[Cube]) -> Option<(usize, bool)> {
+    let mut counts = [[0usize; 2]; 64];
+
+    for cube in cubes {
+        for (i, count) in counts.iter_mut().enumerate() {
+            if (cube.mask >> i) & 1 == 1 {
+                count[((cube.bits >> i) & 1) as usize] += 1;
+            }
+        }
+    }
+
+    counts
+        .iter()
+        .enumerate()
+        .filter(|(_, [zeros, ones])| zeros + ones > 0)
+        .max_by_key(|(i, [zeros, ones])| {
+            (
+                *zeros > 0 && *ones > 0,
+                zeros.min(ones),
+                zeros + ones,
+                Reverse(*i),
+            )
+        })
+        .map(|(i, [zeros, ones])| (i, *zeros > 0 && *ones > 0))
+}
+
+/// Whether `cubes` cover every row
+fn tautology(cubes: &[Cube]) -> bool {
+    if cubes.contains(&Cube::UNIVERSE) {
+        return true;
+    }
+
+    // if no variable appears both ways round, only the universe itself
+    // could cover everything
+    let Some((var, true)) = split_variable(cubes) else {
+        return false;
+    };
+
+    [0, 1 << var].into_iter().all(|bits| {
+        let half = Cube {
+            mask: 1 << var,
+            bits,
+        };
+
+        let cofactors = cubes
+            .iter()
+            .filter_map(|cube| cube.cofactor(&half))
+            .collect::<Vec<_>>();
+
+        tautology(&cofactors)
+    })
+}
+
+/// Cubes covering exactly the rows `cubes` don't
+pub(crate) fn complement(cubes: &[Cube]) -> Vec<Cube> {
+    if cubes.contains(&Cube::UNIVERSE) {
+        return Vec::new();
+    }
+
+    // cubes inside others don't change the complement, but can make it a
+    // lot more work by adding variables to split on
+    let cubes = without_contained(cubes.to_vec());
+
+    let Some((var, _)) = split_variable(&cubes) else {
+        return vec![Cube::UNIVERSE];
+    };
+
+    // De Morgan for a single cube: a row is outside it if it disagrees on
+    // any one of its literals
+    if let [cube] = cubes[..] {
+        return (0..64)
+            .filter(|i| (cube.mask >> i) & 1 == 1)
+            .map(|i| Cube {
+                mask: 1 << i,
+                bits: !cube.bits & (1 << i),
+            })
+            .collect();
+    }
+
+    let bit = 1 << var;
+    let [zero, one] = [0, bit].map(|bits| {
+        let half = Cube { mask: bit, bits };
+
+        let cofactors = cubes
+            .iter()
+            .filter_map(|cube| cube.cofactor(&half))
+            .collect::<Vec<_>>();
+
+        complement(&cofactors)
+    });
+
+    // cubes in both halves don't need the variable at all
+    let in_zero = zero.iter().copied().collect::<HashSet<_>>();
+    let in_one = one.iter().copied().collect::<HashSet<_>>();
+
+    let mut result = Vec::with_capacity(zero.len() + one.len());
+
+    for cube in zero {
+        if in_one.contains(&cube) {
+            result.push(cube);
+        } else {
+            result.push(Cube {
+                mask: cube.mask | bit,
+                ..cube
+            });
+        }
+    }
+
+    for cube in one {
+        if !in_zero.contains(&cube) {
+            result.push(Cube {
+                mask: cube.mask | bit,
+                bits: cube.bits | bit,
+            });
+        }
+    }
+
+    without_contained(result)
+}
+
+/// The smallest cube containing every row `cubes` don't cover, or [None]
+/// if they cover everything. Cheaper than the complement itself.
+fn complement_supercube(cubes: &[Cube]) -> Option<Cube> {
+    if cubes.contains(&Cube::UNIVERSE) {
+        return None;
+    }
+
+    let cubes = without_contained(cubes.to_vec());
+
+    let Some((var, _)) = split_variable(&cubes) else {
+        return Some(Cube::UNIVERSE);
+    };
+
+    // the complement of a single cube is a cube per literal, which only
+    // share the universe unless there's just the one
+    if let [cube] = cubes[..] {
+        return Some(match cube.literal_count() {
+            1 => Cube {
+                mask: cube.mask,
+                bits: !cube.bits & cube.mask,
+            },
+            _ => Cube::UNIVERSE,
+        });
+    }
+
+    let bit = 1 << var;
+
+    [0, bit]
+        .into_iter()
+        .filter_map(|bits| {
+            let half = Cube { mask: bit, bits };
+
+            let cofactors = cubes
+                .iter()
+                .filter_map(|cube| cube.cofactor(&half))
+                .collect::<Vec<_>>();
+
+            complement_supercube(&cofactors).map(|cube| Cube {
+                mask: cube.mask | bit,
+                bits: cube.bits | bits,
+            })
+        })
+        .reduce(|a, b| a.supercube(&b))
+}
+
+/// Drops cubes contained in another, and repeats
+fn without_contained(mut cubes: Vec<Cube>) -> Vec<Cube> {
+    cubes.sort_by_key(Cube::literal_count);
+
+    let mut kept: Vec<Cube> = Vec::with_capacity(cubes.len());
+
+    for cube in cubes {
+        if !kept.iter().any(|other| other.contains(&cube)) {
+            kept.push(cube);
+        }
+    }
+
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Engine, Pla, QuineMcCluskey, Symbol};
+
+    /// A small deterministic generator, so the tests don't need a
+    /// dependency
+    fn xorshift(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    fn random_cubes(state: &mut u64, count: usize, variables: usize) -> Vec<Cube> {
+        let all = (1 << variables) - 1;
+
+        (0..count)
+            .map(|_| {
+                let mask = xorshift(state) & all;
+
+                Cube {
+                    mask,
+                    bits: xorshift(state) & mask,
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_complement_and_tautology() {
+        let mut state = 0x2545_f491_4f6c_dd1d;
+
+        for _ in 0..500 {
+            let count = (xorshift(&mut state) % 6) as usize;
+            let cubes = random_cubes(&mut state, count, 5);
+            let complement = complement(&cubes);
+
+            let covered = |cubes: &[Cube], row| cubes.iter().any(|cube| cube.covers(row));
+
+            for row in 0..32 {
+                assert_ne!(covered(&cubes, row), covered(&complement, row), "{cubes:?}");
+            }
+
+            assert_eq!(
+                complement_supercube(&cubes),
+                complement.iter().copied().reduce(|a, b| a.supercube(&b)),
+                "{cubes:?}"
+            );
+            assert_eq!(
+                tautology(&cubes),
+                (0..32).all(|row| covered(&cubes, row)),
+                "{cubes:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_matches_exact_minimization() {
+        let engine = Engine::default();
+
+        for input in [
+            "a && b || a && !b",
+            "a && b && c || a && b && !c || !a && b && c",
+            "(a => b) && (b => c) && (c => d)",
+            "!a && !b && !c || !a && b && !c || a && b && !c || a && b && c || a && !b && c",
+        ] {
+            let expr = engine.parse(input).unwrap();
+            let pla = Pla::from_ast([(Symbol::intern("f"), &expr)]).unwrap();
+            let minimized = pla.minimize();
+
+            let exact = QuineMcCluskey::new(&expr).cover().unwrap();
+            assert_eq!(minimized.on.len(), exact.len(), "{input}");
+            assert!(
+                engine
+                    .check_equivalent(&minimized.output_ast(0), &expr)
+                    .unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_random_functions() {
+        let mut state = 0x9e37_79b9_7f4a_7c15;
+        let covered = |cubes: &[Cube], row| cubes.iter().any(|cube| cube.covers(row));
+
+        for _ in 0..100 {
+            let on = random_cubes(&mut state, 6, 5);
+            let dont_care = random_cubes(&mut state, 2, 5);
+
+            let term = |input| PlaTerm { input, outputs: 1 };
+            let on_terms = on.iter().copied().map(term).collect::<Vec<_>>();
+            let dont_care_terms = dont_care.iter().copied().map(term).collect::<Vec<_>>();
+
+            let minimized = minimize(&on_terms, &dont_care_terms, 1)
+                .into_iter()
+                .map(|term| term.input)
+                .collect::<Vec<_>>();
+
+            assert!(minimized.len() <= merge(on_terms).len());
+
+            // the result has to agree with the original outside the
+            // don't-cares
+            for row in (0..32).filter(|row| !covered(&dont_care, *row)) {
+                assert_eq!(covered(&on, row), covered(&minimized, row), "{on:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_shared_terms() {
+        let engine = Engine::default();
+        let f = engine.parse("a && b").unwrap();
+        let g = engine.parse("a && b || c").unwrap();
+
+        let minimized = Pla::from_ast([(Symbol::intern("f"), &f), (Symbol::intern("g"), &g)])
+            .unwrap()
+            .minimize();
+
+        // `a && b` serves both outputs
+        assert_eq!(
+            minimized.to_string(),
+            ".i 3\n.o 2\n.ilb a b c\n.ob f g\n.p 2\n11- 11\n--1 01\n.e\n"
+        );
+    }
+
+    #[test]
+    fn test_many_inputs() {
+        let engine = Engine::default();
+
+        // 36 inputs is far beyond a truth table, but the cover has only a
+        // few dozen cubes. The redundant terms should all go.
+        let pairs = (0..12)
+            .map(|i| format!("a{i} && b{i}"))
+            .collect::<Vec<_>>()
+            .join(" || ");
+        let redundant = (0..12)
+            .map(|i| format!("a{i} && b{i} && c{i} || a{i} && b{i} && !c{i}"))
+            .collect::<Vec<_>>()
+            .join(" || ");
+
+        let expr = engine.parse(&format!("{redundant} || {pairs}")).unwrap();
+        let minimized = Pla::from_ast([(Symbol::intern("f"), &expr)])
+            .unwrap()
+            .minimize();
+
+        assert_eq!(minimized.inputs.len(), 36);
+        assert_eq!(minimized.on.len(), 12);
+        assert!(
+            minimized
+                .on
+                .iter()
+                .all(|term| term.input.literal_count() == 2)
+        );
+    }
+}
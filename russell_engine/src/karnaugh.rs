@@ -0,0 +1,591 @@
+use std::{fmt::Write, ops::Range};
+
+use russell_ast::{ASTNode, Symbol};
+
+use crate::{Cube, EngineError, Form, NormalForm, QuineMcCluskey, Result};
+
+/// The value of a cell of a [KarnaughMap]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cell {
+    False,
+    True,
+    DontCare,
+}
+
+impl Cell {
+    /// `0`, `1` or `-`, as cells are usually written
+    pub fn symbol(self) -> char {
+        match self {
+            Cell::False => '0',
+            Cell::True => '1',
+            Cell::DontCare => '-',
+        }
+    }
+}
+
+/// A prime implicant, drawn as a group of cells on a [KarnaughMap]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KarnaughGroup {
+    pub implicant: Cube,
+
+    /// Whether the group is one of those making up the minimal cover
+    pub in_cover: bool,
+}
+
+/// A Karnaugh map: the truth table laid out as a grid, with rows and
+/// columns in Gray code order so that neighbouring cells, including those
+/// across the edges, differ in a single variable. The first half of the
+/// variables pick the row and the rest the column.
+///
+/// Every prime implicant is a group of cells, found by [QuineMcCluskey]
+/// along with which of them make up a minimal cover. With five or six
+/// variables, three variables share an axis and some groups are split
+/// across the map instead of being neighbours.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KarnaughMap {
+    variables: Vec<Symbol>,
+    row_variables: usize,
+
+    /// Row by row
+    cells: Vec<Cell>,
+    groups: Vec<KarnaughGroup>,
+}
+
+/// Colours for the groups in SVG output
+const PALETTE: &[&str] = &[
+    "#e6194b", "#3cb44b", "#4363d8", "#f58231", "#911eb4", "#42d4f4", "#f032e6", "#9a6324",
+];
+
+impl KarnaughMap {
+    pub const MAX_VARIABLES: usize = 6;
+
+    pub fn from_ast(expr: &ASTNode) -> Result<Self> {
+        Self::new(&QuineMcCluskey::new(expr))
+    }
+
+    /// The map of the function `minimizer` minimizes, don't-cares and all
+    pub fn new(minimizer: &QuineMcCluskey) -> Result<Self> {
+        let variables = minimizer.variables().to_vec();
+
+        if variables.len() > Self::MAX_VARIABLES {
+            return Err(EngineError::TooManyVariables {
+                count: variables.len(),
+                limit: Self::MAX_VARIABLES,
+            });
+        }
+
+        let (on, dont_care) = minimizer.table()?;
+        let cover = minimizer.cover()?;
+
+        let mut values = vec![Cell::False; 1 << variables.len()];
+
+        for (rows, value) in [(on, Cell::True), (dont_care, Cell::DontCare)] {
+            for row in rows {
+                values[row as usize] = value;
+            }
+        }
+
+        let groups = minimizer
+            .prime_implicants()?
+            .into_iter()
+            .map(|implicant| KarnaughGroup {
+                implicant,
+                in_cover: cover.contains(&implicant),
+            })
+            .collect();
+
+        let mut map = KarnaughMap {
+            row_variables: variables.len() / 2,
+            variables,
+            cells: Vec::new(),
+            groups,
+        };
+
+        map.cells = (0..map.rows())
+            .flat_map(|row| (0..map.columns()).map(move |column| (row, column)))
+            .map(|(row, column)| values[map.table_row(row, column) as usize])
+            .collect();
+
+        Ok(map)
+    }
+
+    pub fn variables(&self) -> &[Symbol] {
+        &self.variables
+    }
+
+    /// The variables picking the row, the first being the leftmost digit of
+    /// each label
+    pub fn row_variables(&self) -> &[Symbol] {
+        &self.variables[..self.row_variables]
+    }
+
+    /// The variables picking the column
+    pub fn column_variables(&self) -> &[Symbol] {
+        &self.variables[self.row_variables..]
+    }
+
+    pub fn rows(&self) -> usize {
+        1 << self.row_variables().len()
+    }
+
+    pub fn columns(&self) -> usize {
+        1 << self.column_variables().len()
+    }
+
+    /// The values of the row variables in row `row`, like `01`
+    pub fn row_label(&self, row: usize) -> String {
+        gray_label(row, self.row_variables().len())
+    }
+
+    /// The values of the column variables in column `column`
+    pub fn column_label(&self, column: usize) -> String {
+        gray_label(column, self.column_variables().len())
+    }
+
+    /// The row of the truth table a cell stands for, numbered in
+    /// [binary order](crate::Order::Binary) over [KarnaughMap::variables]
+    pub fn table_row(&self, row: usize, column: usize) -> u64 {
+        let (rows, columns) = (self.row_variables().len(), self.column_variables().len());
+
+        // the first variable of each axis is the top bit of its Gray code
+        let row_bits = (0..rows).map(|i| ((gray(row) >> (rows - 1 - i)) & 1) << i);
+        let column_bits =
+            (0..columns).map(|i| ((gray(column) >> (columns - 1 - i)) & 1) << (rows + i));
+
+        row_bits.chain(column_bits).sum::<usize>() as u64
+    }
+
+    pub fn cell(&self, row: usize, column: usize) -> Cell {
+        self.cells[row * self.columns() + column]
+    }
+
+    pub fn groups(&self) -> &[KarnaughGroup] {
+        &self.groups
+    }
+
+    /// The cells in a group, as `(row, column)`
+    pub fn group_cells(&self, group: &KarnaughGroup) -> Vec<(usize, usize)> {
+        (0..self.rows())
+            .flat_map(|row| (0..self.columns()).map(move |column| (row, column)))
+            .filter(|(row, column)| group.implicant.covers(self.table_row(*row, *column)))
+            .collect()
+    }
+
+    /// The group as a conjunction of literals
+    pub fn group_ast(&self, group: &KarnaughGroup) -> ASTNode {
+        NormalForm {
+            form: Form::Disjunctive,
+            terms: vec![group.implicant.literals(&self.variables)],
+        }
+        .to_ast()
+    }
+
+    /// A short name for the `index`th group: `A` to `Z`, then `AA` and so
+    /// on
+    pub fn group_name(index: usize) -> String {
+        let letter = |i: usize| char::from(b'A' + (i % 26) as u8);
+
+        match index {
+            0..26 => letter(index).to_string(),
+            _ => format!("{}{}", Self::group_name(index / 26 - 1), letter(index)),
+        }
+    }
+
+    /// The map as text. Each cell shows its value and the names of the
+    /// groups containing it, which are listed underneath with a `*` on the
+    /// ones in the minimal cover.
+    ///
+    /// ```text
+    /// a \ b c | 00  01   11    10
+    /// --------+-------------------
+    ///       0 | 0   1 B  1 B   0
+    ///       1 | 0   1 B  1 AB  1 A
+    ///
+    /// A* a && b
+    /// B* c
+    /// ```
+    pub fn to_ascii(&self) -> String {
+        let names = |vars: &[Symbol]| {
+            vars.iter()
+                .map(|var| var.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        let corner = format!(
+            "{} \\ {}",
+            names(self.row_variables()),
+            names(self.column_variables())
+        );
+
+        let mut text = vec![vec![String::new(); self.columns()]; self.rows()];
+
+        for (row, texts) in text.iter_mut().enumerate() {
+            for (column, cell_text) in texts.iter_mut().enumerate() {
+                cell_text.push(self.cell(row, column).symbol());
+            }
+        }
+
+        for (index, group) in self.groups.iter().enumerate() {
+            for (row, column) in self.group_cells(group) {
+                let cell_text = &mut text[row][column];
+
+                if cell_text.len() == 1 {
+                    cell_text.push(' ');
+                }
+
+                cell_text.push_str(&Self::group_name(index));
+            }
+        }
+
+        let widths = (0..self.columns())
+            .map(|column| {
+                let cells = text.iter().map(|row| row[column].len());
+                cells
+                    .chain([self.column_label(column).len()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect::<Vec<_>>();
+        let label_width = corner.len().max(self.row_variables().len());
+
+        let line = |label: &str, cells: Vec<String>| {
+            let cells = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:width$}"))
+                .collect::<Vec<_>>()
+                .join("  ");
+
+            format!("{label:>label_width$} | {cells}")
+                .trim_end()
+                .to_string()
+                + "\n"
+        };
+
+        let mut out = line(
+            &corner,
+            (0..self.columns()).map(|c| self.column_label(c)).collect(),
+        );
+
+        let table_width = widths.iter().sum::<usize>() + 2 * widths.len().saturating_sub(1);
+        writeln!(
+            out,
+            "{}-+-{}",
+            "-".repeat(label_width),
+            "-".repeat(table_width)
+        )
+        .unwrap();
+
+        for (row, cells) in text.into_iter().enumerate() {
+            out.push_str(&line(&self.row_label(row), cells));
+        }
+
+        if !self.groups.is_empty() {
+            out.push('\n');
+        }
+
+        for (index, group) in self.groups.iter().enumerate() {
+            let mark = if group.in_cover { '*' } else { ' ' };
+
+            writeln!(
+                out,
+                "{}{mark} {}",
+                Self::group_name(index),
+                self.group_ast(group)
+            )
+            .unwrap();
+        }
+
+        out
+    }
+
+    /// The map as an SVG image, with a coloured outline round each group.
+    /// Groups outside the minimal cover are dashed.
+    pub fn to_svg(&self) -> String {
+        const CELL: usize = 48;
+        const LINE: usize = 20;
+
+        let label_chars = self.row_variables().len().max(
+            self.row_variables()
+                .iter()
+                .map(|var| var.as_str().len() + 1)
+                .sum(),
+        );
+        let left = 24 + 9 * label_chars;
+        let top = 64;
+
+        let grid_width = self.columns() * CELL;
+        let grid_height = self.rows() * CELL;
+        let width = left + grid_width + 16;
+        let height = top + grid_height + 24 + LINE * self.groups.len();
+
+        let names = |vars: &[Symbol]| {
+            let names = vars.iter().map(|var| var.as_str()).collect::<Vec<_>>();
+            escape(&names.join(" "))
+        };
+
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+             viewBox=\"0 0 {width} {height}\" font-family=\"monospace\" font-size=\"14\">\n"
+        );
+
+        // axis titles and labels
+        writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+            left + grid_width / 2,
+            top - 36,
+            names(self.column_variables())
+        )
+        .unwrap();
+        writeln!(
+            svg,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>",
+            left - 8,
+            top - 12,
+            names(self.row_variables())
+        )
+        .unwrap();
+
+        for column in 0..self.columns() {
+            writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+                left + column * CELL + CELL / 2,
+                top - 12,
+                self.column_label(column)
+            )
+            .unwrap();
+        }
+
+        for row in 0..self.rows() {
+            writeln!(
+                svg,
+                "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>",
+                left - 8,
+                top + row * CELL + CELL / 2 + 5,
+                self.row_label(row)
+            )
+            .unwrap();
+        }
+
+        // the cells
+        for row in 0..self.rows() {
+            for column in 0..self.columns() {
+                let (x, y) = (left + column * CELL, top + row * CELL);
+
+                writeln!(
+                    svg,
+                    "<rect x=\"{x}\" y=\"{y}\" width=\"{CELL}\" height=\"{CELL}\" fill=\"none\" stroke=\"#888\"/>\
+                     <text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+                    x + CELL / 2,
+                    y + CELL / 2 + 5,
+                    self.cell(row, column).symbol()
+                )
+                .unwrap();
+            }
+        }
+
+        // the groups, each a little further inside the cells than the last
+        // so that overlapping outlines stay visible
+        for (index, group) in self.groups.iter().enumerate() {
+            let colour = PALETTE[index % PALETTE.len()];
+            let inset = 4 + 3 * (index % 4);
+            let dash = if group.in_cover {
+                ""
+            } else {
+                " stroke-dasharray=\"4 3\""
+            };
+
+            for (rows, columns) in self.group_rectangles(group) {
+                writeln!(
+                    svg,
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"10\" fill=\"none\" \
+                     stroke=\"{colour}\" stroke-width=\"2\"{dash}/>",
+                    left + columns.start * CELL + inset,
+                    top + rows.start * CELL + inset,
+                    columns.len() * CELL - 2 * inset,
+                    rows.len() * CELL - 2 * inset,
+                )
+                .unwrap();
+            }
+
+            writeln!(
+                svg,
+                "<text x=\"16\" y=\"{}\" fill=\"{colour}\">{}{} {}</text>",
+                top + grid_height + 24 + LINE * index,
+                Self::group_name(index),
+                if group.in_cover { "*" } else { "" },
+                escape(&self.group_ast(group).to_string())
+            )
+            .unwrap();
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// The blocks of neighbouring cells making up a group, as ranges of
+    /// rows and columns. Groups wrapping round an edge come in pieces.
+    fn group_rectangles(&self, group: &KarnaughGroup) -> Vec<(Range<usize>, Range<usize>)> {
+        let cells = self.group_cells(group);
+
+        // a group is always every combination of some rows and columns
+        let mut rows = cells.iter().map(|(row, _)| *row).collect::<Vec<_>>();
+        let mut columns = cells.iter().map(|(_, column)| *column).collect::<Vec<_>>();
+
+        for indices in [&mut rows, &mut columns] {
+            indices.sort();
+            indices.dedup();
+        }
+
+        let rows = runs(&rows);
+        let columns = runs(&columns);
+
+        rows.iter()
+            .flat_map(|rows| {
+                columns
+                    .iter()
+                    .map(move |columns| (rows.clone(), columns.clone()))
+            })
+            .collect()
+    }
+}
+
+/// The `index`th Gray code
+fn gray(index: usize) -> usize {
+    index ^ (index >> 1)
+}
+
+fn gray_label(index: usize, bits: usize) -> String {
+    match bits {
+        0 => String::new(),
+        _ => format!("{:0bits$b}", gray(index)),
+    }
+}
+
+/// Splits sorted indices into ranges of consecutive ones
+fn runs(indices: &[usize]) -> Vec<Range<usize>> {
+    let mut runs: Vec<Range<usize>> = Vec::new();
+
+    for &index in indices {
+        match runs.last_mut() {
+            Some(run) if run.end == index => run.end += 1,
+            _ => runs.push(index..index + 1),
+        }
+    }
+
+    runs
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Engine;
+
+    fn map(input: &str) -> KarnaughMap {
+        KarnaughMap::from_ast(&Engine::default().parse(input).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_layout() {
+        let map = map("a && b && !c || d");
+
+        assert_eq!(map.rows(), 4);
+        assert_eq!(map.columns(), 4);
+        assert_eq!(
+            (0..4).map(|row| map.row_label(row)).collect::<Vec<_>>(),
+            ["00", "01", "11", "10"]
+        );
+
+        // neighbouring cells differ in exactly one variable, wrapping round
+        for row in 0..4 {
+            for column in 0..4 {
+                let here = map.table_row(row, column);
+
+                for (r, c) in [((row + 1) % 4, column), (row, (column + 1) % 4)] {
+                    assert_eq!((here ^ map.table_row(r, c)).count_ones(), 1);
+                }
+            }
+        }
+
+        // a = 1, b = 1, c = 0, d = 0
+        assert_eq!(map.table_row(2, 0), 0b0011);
+        assert_eq!(map.cell(2, 0), Cell::True);
+        assert_eq!(map.cell(0, 0), Cell::False);
+    }
+
+    #[test]
+    fn test_ascii() {
+        assert_eq!(
+            map("a && b || c").to_ascii(),
+            "a \\ b c | 00  01   11    10\n\
+             --------+-------------------\n      \
+             0 | 0   1 B  1 B   0\n      \
+             1 | 0   1 B  1 AB  1 A\n\
+             \n\
+             A* a && b\n\
+             B* c\n"
+        );
+    }
+
+    #[test]
+    fn test_groups() {
+        let engine = Engine::default();
+
+        // the corners of a four variable map are one group
+        let expr = engine.parse("(a || !a) && !b && (c || !c) && !d").unwrap();
+        let map = KarnaughMap::from_ast(&expr).unwrap();
+        assert_eq!(map.groups().len(), 1);
+        assert_eq!(map.group_ast(&map.groups()[0]).to_string(), "!b && !d");
+        assert_eq!(
+            map.group_cells(&map.groups()[0]),
+            [(0, 0), (0, 3), (3, 0), (3, 3)]
+        );
+        assert_eq!(map.group_rectangles(&map.groups()[0]).len(), 4);
+
+        // and don't-cares join groups without needing covering
+        let minimizer = QuineMcCluskey::new(&engine.parse("a && b").unwrap())
+            .dont_care(&engine.parse("a && !b").unwrap());
+        let map = KarnaughMap::new(&minimizer).unwrap();
+
+        assert_eq!(map.cell(1, 0), Cell::DontCare);
+        assert_eq!(map.groups().len(), 1);
+        assert_eq!(map.group_ast(&map.groups()[0]).to_string(), "a");
+    }
+
+    #[test]
+    fn test_svg() {
+        let svg = map("a && b || !a && !b && c").to_svg();
+
+        assert!(svg.starts_with("<svg "));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains("a &amp;&amp; b"));
+        assert_eq!(svg.matches("stroke-width=\"2\"").count(), 2);
+    }
+
+    #[test]
+    fn test_limits() {
+        let map = map("a && b && c && d && e && f");
+        assert_eq!((map.rows(), map.columns()), (8, 8));
+
+        assert_eq!(
+            KarnaughMap::from_ast(
+                &Engine::default()
+                    .parse("a && b && c && d && e && f && g")
+                    .unwrap()
+            ),
+            Err(EngineError::TooManyVariables { count: 7, limit: 6 })
+        );
+
+        assert_eq!(KarnaughMap::group_name(25), "Z");
+        assert_eq!(KarnaughMap::group_name(26), "AA");
+        assert_eq!(KarnaughMap::group_name(27), "AB");
+    }
+}
@@ -1,156 +1,318 @@
-use std::{
-    collections::{HashMap, HashSet},
-    hash::{DefaultHasher, Hasher},
+use std::collections::HashSet;
+
+mod assignments;
+mod bdd;
+mod bitparallel;
+mod bytecode;
+mod cube;
+mod decider;
+mod derivation;
+mod document;
+mod enumerate;
+mod error;
+mod espresso;
+mod karnaugh;
+mod normal;
+#[cfg(feature = "parallel")]
+mod parallel;
+mod pla;
+mod qm;
+mod rewrite;
+mod sat;
+mod table;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod tseitin;
+
+pub use assignments::Assignments;
+pub use bdd::{BddManager, BddRef, BinaryOp};
+pub use bitparallel::{BLOCK_ROWS, BlockEvaluator, TruthTableIter};
+pub use bytecode::{Instruction, Program, Word};
+pub use cube::Cube;
+pub use decider::{Auto, Bdd, Decider, Sat, TruthTable};
+pub use derivation::{Derivation, Step};
+pub use document::{DocumentReport, Goal, Source};
+pub use enumerate::{AssignmentIter, Order};
+pub use error::{EngineError, Result};
+pub use karnaugh::{Cell, KarnaughGroup, KarnaughMap};
+pub use normal::{Form, Literal, NormalForm, to_cnf, to_dnf, to_nnf};
+pub use pla::{Pla, PlaTerm};
+pub use qm::QuineMcCluskey;
+pub use rewrite::{Rewriter, Rule, size};
+pub use russell_ast::{
+    ASTNode, Document, Formula, NodeId, Notation, Span, SpanTable, Statement, StatementKind, Symbol,
 };
+pub use russell_parser::{Level, ParseError, ParseErrors, Report, line_column};
+use russell_parser::{parse, parse_document, parse_spanned};
+pub use sat::{Lit, Solver, Stats as SolverStats, Var};
+pub use table::{Table, TableBuilder, TableFormat, ValueStyle};
+pub use tseitin::Cnf;
+
+/// Returns every variable in `expr`, without duplicates, in the order
+/// they first appear. Unlike most of what an [Engine] does, this doesn't
+/// depend on how it's configured.
+pub fn collect_variables(expr: &ASTNode) -> Vec<Symbol> {
+    fn walk(expr: &ASTNode, seen: &mut HashSet<Symbol>, vars: &mut Vec<Symbol>) {
+        match expr {
+            ASTNode::Variable(symbol) => {
+                if seen.insert(*symbol) {
+                    vars.push(*symbol);
+                }
+            }
 
-use anyhow::anyhow;
-use russell_ast::ASTNode;
-use russell_parser::parse;
+            ASTNode::Literal(_) => {}
 
-#[derive(Default, PartialEq, Eq, Serialize, Deserialize)]
-pub struct Assignments(HashMap<char, bool>);
+            ASTNode::Not(node) | ASTNode::Paren(node) => walk(node, seen, vars),
 
-impl std::hash::Hash for Assignments {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        for (&ch, &value) in &self.0 {
-            let mut hasher = DefaultHasher::new();
+            ASTNode::And(p, q)
+            | ASTNode::Or(p, q)
+            | ASTNode::Implies(p, q)
+            | ASTNode::Equivalent(p, q) => {
+                walk(p, seen, vars);
+                walk(q, seen, vars);
+            }
+        }
+    }
 
-            (ch, value).hash(&mut hasher);
+    let mut vars = Vec::new();
+    walk(expr, &mut HashSet::new(), &mut vars);
 
-            state.write_u64(hasher.finish());
+    vars
+}
 
-            // NOTE: another possible solution would be to ignore char and
-            // simply assign each entry a bit position and write the value to
-            // that position, using bit shifting
-            //
-            // this has collisions, i tested it !
-        }
+#[derive(Debug)]
+pub struct Engine {
+    decider: Box<dyn Decider>,
+    rewriter: Rewriter,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Engine::builder().build()
     }
 }
 
-#[derive(Default, Debug)]
-pub struct Engine {}
+/// Configures an [Engine]
+#[derive(Debug)]
+pub struct EngineBuilder {
+    decider: Box<dyn Decider>,
+    rewriter: Rewriter,
+}
+
+impl EngineBuilder {
+    /// The procedure the engine decides tautologies, contradictions and
+    /// satisfiability with. Defaults to [Auto].
+    pub fn decider(mut self, decider: impl Decider + 'static) -> Self {
+        self.decider = Box::new(decider);
+        self
+    }
+
+    /// The rewriter [Engine::simplify] uses. Defaults to
+    /// [Rewriter::builtin].
+    pub fn rewriter(mut self, rewriter: Rewriter) -> Self {
+        self.rewriter = rewriter;
+        self
+    }
+
+    pub fn build(self) -> Engine {
+        Engine {
+            decider: self.decider,
+            rewriter: self.rewriter,
+        }
+    }
+}
 
 impl Engine {
-    pub fn parse(&self, input: &str) -> anyhow::Result<ASTNode> {
+    pub fn builder() -> EngineBuilder {
+        EngineBuilder {
+            decider: Box::new(Auto::default()),
+            rewriter: Rewriter::builtin(),
+        }
+    }
+
+    pub fn decider(&self) -> &dyn Decider {
+        self.decider.as_ref()
+    }
+
+    /// The most variables an expression can have for us to enumerate its
+    /// truth table
+    pub const MAX_VARIABLES: usize = 32;
+
+    /// A reasonable limit on the terms [to_cnf] and [to_dnf] may build
+    pub const MAX_NORMAL_FORM_TERMS: usize = 4096;
+
+    pub fn parse(&self, input: &str) -> Result<ASTNode, ParseErrors> {
         parse(input)
     }
 
-    pub fn eval_str(&self, input: &str, assignments: &Assignments) -> anyhow::Result<bool> {
-        self.eval(&self.parse(input)?, assignments)
+    pub fn parse_spanned(&self, input: &str) -> Result<(ASTNode, SpanTable), ParseErrors> {
+        parse_spanned(input)
+    }
+
+    /// Parses `input` like [Engine::parse_spanned], but explains when it
+    /// fails because it's a [Document] with statements rather than a single
+    /// formula
+    pub fn parse_formula(&self, input: &str) -> Result<(ASTNode, SpanTable)> {
+        let errors = match parse_spanned(input) {
+            Ok(parsed) => return Ok(parsed),
+            Err(errors) => errors,
+        };
+
+        let Ok(document) = parse_document(input) else {
+            return Err(errors.into());
+        };
+
+        let extra = match &document.statements[..] {
+            [first, second, ..] if matches!(first.kind, StatementKind::Formula(_)) => second,
+            [first, ..] => first,
+            [] => return Err(errors.into()),
+        };
+
+        Err(EngineError::NotAFormula {
+            span: Some(extra.span.clone()),
+        })
+    }
+
+    /// Parses a whole `.rsl` file. See [Document].
+    pub fn parse_document(&self, input: &str) -> Result<Document, ParseErrors> {
+        parse_document(input)
+    }
+
+    pub fn eval_str(&self, input: &str, assignments: &Assignments) -> Result<bool> {
+        let (expr, spans) = self.parse_spanned(input)?;
+
+        self.eval(&expr, assignments)
+            .map_err(|e| e.locate(&expr, &spans))
     }
 
-    pub fn eval(&self, expr: &ASTNode, assignments: &Assignments) -> anyhow::Result<bool> {
+    pub fn eval(&self, expr: &ASTNode, assignments: &Assignments) -> Result<bool> {
         match expr {
-            ASTNode::Variable(symbol) => match assignments.0.get(&symbol) {
-                Some(val) => Ok(*val),
-                None => Err(anyhow!("")),
+            ASTNode::Variable(symbol) => match assignments.get(*symbol) {
+                Some(val) => Ok(val),
+                None => Err(EngineError::UnboundVariable {
+                    name: *symbol,
+                    span: None,
+                }),
             },
             ASTNode::Literal(value) => Ok(*value),
-            ASTNode::Not(node) => Ok(!self.eval(&node, assignments)?),
-            ASTNode::And(p, q) => Ok(self.eval(&p, assignments)? && self.eval(&q, assignments)?),
-            ASTNode::Or(p, q) => Ok(self.eval(&p, assignments)? || self.eval(&q, assignments)?),
-            ASTNode::Implies(p, q) => {
-                Ok(!self.eval(&p, assignments)? || self.eval(&q, assignments)?)
-            }
+            ASTNode::Not(node) => Ok(!self.eval(node, assignments)?),
+            ASTNode::And(p, q) => Ok(self.eval(p, assignments)? && self.eval(q, assignments)?),
+            ASTNode::Or(p, q) => Ok(self.eval(p, assignments)? || self.eval(q, assignments)?),
+            ASTNode::Implies(p, q) => Ok(!self.eval(p, assignments)? || self.eval(q, assignments)?),
             ASTNode::Equivalent(p, q) => {
-                Ok(self.eval(&p, assignments)? == self.eval(&q, assignments)?)
+                Ok(self.eval(p, assignments)? == self.eval(q, assignments)?)
             }
-            ASTNode::Paren(inner) => self.eval(&inner, assignments),
+            ASTNode::Paren(inner) => self.eval(inner, assignments),
         }
     }
 
-    pub fn collect_variables(&self, expr: &ASTNode) -> Vec<char> {
-        let vars = match expr {
-            ASTNode::Variable(symbol) => vec![*symbol],
-
-            ASTNode::Literal(_) => vec![],
-
-            ASTNode::Not(node) => self.collect_variables(node),
-
-            ASTNode::And(p, q) => [self.collect_variables(p), self.collect_variables(q)].concat(),
+    /// Returns every variable in `expr`, without duplicates, in the order
+    /// they first appear. The same as [collect_variables], for callers with
+    /// an engine at hand.
+    pub fn collect_variables(&self, expr: &ASTNode) -> Vec<Symbol> {
+        collect_variables(expr)
+    }
 
-            ASTNode::Or(p, q) => [self.collect_variables(p), self.collect_variables(q)].concat(),
+    /// Lazily enumerates every assignment of `variables` in the given
+    /// [Order]. See [AssignmentIter].
+    pub fn enumerate_assignments(
+        &self,
+        variables: Vec<Symbol>,
+        order: Order,
+    ) -> Result<AssignmentIter> {
+        if variables.len() > Self::MAX_VARIABLES {
+            return Err(EngineError::TooManyVariables {
+                count: variables.len(),
+                limit: Self::MAX_VARIABLES,
+            });
+        }
 
-            ASTNode::Implies(p, q) => {
-                [self.collect_variables(p), self.collect_variables(q)].concat()
-            }
+        Ok(AssignmentIter::new(variables, order))
+    }
 
-            ASTNode::Equivalent(p, q) => {
-                [self.collect_variables(p), self.collect_variables(q)].concat()
-            }
+    pub fn check_tautology(&self, expr: &ASTNode) -> Result<bool> {
+        Ok(self.find_counterexample(expr)?.is_none())
+    }
 
-            ASTNode::Paren(node) => self.collect_variables(node),
-        };
+    pub fn check_contradiction(&self, expr: &ASTNode) -> Result<bool> {
+        Ok(self.find_witness(expr)?.is_none())
+    }
 
-        // NOTE: this is one of the worst things I've ever written
-        vars.iter()
-            .map(|x| *x)
-            .collect::<HashSet<char>>()
-            .iter()
-            .map(|x| *x)
-            .collect::<Vec<char>>()
+    /// Whether there's any assignment under which `expr` is true
+    pub fn check_satisfiable(&self, expr: &ASTNode) -> Result<bool> {
+        Ok(self.find_witness(expr)?.is_some())
     }
 
-    pub fn compute_assignments(&self, variables: Vec<char>) -> Vec<Assignments> {
-        // this is how many rows our truth table of sorts will have
-        let rows = usize::pow(2, variables.len() as u32);
+    /// Whether `p` and `q` have the same value under every assignment
+    pub fn check_equivalent(&self, p: &ASTNode, q: &ASTNode) -> Result<bool> {
+        let equivalence = ASTNode::Equivalent(Box::new(p.clone()), Box::new(q.clone()));
 
-        (0..rows)
-            .map(|i| {
-                // 'i' is a number which if we perform certain bitwise
-                // operations on, will give us the truth values for each
-                // variable
-                let mut assignments = Assignments::default();
+        self.check_tautology(&equivalence)
+    }
 
-                let mut pos = 0;
+    /// Finds an assignment under which `expr` is false, which shows it isn't
+    /// a tautology. Returns [None] if it is one.
+    pub fn find_counterexample(&self, expr: &ASTNode) -> Result<Option<Assignments>> {
+        self.find_assignment(expr, false)
+    }
 
-                for var in &variables {
-                    // we extract the bit on the nth position (where n is the
-                    // position we're currently in, and turn it into a boolean
-                    // by checking if it's 1
-                    let value = (i >> pos) & 1 == 1;
+    /// Finds an assignment under which `expr` is true, which shows it isn't
+    /// a contradiction. Returns [None] if it is one.
+    pub fn find_witness(&self, expr: &ASTNode) -> Result<Option<Assignments>> {
+        self.find_assignment(expr, true)
+    }
 
-                    assignments.0.insert(*var, value);
+    fn find_assignment(&self, expr: &ASTNode, value: bool) -> Result<Option<Assignments>> {
+        self.decider.find_assignment(expr, value)
+    }
 
-                    pos += 1
-                }
+    /// Counts the assignments under which `expr` is true
+    pub fn count_models(&self, expr: &ASTNode) -> Result<u128> {
+        self.decider.count_models(expr)
+    }
 
-                assignments
-            })
-            .collect::<Vec<Assignments>>()
+    /// Compiles `expr` to a [Program], for when it will be evaluated many
+    /// times
+    pub fn compile(&self, expr: &ASTNode) -> Program {
+        Program::compile(expr)
     }
 
-    pub fn check_tautology(&self, expr: &ASTNode) -> anyhow::Result<bool> {
-        let variables: Vec<char> = self.collect_variables(&expr);
-        let assignments = self.compute_assignments(variables);
+    /// Iterates over the truth table of `expr`, in [binary order](Order::Binary)
+    pub fn truth_table(&self, expr: &ASTNode) -> Result<TruthTableIter> {
+        Ok(TruthTableIter::new(TruthTable::evaluator(expr)?))
+    }
 
-        for assignments in assignments {
-            // NOTE: cloning -- bad
-            if !self.eval(&expr, &assignments)? {
-                return Ok(false);
-            }
-        }
+    /// Rewrites `expr` into a simpler equivalent expression. See [Rewriter].
+    pub fn simplify(&self, expr: &ASTNode) -> ASTNode {
+        self.rewriter.simplify(expr)
+    }
 
-        Ok(true)
+    /// The smallest sum of products equivalent to `expr`. See
+    /// [QuineMcCluskey] for don't-cares.
+    pub fn minimize(&self, expr: &ASTNode) -> Result<ASTNode> {
+        Ok(QuineMcCluskey::new(expr).minimize()?.to_ast())
     }
 
-    pub fn check_contradiction(&self, expr: &ASTNode) -> anyhow::Result<bool> {
-        let variables: Vec<char> = self.collect_variables(&expr);
-        let assignments = self.compute_assignments(variables);
+    /// The Karnaugh map of `expr`, which can have at most
+    /// [KarnaughMap::MAX_VARIABLES] variables
+    pub fn karnaugh_map(&self, expr: &ASTNode) -> Result<KarnaughMap> {
+        KarnaughMap::from_ast(expr)
+    }
 
-        for assignments in assignments {
-            // NOTE: cloning -- bad
-            if self.eval(&expr, &assignments)? {
-                return Ok(false);
-            }
-        }
+    /// Simplifies `expr` like [Engine::simplify], recording each law used.
+    /// See [Rewriter::derive].
+    pub fn derive(&self, expr: &ASTNode) -> Derivation {
+        self.rewriter.derive(expr)
+    }
 
-        Ok(true)
+    /// Looks for a derivation from `p` to `q` using the rewriter's laws.
+    /// See [Rewriter::prove_equivalent].
+    pub fn prove_equivalent(&self, p: &ASTNode, q: &ASTNode) -> Option<Derivation> {
+        self.rewriter.prove_equivalent(p, q)
     }
 
-    pub fn check_contingency(&self, expr: &ASTNode) -> anyhow::Result<bool> {
-        Ok(!self.check_tautology(&expr)? && !self.check_contradiction(&expr)?)
+    pub fn check_contingency(&self, expr: &ASTNode) -> Result<bool> {
+        Ok(!self.check_tautology(expr)? && !self.check_contradiction(expr)?)
     }
 }
 
@@ -169,7 +331,10 @@ mod tests {
         );
         actual.sort();
 
-        let expected = vec!['a', 'b', 'c', 'd', 'e', 'f', 'g'];
+        let expected: Vec<Symbol> = ["a", "b", "c", "d", "e", "f", "g"]
+            .into_iter()
+            .map(Symbol::intern)
+            .collect();
 
         assert_eq!(actual, expected);
     }
@@ -245,51 +410,257 @@ mod tests {
         assert!(!engine.check_contingency(&expr).unwrap());
     }
 
-    // this fails!
-    // #[test]
-    // fn test_assignments_hashing() {
-    //     let mut hashes: HashSet<u64> = HashSet::default();
-
-    //     for a in 'a'..='z' {
-    //         for b in 'a'..='z' {
-    //             for c in 'a'..='z' {
-    //                 // no duplicate keys
-    //                 if a == b || a == c || b == c {
-    //                     continue;
-    //                 }
-
-    //                 for av in 0..=1 {
-    //                     for bv in 0..=1 {
-    //                         for cv in 0..=1 {
-    //                             // populate truth table
-    //                             let mut assignments = Assignments::default();
-
-    //                             assignments.0.insert(a, av == 1);
-    //                             assignments.0.insert(b, bv == 1);
-    //                             assignments.0.insert(c, cv == 1);
-
-    //                             // hash truth table
-    //                             let mut hasher = DefaultHasher::default();
-    //                             assignments.hash(&mut hasher);
-
-    //                             let hash = hasher.finish();
-
-    //                             // ensure that the hash is unique (since the truth table is unique too)
-    //                             assert!(!hashes.contains(&hash));
-
-    //                             hashes.insert(hash);
-    //                         }
-    //                     }
-    //                 }
-    //             }
-    //         }
-    //     }
-    // }
+    #[test]
+    fn test_collect_variables_multi_character() {
+        let engine = Engine::default();
+
+        let expr = engine
+            .parse("door_open && !alarm_armed => door_open || alarm")
+            .unwrap();
+
+        let expected: Vec<Symbol> = ["door_open", "alarm_armed", "alarm"]
+            .into_iter()
+            .map(Symbol::intern)
+            .collect();
+
+        assert_eq!(engine.collect_variables(&expr), expected);
+        assert_eq!(collect_variables(&expr), expected);
+    }
+
+    #[test]
+    fn test_eval_multi_character() {
+        let engine = Engine::default();
+
+        let assignments: Assignments = [
+            (Symbol::intern("door_open"), true),
+            (Symbol::intern("alarm_armed"), false),
+        ]
+        .into_iter()
+        .collect();
+
+        assert!(
+            engine
+                .eval_str("door_open && !alarm_armed", &assignments)
+                .unwrap()
+        );
+        assert!(engine.eval_str("door_open && alarm", &assignments).is_err());
+    }
+
+    #[test]
+    fn test_counterexample() {
+        let engine = Engine::default();
+
+        // p => q only fails when p is true and q is false
+        let expr = engine.parse("p => q").unwrap();
+        let counterexample = engine.find_counterexample(&expr).unwrap().unwrap();
+
+        assert_eq!(counterexample.get(Symbol::intern("p")), Some(true));
+        assert_eq!(counterexample.get(Symbol::intern("q")), Some(false));
+        assert!(!engine.eval(&expr, &counterexample).unwrap());
+
+        // tautologies have no counterexample
+        let expr = engine.parse("p || !p").unwrap();
+        assert_eq!(engine.find_counterexample(&expr).unwrap(), None);
+    }
+
+    #[test]
+    fn test_witness() {
+        let engine = Engine::default();
+
+        let expr = engine.parse("!p && q").unwrap();
+        let witness = engine.find_witness(&expr).unwrap().unwrap();
+
+        assert_eq!(witness.to_string(), "p = false, q = true");
+
+        // contradictions have no witness
+        let expr = engine.parse("p && !p").unwrap();
+        assert_eq!(engine.find_witness(&expr).unwrap(), None);
+    }
+
+    #[test]
+    fn test_early_exit_on_large_formula() {
+        let engine = Engine::default();
+
+        // 2^32 rows would never fit in memory, but the very first row is a
+        // counterexample so we never look at the rest
+        let input = testing::joined(Engine::MAX_VARIABLES, "&&");
+
+        assert!(
+            !engine
+                .check_tautology(&engine.parse(&input).unwrap())
+                .unwrap()
+        );
+    }
+
+    /// `(x0 => x1) && (x1 => x2) && ... => (x0 => xn)`, a tautology with
+    /// far too many variables for a truth table
+    fn implication_chain(n: usize) -> String {
+        let links = (0..n)
+            .map(|i| format!("(x{i} => x{})", i + 1))
+            .collect::<Vec<_>>()
+            .join(" && ");
+
+        format!("{links} => (x0 => x{n})")
+    }
+
+    #[test]
+    fn test_tautology_with_thousands_of_variables() {
+        let engine = Engine::default();
+
+        let expr = engine.parse(&implication_chain(2000)).unwrap();
+        assert!(engine.check_tautology(&expr).unwrap());
+        assert!(engine.check_satisfiable(&expr).unwrap());
+
+        // the other way around can fail, by breaking the chain anywhere
+        let input = implication_chain(2000).replace("=> (x0 => x2000)", "=> (x2000 => x0)");
+        let expr = engine.parse(&input).unwrap();
+        let counterexample = engine.find_counterexample(&expr).unwrap().unwrap();
+
+        assert_eq!(counterexample.len(), 2001);
+        assert!(!engine.compile(&expr).eval(&counterexample).unwrap());
+    }
+
+    #[test]
+    fn test_contradiction_with_thousands_of_variables() {
+        let engine = Engine::default();
+
+        let expr = engine
+            .parse(&format!("!({})", implication_chain(1000)))
+            .unwrap();
+
+        assert!(engine.check_contradiction(&expr).unwrap());
+        assert!(!engine.check_satisfiable(&expr).unwrap());
+    }
+
+    #[test]
+    fn test_count_models() {
+        let engine = Engine::default();
+
+        let count = |input| engine.count_models(&engine.parse(input).unwrap()).unwrap();
+
+        assert_eq!(count("p || !p"), 2);
+        assert_eq!(count("p && !p"), 0);
+        assert_eq!(count("p && q"), 1);
+        assert_eq!(count("p || q || r"), 7);
+
+        // large enough to be split across threads with the parallel feature
+        let input = testing::joined(20, "||");
+
+        assert_eq!(count(&input), (1 << 20) - 1);
+    }
+
+    #[test]
+    fn test_unbound_variable_error() {
+        let engine = Engine::default();
+        let assignments: Assignments = [(Symbol::intern("p"), true)].into_iter().collect();
+
+        assert_eq!(
+            engine.eval_str("p && (q || p)", &assignments),
+            Err(EngineError::UnboundVariable {
+                name: Symbol::intern("q"),
+                span: Some(6..7),
+            })
+        );
+
+        let expr = engine.parse("p && q").unwrap();
+        assert_eq!(
+            engine.eval(&expr, &assignments),
+            Err(EngineError::UnboundVariable {
+                name: Symbol::intern("q"),
+                span: None,
+            })
+        );
+
+        let error = engine.eval(&expr, &assignments).unwrap_err();
+        assert_eq!(error.kind(), "unbound_variable");
+    }
+
+    #[test]
+    fn test_parse_formula() {
+        let engine = Engine::default();
+
+        let (expr, _) = engine.parse_formula("p && /* both */ q").unwrap();
+        assert_eq!(expr, engine.parse("p && q").unwrap());
+
+        assert_eq!(
+            engine.parse_formula("let a = p\na").unwrap_err(),
+            EngineError::NotAFormula { span: Some(0..9) }
+        );
+        assert_eq!(
+            engine.parse_formula("p\nprove q").unwrap_err(),
+            EngineError::NotAFormula { span: Some(2..9) }
+        );
+        assert!(matches!(
+            engine.parse_formula("p &&"),
+            Err(EngineError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_too_many_variables() {
+        let engine = Engine::default();
+
+        let input = testing::joined(Engine::MAX_VARIABLES + 1, "||");
+
+        assert_eq!(
+            engine.truth_table(&engine.parse(&input).unwrap()).err(),
+            Some(EngineError::TooManyVariables {
+                count: Engine::MAX_VARIABLES + 1,
+                limit: Engine::MAX_VARIABLES,
+            })
+        );
+    }
+
+    #[test]
+    fn test_builder() {
+        let engine = Engine::builder().decider(Sat).build();
+        assert_eq!(engine.decider().name(), "sat");
+
+        let expr = engine.parse("(p => q) => (!q => !p)").unwrap();
+        assert!(engine.check_tautology(&expr).unwrap());
+
+        assert_eq!(Engine::default().decider().name(), "auto");
+    }
+
+    #[test]
+    fn test_truth_table_decider_limit() {
+        let engine = Engine::builder().decider(TruthTable).build();
+
+        let input = testing::joined(Engine::MAX_VARIABLES + 1, "||");
+
+        assert!(matches!(
+            engine.check_tautology(&engine.parse(&input).unwrap()),
+            Err(EngineError::TooManyVariables { .. })
+        ));
+    }
+
+    #[test]
+    fn test_equivalent() {
+        let engine = Engine::builder().decider(Bdd).build();
+
+        let p = engine.parse("!(a && b)").unwrap();
+        let q = engine.parse("!a || !b").unwrap();
+        assert!(engine.check_equivalent(&p, &q).unwrap());
+
+        let q = engine.parse("!a && !b").unwrap();
+        assert!(!engine.check_equivalent(&p, &q).unwrap());
+    }
+
+    #[test]
+    fn test_parse_error() {
+        let engine = Engine::default();
+
+        let error = engine
+            .eval_str("p &&", &Assignments::default())
+            .unwrap_err();
+
+        assert!(matches!(error, EngineError::Parse(_)));
+        assert_eq!(error.kind(), "parse");
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
 pub mod wasm;
 
-use serde::{Deserialize, Serialize};
 #[cfg(target_arch = "wasm32")]
 pub use wasm::*;
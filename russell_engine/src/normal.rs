@@ -0,0 +1,407 @@
+use std::{collections::HashSet, fmt};
+
+use russell_ast::{ASTNode, Symbol};
+
+use crate::{Cnf, EngineError, Result};
+
+/// A variable or its negation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Literal {
+    pub var: Symbol,
+    pub positive: bool,
+}
+
+impl Literal {
+    pub fn to_ast(self) -> ASTNode {
+        let var = ASTNode::Variable(self.var);
+
+        if self.positive {
+            var
+        } else {
+            ASTNode::Not(Box::new(var))
+        }
+    }
+
+    fn negate(self) -> Literal {
+        Literal {
+            positive: !self.positive,
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.positive {
+            f.write_str("!")?;
+        }
+
+        write!(f, "{}", self.var)
+    }
+}
+
+/// Which way round a [NormalForm] is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Form {
+    /// A conjunction of clauses, each a disjunction of literals
+    Conjunctive,
+
+    /// A disjunction of terms, each a conjunction of literals
+    Disjunctive,
+}
+
+/// An expression in conjunctive or disjunctive normal form.
+///
+/// Terms never contain a literal twice, or a literal and its negation, and
+/// no term appears twice. With no terms at all a CNF is true and a DNF is
+/// false, while an empty term makes a CNF false and a DNF true.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalForm {
+    pub form: Form,
+    pub terms: Vec<Vec<Literal>>,
+}
+
+impl NormalForm {
+    /// The equivalent expression
+    pub fn to_ast(&self) -> ASTNode {
+        let (outer, inner): (fn(_, _) -> _, fn(_, _) -> _) = match self.form {
+            Form::Conjunctive => (ASTNode::And, ASTNode::Or),
+            Form::Disjunctive => (ASTNode::Or, ASTNode::And),
+        };
+
+        // the identity of a conjunction is true, and of a disjunction false
+        let (outer_identity, inner_identity) = (
+            self.form == Form::Conjunctive,
+            self.form == Form::Disjunctive,
+        );
+
+        let terms = self.terms.iter().map(|term| {
+            let literals = term.iter().map(|lit| lit.to_ast());
+            join(literals, inner, inner_identity)
+        });
+
+        join(terms, outer, outer_identity)
+    }
+
+    /// The number of literals across every term
+    pub fn size(&self) -> usize {
+        self.terms.iter().map(Vec::len).sum()
+    }
+}
+
+/// Joins `nodes` with a left associative operator, giving `identity` if
+/// there aren't any
+fn join(
+    nodes: impl Iterator<Item = ASTNode>,
+    op: fn(Box<ASTNode>, Box<ASTNode>) -> ASTNode,
+    identity: bool,
+) -> ASTNode {
+    nodes
+        .reduce(|p, q| op(Box::new(p), Box::new(q)))
+        .unwrap_or(ASTNode::Literal(identity))
+}
+
+/// Converts `expr` to negation normal form: only `&&`, `||` and `!`, with
+/// every `!` directly on a variable. Implications and equivalences are
+/// expanded, which can double the size of an expression for every level of
+/// nested `==`.
+pub fn to_nnf(expr: &ASTNode) -> ASTNode {
+    nnf(expr, true)
+}
+
+/// The negation normal form of `expr` if `positive`, or of `!expr`
+/// otherwise
+fn nnf(expr: &ASTNode, positive: bool) -> ASTNode {
+    let and = |p, q| ASTNode::And(Box::new(p), Box::new(q));
+    let or = |p, q| ASTNode::Or(Box::new(p), Box::new(q));
+
+    match expr {
+        ASTNode::Variable(var) => Literal {
+            var: *var,
+            positive,
+        }
+        .to_ast(),
+        ASTNode::Literal(value) => ASTNode::Literal(*value == positive),
+        ASTNode::Not(inner) => nnf(inner, !positive),
+        ASTNode::Paren(inner) => nnf(inner, positive),
+
+        // De Morgan swaps the operator when negating
+        ASTNode::And(p, q) if positive => and(nnf(p, true), nnf(q, true)),
+        ASTNode::And(p, q) => or(nnf(p, false), nnf(q, false)),
+        ASTNode::Or(p, q) if positive => or(nnf(p, true), nnf(q, true)),
+        ASTNode::Or(p, q) => and(nnf(p, false), nnf(q, false)),
+
+        // p => q is !p || q, and its negation p && !q
+        ASTNode::Implies(p, q) if positive => or(nnf(p, false), nnf(q, true)),
+        ASTNode::Implies(p, q) => and(nnf(p, true), nnf(q, false)),
+
+        // p == q is (p && q) || (!p && !q), and its negation
+        // (p && !q) || (!p && q)
+        ASTNode::Equivalent(p, q) => or(
+            and(nnf(p, true), nnf(q, positive)),
+            and(nnf(p, false), nnf(q, !positive)),
+        ),
+    }
+}
+
+/// Converts `expr` to an equivalent CNF by distributing `||` over `&&`.
+/// This can grow exponentially, so it fails once there would be more than
+/// `limit` clauses. See [Cnf::tseitin] for an encoding that stays small.
+pub fn to_cnf(expr: &ASTNode, limit: usize) -> Result<NormalForm> {
+    Ok(NormalForm {
+        form: Form::Conjunctive,
+        terms: distribute(&to_nnf(expr), Form::Conjunctive, limit)?,
+    })
+}
+
+/// Converts `expr` to an equivalent DNF by distributing `&&` over `||`.
+/// This can grow exponentially, so it fails once there would be more than
+/// `limit` terms.
+pub fn to_dnf(expr: &ASTNode, limit: usize) -> Result<NormalForm> {
+    Ok(NormalForm {
+        form: Form::Disjunctive,
+        terms: distribute(&to_nnf(expr), Form::Disjunctive, limit)?,
+    })
+}
+
+/// The terms of an expression in negation normal form. For a CNF, `&&`
+/// collects clauses and `||` takes their pairwise unions, and the other way
+/// around for a DNF.
+fn distribute(nnf: &ASTNode, form: Form, limit: usize) -> Result<Vec<Vec<Literal>>> {
+    let (collects, multiplies) = match (nnf, form) {
+        (ASTNode::And(p, q), Form::Conjunctive) | (ASTNode::Or(p, q), Form::Disjunctive) => {
+            ((p, q), false)
+        }
+        (ASTNode::Or(p, q), Form::Conjunctive) | (ASTNode::And(p, q), Form::Disjunctive) => {
+            ((p, q), true)
+        }
+
+        // true is the empty CNF, false the empty DNF, and the other way round
+        // they're a single empty term
+        (ASTNode::Literal(value), _) => {
+            let empty = *value == (form == Form::Conjunctive);
+            return Ok(if empty { vec![] } else { vec![vec![]] });
+        }
+
+        (literal, _) => return Ok(vec![vec![as_literal(literal)]]),
+    };
+
+    let (p, q) = collects;
+    let p = distribute(p, form, limit)?;
+    let q = distribute(q, form, limit)?;
+
+    let terms = if multiplies {
+        if p.len().saturating_mul(q.len()) > limit {
+            return Err(EngineError::NormalFormTooLarge { limit });
+        }
+
+        p.iter()
+            .flat_map(|a| q.iter().filter_map(move |b| merge(a, b)))
+            .collect()
+    } else {
+        p.into_iter().chain(q).collect()
+    };
+
+    let terms = dedup(terms);
+
+    if terms.len() > limit {
+        return Err(EngineError::NormalFormTooLarge { limit });
+    }
+
+    Ok(terms)
+}
+
+fn as_literal(nnf: &ASTNode) -> Literal {
+    match nnf {
+        ASTNode::Variable(var) => Literal {
+            var: *var,
+            positive: true,
+        },
+        ASTNode::Not(inner) => as_literal(inner).negate(),
+        _ => unreachable!("only literals are left in negation normal form"),
+    }
+}
+
+/// The union of two terms, or [None] if it contains a literal and its
+/// negation, in which case the term can be dropped
+fn merge(a: &[Literal], b: &[Literal]) -> Option<Vec<Literal>> {
+    let mut term = a.to_vec();
+
+    for lit in b {
+        if term.contains(&lit.negate()) {
+            return None;
+        }
+
+        if !term.contains(lit) {
+            term.push(*lit);
+        }
+    }
+
+    Some(term)
+}
+
+/// Drops repeated terms, keeping the first of each
+fn dedup(terms: Vec<Vec<Literal>>) -> Vec<Vec<Literal>> {
+    let mut seen = HashSet::new();
+
+    terms
+        .into_iter()
+        .filter(|term| {
+            let mut key = term.clone();
+            key.sort();
+            seen.insert(key)
+        })
+        .collect()
+}
+
+impl Cnf {
+    /// The formula as an expression, naming fresh variables `t1`, `t2` and
+    /// so on, skipping any names the expression already uses
+    pub fn to_ast(&self) -> ASTNode {
+        let taken = self.variables().iter().copied().collect::<HashSet<_>>();
+
+        let fresh = (1..)
+            .map(|n| Symbol::intern(&format!("t{n}")))
+            .filter(|name| !taken.contains(name));
+
+        let names = self
+            .variables()
+            .iter()
+            .copied()
+            .chain(fresh)
+            .take(self.var_count())
+            .collect::<Vec<_>>();
+
+        let clauses = self.clauses().iter().map(|clause| {
+            let literals = clause.iter().map(|lit| {
+                Literal {
+                    var: names[lit.var().index()],
+                    positive: lit.is_positive(),
+                }
+                .to_ast()
+            });
+
+            join(literals, ASTNode::Or, false)
+        });
+
+        join(clauses, ASTNode::And, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Engine, testing::FORMULAS};
+
+    #[test]
+    fn test_nnf() {
+        let engine = Engine::default();
+        let nnf = |input| to_nnf(&engine.parse(input).unwrap()).to_string();
+
+        assert_eq!(nnf("!(p && !q)"), "!p || q");
+        assert_eq!(nnf("!(p => q)"), "p && !q");
+        assert_eq!(nnf("!!(p)"), "p");
+        assert_eq!(nnf("p == q"), "p && q || !p && !q");
+        assert_eq!(nnf("!true || r"), "false || r");
+    }
+
+    #[test]
+    fn test_forms_are_equivalent() {
+        let engine = Engine::default();
+
+        for formula in FORMULAS {
+            let expr = engine.parse(formula).unwrap();
+
+            for converted in [
+                to_nnf(&expr),
+                to_cnf(&expr, 1000).unwrap().to_ast(),
+                to_dnf(&expr, 1000).unwrap().to_ast(),
+            ] {
+                assert!(
+                    engine.check_equivalent(&expr, &converted).unwrap(),
+                    "{formula} vs {converted}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_cnf() {
+        let engine = Engine::default();
+        let cnf = |input| {
+            to_cnf(&engine.parse(input).unwrap(), 1000)
+                .unwrap()
+                .to_ast()
+                .to_string()
+        };
+
+        assert_eq!(cnf("p && q || r"), "(p || r) && (q || r)");
+        assert_eq!(cnf("p => q"), "!p || q");
+
+        // tautological clauses disappear, leaving the empty conjunction
+        assert_eq!(cnf("p || !p"), "true");
+        assert_eq!(cnf("p && !p"), "p && !p");
+        assert_eq!(cnf("false"), "false");
+    }
+
+    #[test]
+    fn test_dnf() {
+        let engine = Engine::default();
+        let dnf = |input| {
+            to_dnf(&engine.parse(input).unwrap(), 1000)
+                .unwrap()
+                .to_ast()
+                .to_string()
+        };
+
+        assert_eq!(dnf("(p || q) && r"), "p && r || q && r");
+        assert_eq!(dnf("p && !p"), "false");
+        assert_eq!(dnf("true"), "true");
+    }
+
+    #[test]
+    fn test_size_guard() {
+        let engine = Engine::default();
+
+        // the CNF of (a1 && b1) || ... || (a10 && b10) has 2^10 clauses
+        let input = (1..=10)
+            .map(|i| format!("a{i} && b{i}"))
+            .collect::<Vec<_>>()
+            .join(" || ");
+        let expr = engine.parse(&input).unwrap();
+
+        assert_eq!(
+            to_cnf(&expr, 1000),
+            Err(EngineError::NormalFormTooLarge { limit: 1000 })
+        );
+        assert_eq!(to_cnf(&expr, 1024).unwrap().terms.len(), 1024);
+
+        // while the DNF is already there
+        assert_eq!(to_dnf(&expr, 1000).unwrap().terms.len(), 10);
+    }
+
+    #[test]
+    fn test_tseitin_to_ast() {
+        let engine = Engine::default();
+
+        for formula in FORMULAS {
+            let expr = engine.parse(formula).unwrap();
+            let encoded = Cnf::tseitin(&expr, true).to_ast();
+
+            // equisatisfiable rather than equivalent, since the fresh
+            // variables are constrained
+            assert_eq!(
+                engine.check_satisfiable(&encoded).unwrap(),
+                engine.check_satisfiable(&expr).unwrap(),
+                "{formula} vs {encoded}"
+            );
+        }
+
+        // t1 is taken, so the fresh variable is t2
+        let expr = engine.parse("t1 && q").unwrap();
+        assert_eq!(
+            Cnf::tseitin(&expr, true).to_ast().to_string(),
+            "(!t2 || t1) && (!t2 || q) && (t2 || !t1 || !q) && t2"
+        );
+    }
+}
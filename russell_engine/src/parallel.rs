@@ -0,0 +1,122 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rayon::prelude::*;
+
+use crate::{BLOCK_ROWS, BlockEvaluator};
+
+/// How many blocks each task works through. Large enough that scheduling
+/// overhead doesn't matter, small enough that the work spreads evenly.
+const CHUNK_BLOCKS: u64 = 256;
+
+/// Tables with fewer blocks than this aren't worth spreading across threads
+pub const MIN_PARALLEL_BLOCKS: u64 = 4 * CHUNK_BLOCKS;
+
+/// Splits the blocks of a table into chunks, as ranges
+fn chunks(blocks: u64) -> impl IndexedParallelIterator<Item = std::ops::Range<u64>> {
+    // rayon can only split ranges of u64 it knows the length of as a usize
+    let chunks = blocks.div_ceil(CHUNK_BLOCKS) as usize;
+
+    (0..chunks).into_par_iter().map(move |chunk| {
+        let start = chunk as u64 * CHUNK_BLOCKS;
+
+        start..(start + CHUNK_BLOCKS).min(blocks)
+    })
+}
+
+/// Like [BlockEvaluator::find_row], but spread across threads.
+///
+/// As soon as any thread finds a matching row, every other thread stops at
+/// the end of the block it's on. Which matching row is returned isn't
+/// deterministic.
+pub fn find_row(evaluator: &BlockEvaluator, value: bool) -> Option<u64> {
+    let found = AtomicBool::new(false);
+    let valid = evaluator.valid_mask();
+
+    chunks(evaluator.blocks()).find_map_any(|range| {
+        let mut evaluator = evaluator.clone();
+
+        for block in range {
+            if found.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            let result = evaluator.eval(block);
+            let hits = if value { result } else { !result } & valid;
+
+            if hits != 0 {
+                found.store(true, Ordering::Relaxed);
+                return Some(block * BLOCK_ROWS + hits.trailing_zeros() as u64);
+            }
+        }
+
+        None
+    })
+}
+
+/// Like [BlockEvaluator::count_rows], but spread across threads
+pub fn count_rows(evaluator: &BlockEvaluator, value: bool) -> u64 {
+    let valid = evaluator.valid_mask();
+
+    chunks(evaluator.blocks())
+        .map(|range| {
+            let mut evaluator = evaluator.clone();
+
+            range
+                .map(|block| {
+                    let result = evaluator.eval(block);
+                    let hits = if value { result } else { !result } & valid;
+
+                    hits.count_ones() as u64
+                })
+                .sum::<u64>()
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Engine, Program};
+
+    fn evaluator(input: &str) -> BlockEvaluator {
+        let engine = Engine::default();
+
+        BlockEvaluator::new(Program::compile(&engine.parse(input).unwrap()))
+    }
+
+    #[test]
+    fn test_find_row() {
+        // only false when every variable is true, which is the very last row
+        let mut evaluator = evaluator(
+            "!(a && b && c && d && e && f && g && h && i && j && k && l && m && n && o && p && q && r && s && t)",
+        );
+
+        assert!(evaluator.blocks() >= MIN_PARALLEL_BLOCKS);
+
+        assert_eq!(find_row(&evaluator, false), Some(evaluator.rows() - 1));
+        assert_eq!(find_row(&evaluator, false), evaluator.find_row(false));
+
+        // a tautology has no row where it's false
+        let evaluator = evaluator_for_tautology();
+        assert_eq!(find_row(&evaluator, false), None);
+    }
+
+    fn evaluator_for_tautology() -> BlockEvaluator {
+        evaluator(
+            "(a || b || c || d || e || f || g || h || i || j) || !(a || b || c || d || e || f || g || h || i || j) || k && l && m && n && o && p && q && r && s && t",
+        )
+    }
+
+    #[test]
+    fn test_count_rows_matches_serial() {
+        let mut evaluator = evaluator(
+            "(a => b) && (c == d) || (e && f && !g) || (h == (i || j)) && k && !(l || m) || n && o && p && q && r && s && t",
+        );
+
+        assert!(evaluator.blocks() >= MIN_PARALLEL_BLOCKS);
+
+        for value in [true, false] {
+            assert_eq!(count_rows(&evaluator, value), evaluator.count_rows(value));
+        }
+    }
+}
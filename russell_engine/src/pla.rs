@@ -0,0 +1,494 @@
+use std::fmt;
+
+use russell_ast::{ASTNode, Symbol};
+
+use crate::{
+    Cube, Engine, EngineError, Form, NormalForm, Result,
+    espresso::{self, complement},
+    to_dnf,
+};
+
+/// A line of a [Pla]: an input cube, and the outputs it's part of as a
+/// bitset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PlaTerm {
+    pub input: Cube,
+    pub outputs: u64,
+}
+
+impl PlaTerm {
+    /// Whether this term covers all of `other`, for every output `other` is
+    /// part of
+    pub fn contains(&self, other: &PlaTerm) -> bool {
+        self.input.contains(&other.input) && other.outputs & !self.outputs == 0
+    }
+}
+
+/// A function with several outputs as lists of cubes over shared inputs,
+/// as in the PLA files read and written by Espresso and other logic tools.
+///
+/// Output `j` is true on the rows covered by the terms in `on` that have
+/// bit `j` of their outputs set, except where the terms in `dont_care` make
+/// its value not matter, and false everywhere else. Input `i` is bit `i` of
+/// each cube.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pla {
+    pub inputs: Vec<Symbol>,
+    pub outputs: Vec<Symbol>,
+    pub on: Vec<PlaTerm>,
+    pub dont_care: Vec<PlaTerm>,
+}
+
+impl Pla {
+    pub const MAX_INPUTS: usize = u64::BITS as usize;
+    pub const MAX_OUTPUTS: usize = u64::BITS as usize;
+
+    pub fn new() -> Self {
+        Pla::default()
+    }
+
+    /// A PLA with an output for each expression, in order
+    pub fn from_ast<'a>(outputs: impl IntoIterator<Item = (Symbol, &'a ASTNode)>) -> Result<Pla> {
+        let mut pla = Pla::new();
+
+        for (name, expr) in outputs {
+            pla.add_output(name, expr)?;
+        }
+
+        Ok(pla)
+    }
+
+    /// Adds an output called `name` that's true where `expr` is, returning
+    /// its index. Any of the expression's variables that aren't inputs yet
+    /// are added as inputs.
+    pub fn add_output(&mut self, name: Symbol, expr: &ASTNode) -> Result<usize> {
+        let output = self.outputs.len();
+
+        if output == Self::MAX_OUTPUTS {
+            return Err(EngineError::TooManyOutputs {
+                count: output + 1,
+                limit: Self::MAX_OUTPUTS,
+            });
+        }
+
+        let cubes = self.cubes(expr)?;
+        self.outputs.push(name);
+        self.on.extend(cubes.into_iter().map(|input| PlaTerm {
+            input,
+            outputs: 1 << output,
+        }));
+
+        Ok(output)
+    }
+
+    /// Makes the rows where `expr` is true don't-cares for output `output`
+    pub fn add_dont_care(&mut self, output: usize, expr: &ASTNode) -> Result<()> {
+        assert!(output < self.outputs.len(), "no output {output}");
+
+        let cubes = self.cubes(expr)?;
+        self.dont_care
+            .extend(cubes.into_iter().map(|input| PlaTerm {
+                input,
+                outputs: 1 << output,
+            }));
+
+        Ok(())
+    }
+
+    /// Output `output` as a sum of products
+    pub fn output_ast(&self, output: usize) -> ASTNode {
+        let terms = self
+            .on
+            .iter()
+            .filter(|term| (term.outputs >> output) & 1 == 1)
+            .map(|term| term.input.literals(&self.inputs))
+            .collect();
+
+        NormalForm {
+            form: Form::Disjunctive,
+            terms,
+        }
+        .to_ast()
+    }
+
+    /// A smaller PLA with the same outputs, as far as the don't-cares
+    /// allow, found by Espresso's heuristic. Terms are shared between
+    /// outputs where possible, so each output on its own may be a term or
+    /// two bigger than it could be.
+    pub fn minimize(&self) -> Pla {
+        Pla {
+            on: espresso::minimize(&self.on, &self.dont_care, self.outputs.len()),
+            ..self.clone()
+        }
+    }
+
+    /// The cubes of the DNF of `expr`, adding new inputs as needed
+    fn cubes(&mut self, expr: &ASTNode) -> Result<Vec<Cube>> {
+        let dnf = to_dnf(expr, Engine::MAX_NORMAL_FORM_TERMS)?;
+        let mut cubes = Vec::with_capacity(dnf.terms.len());
+
+        for term in &dnf.terms {
+            let mut cube = Cube::UNIVERSE;
+
+            for literal in term {
+                let input = match self.inputs.iter().position(|var| *var == literal.var) {
+                    Some(input) => input,
+                    None if self.inputs.len() == Self::MAX_INPUTS => {
+                        return Err(EngineError::TooManyVariables {
+                            count: self.inputs.len() + 1,
+                            limit: Self::MAX_INPUTS,
+                        });
+                    }
+                    None => {
+                        self.inputs.push(literal.var);
+                        self.inputs.len() - 1
+                    }
+                };
+
+                cube.mask |= 1 << input;
+                cube.bits |= (literal.positive as u64) << input;
+            }
+
+            cubes.push(cube);
+        }
+
+        Ok(cubes)
+    }
+
+    /// Reads a PLA file. `.i`, `.o`, `.ilb`, `.ob`, `.p`, `.type` and `.e`
+    /// are understood. With `.type fr` or `fdr`, rows in neither the on-set
+    /// nor the off-set are don't-cares. Inputs and outputs without names
+    /// are called `x0`, `x1`, ... and `f0`, `f1`, ...
+    pub fn parse(input: &str) -> Result<Pla> {
+        let error = |line, reason: &str| EngineError::InvalidPla {
+            line,
+            reason: reason.to_string(),
+        };
+
+        let mut counts: [Option<usize>; 2] = [None, None];
+        let mut names: [Option<(usize, Vec<Symbol>)>; 2] = [None, None];
+
+        // which of the on-set, don't-care set and off-set the terms give;
+        // `fd` unless `.type` says otherwise
+        let mut has_dont_care = true;
+        let mut has_off = false;
+
+        let mut on = Vec::new();
+        let mut dont_care = Vec::new();
+        let mut off = Vec::new();
+
+        let mut last_line = 0;
+
+        for (line, text) in input.lines().enumerate() {
+            let line = line + 1;
+            let text = text.split('#').next().unwrap_or_default().trim();
+            last_line = line;
+
+            if text.is_empty() {
+                continue;
+            }
+
+            if let Some(directive) = text.strip_prefix('.') {
+                let mut words = directive.split_whitespace();
+                let keyword = words.next().unwrap_or_default();
+                let args = words.collect::<Vec<_>>();
+
+                match (keyword, &args[..]) {
+                    ("i" | "o", [count]) => {
+                        let side = (keyword == "o") as usize;
+                        let count = count
+                            .parse::<usize>()
+                            .map_err(|_| error(line, "expected a number"))?;
+
+                        if count > u64::BITS as usize {
+                            return Err(error(line, "at most 64 inputs and outputs are supported"));
+                        }
+
+                        counts[side] = Some(count);
+                    }
+                    ("ilb" | "ob", _) => {
+                        let side = (keyword == "ob") as usize;
+                        let symbols = args.iter().map(|name| Symbol::intern(name)).collect();
+
+                        names[side] = Some((line, symbols));
+                    }
+
+                    // the number of terms, which we don't need
+                    ("p", _) => {}
+
+                    ("type", [kind]) => {
+                        (has_dont_care, has_off) = match *kind {
+                            "f" => (false, false),
+                            "fd" => (true, false),
+                            "fr" => (false, true),
+                            "fdr" => (true, true),
+                            _ => return Err(error(line, "unknown type")),
+                        };
+                    }
+                    ("e" | "end", _) => break,
+                    _ => return Err(error(line, &format!("unsupported directive `.{keyword}`"))),
+                }
+
+                continue;
+            }
+
+            let [Some(inputs), Some(outputs)] = counts else {
+                return Err(error(line, "terms must come after `.i` and `.o`"));
+            };
+
+            let chars = text
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<Vec<_>>();
+
+            if chars.len() != inputs + outputs {
+                return Err(error(
+                    line,
+                    &format!("expected {inputs} inputs and {outputs} outputs"),
+                ));
+            }
+
+            let mut cube = Cube::UNIVERSE;
+
+            for (i, c) in chars[..inputs].iter().enumerate() {
+                match c {
+                    '0' => cube.mask |= 1 << i,
+                    '1' => {
+                        cube.mask |= 1 << i;
+                        cube.bits |= 1 << i;
+                    }
+                    '-' => {}
+                    _ => return Err(error(line, &format!("invalid input `{c}`"))),
+                }
+            }
+
+            let mut sets = [0u64; 3];
+
+            for (j, c) in chars[inputs..].iter().enumerate() {
+                let set = match c {
+                    '1' => 0,
+                    '-' => 1,
+                    '0' => 2,
+                    '~' => continue,
+                    _ => return Err(error(line, &format!("invalid output `{c}`"))),
+                };
+
+                sets[set] |= 1 << j;
+            }
+
+            let [on_outputs, dont_care_outputs, off_outputs] = sets;
+
+            for (outputs, included, terms) in [
+                (on_outputs, true, &mut on),
+                (dont_care_outputs, has_dont_care, &mut dont_care),
+                (off_outputs, has_off, &mut off),
+            ] {
+                if included && outputs != 0 {
+                    terms.push(PlaTerm {
+                        input: cube,
+                        outputs,
+                    });
+                }
+            }
+        }
+
+        let [Some(inputs), Some(outputs)] = counts else {
+            return Err(error(last_line, "missing `.i` or `.o`"));
+        };
+
+        let named = |names: Option<(usize, Vec<Symbol>)>, count, prefix| match names {
+            Some((line, names)) if names.len() != count => Err(error(
+                line,
+                &format!("expected {count} names, found {}", names.len()),
+            )),
+            Some((_, names)) => Ok(names),
+            None => Ok((0..count)
+                .map(|i| Symbol::intern(&format!("{prefix}{i}")))
+                .collect()),
+        };
+
+        let [input_names, output_names] = names;
+        let inputs_named = named(input_names, inputs, "x")?;
+        let outputs_named = named(output_names, outputs, "f")?;
+
+        // with an off-set, whatever isn't in the on-set or off-set doesn't
+        // matter
+        if has_off {
+            for j in 0..outputs {
+                let specified = on
+                    .iter()
+                    .chain(&off)
+                    .chain(&dont_care)
+                    .filter(|term| (term.outputs >> j) & 1 == 1)
+                    .map(|term| term.input)
+                    .collect::<Vec<_>>();
+
+                dont_care.extend(complement(&specified).into_iter().map(|input| PlaTerm {
+                    input,
+                    outputs: 1 << j,
+                }));
+            }
+        }
+
+        Ok(Pla {
+            inputs: inputs_named,
+            outputs: outputs_named,
+            on,
+            dont_care,
+        })
+    }
+}
+
+/// Writes the PLA file, with don't-cares as `-` outputs
+impl fmt::Display for Pla {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names = |symbols: &[Symbol]| {
+            symbols
+                .iter()
+                .map(|symbol| symbol.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        writeln!(f, ".i {}", self.inputs.len())?;
+        writeln!(f, ".o {}", self.outputs.len())?;
+
+        if !self.inputs.is_empty() {
+            writeln!(f, ".ilb {}", names(&self.inputs))?;
+        }
+
+        if !self.outputs.is_empty() {
+            writeln!(f, ".ob {}", names(&self.outputs))?;
+        }
+
+        writeln!(f, ".p {}", self.on.len() + self.dont_care.len())?;
+
+        for (terms, set) in [(&self.on, '1'), (&self.dont_care, '-')] {
+            for term in terms {
+                for i in 0..self.inputs.len() {
+                    let c = match ((term.input.mask >> i) & 1, (term.input.bits >> i) & 1) {
+                        (0, _) => '-',
+                        (_, 0) => '0',
+                        _ => '1',
+                    };
+
+                    write!(f, "{c}")?;
+                }
+
+                f.write_str(" ")?;
+
+                for j in 0..self.outputs.len() {
+                    let c = if (term.outputs >> j) & 1 == 1 {
+                        set
+                    } else {
+                        '0'
+                    };
+
+                    write!(f, "{c}")?;
+                }
+
+                writeln!(f)?;
+            }
+        }
+
+        writeln!(f, ".e")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL_ADDER: &str = "\
+# a full adder
+.i 3
+.o 2
+.ilb a b cin
+.ob sum cout
+.p 7
+100 10
+010 10
+001 10
+111 11
+11- 01
+1-1 01
+-11 01
+.e
+";
+
+    #[test]
+    fn test_parse() {
+        let pla = Pla::parse(FULL_ADDER).unwrap();
+        let engine = Engine::default();
+
+        assert_eq!(pla.inputs.len(), 3);
+        assert_eq!(pla.outputs[1], Symbol::intern("cout"));
+        assert_eq!(pla.on.len(), 7);
+        assert!(pla.dont_care.is_empty());
+
+        let sum = engine.parse("(a == b) == cin").unwrap();
+        let cout = engine.parse("a && b || a && cin || b && cin").unwrap();
+
+        assert!(engine.check_equivalent(&pla.output_ast(0), &sum).unwrap());
+        assert!(engine.check_equivalent(&pla.output_ast(1), &cout).unwrap());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let pla = Pla::parse(FULL_ADDER).unwrap();
+        let written = pla.to_string();
+
+        assert_eq!(written, FULL_ADDER.trim_start_matches("# a full adder\n"));
+        assert_eq!(Pla::parse(&written).unwrap(), pla);
+    }
+
+    #[test]
+    fn test_off_set() {
+        // with only the off-set and on-set given, everything else is a
+        // don't-care
+        let pla = Pla::parse(".i 2\n.o 1\n.type fr\n11 1\n00 0\n").unwrap();
+
+        assert_eq!(pla.inputs, [Symbol::intern("x0"), Symbol::intern("x1")]);
+        assert_eq!(pla.on.len(), 1);
+
+        let minimized = pla.minimize();
+        assert_eq!(minimized.on.len(), 1);
+        assert_eq!(minimized.on[0].input.literal_count(), 1);
+    }
+
+    #[test]
+    fn test_from_ast() {
+        let engine = Engine::default();
+        let f = engine.parse("a && !b").unwrap();
+        let g = engine.parse("c || a").unwrap();
+
+        let mut pla =
+            Pla::from_ast([(Symbol::intern("f"), &f), (Symbol::intern("g"), &g)]).unwrap();
+        pla.add_dont_care(0, &engine.parse("a && b").unwrap())
+            .unwrap();
+
+        assert_eq!(pla.inputs.len(), 3);
+        assert_eq!(
+            pla.to_string(),
+            ".i 3\n.o 2\n.ilb a b c\n.ob f g\n.p 4\n10- 10\n--1 01\n1-- 01\n11- -0\n.e\n"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |input| match Pla::parse(input) {
+            Err(EngineError::InvalidPla { line, reason }) => (line, reason),
+            other => panic!("expected an error, got {other:?}"),
+        };
+
+        assert_eq!(error("10 1\n").0, 1);
+        assert_eq!(
+            error(".i 2\n.o 1\n12 1\n"),
+            (3, "invalid input `2`".to_string())
+        );
+        assert_eq!(error(".i 2\n.o 1\n10 11\n").0, 3);
+        assert_eq!(error(".i 2\n.o 1\n.ilb a\n").0, 3);
+        assert_eq!(error(".i 2\n.mv 3\n").0, 2);
+        assert_eq!(error(".i 2\n").0, 1);
+    }
+}
@@ -0,0 +1,455 @@
+use std::collections::HashSet;
+
+use russell_ast::{ASTNode, Symbol};
+
+use crate::{BlockEvaluator, Cube, EngineError, Form, NormalForm, Program, Result};
+
+/// Exact two-level minimization. Finds the prime implicants of an
+/// expression from its truth table by Quine–McCluskey, then the smallest
+/// set of them covering every row where it's true by Petrick's method.
+///
+/// Rows can be marked as don't-cares, either as the rows where another
+/// expression is true or by number, leaving the minimizer free to cover
+/// them or not, whichever gives the smaller result.
+///
+/// ```
+/// # use russell_engine::{Engine, QuineMcCluskey};
+/// let engine = Engine::default();
+/// let expr = engine.parse("a && b && !c || a && !b && !c").unwrap();
+/// let dont_care = engine.parse("a && c").unwrap();
+///
+/// let sop = QuineMcCluskey::new(&expr).dont_care(&dont_care).minimize().unwrap();
+/// assert_eq!(sop.to_ast().to_string(), "a");
+/// ```
+#[derive(Debug, Clone)]
+pub struct QuineMcCluskey {
+    expr: ASTNode,
+    variables: Vec<Symbol>,
+    dont_care: Option<ASTNode>,
+    dont_care_rows: Vec<u64>,
+}
+
+impl QuineMcCluskey {
+    /// The most variables we'll minimize over. Both the number of prime
+    /// implicants and Petrick's method grow exponentially.
+    pub const MAX_VARIABLES: usize = 16;
+
+    pub fn new(expr: &ASTNode) -> Self {
+        QuineMcCluskey {
+            expr: expr.clone(),
+            variables: Program::compile(expr).variables().to_vec(),
+            dont_care: None,
+            dont_care_rows: Vec::new(),
+        }
+    }
+
+    /// Marks the rows where `dont_care` is true as don't-cares. Variables
+    /// it has that the expression doesn't are added after the expression's
+    /// own.
+    pub fn dont_care(mut self, dont_care: &ASTNode) -> Self {
+        for var in Program::compile(dont_care).variables() {
+            if !self.variables.contains(var) {
+                self.variables.push(*var);
+            }
+        }
+
+        self.dont_care = Some(match self.dont_care {
+            Some(previous) => ASTNode::Or(Box::new(previous), Box::new(dont_care.clone())),
+            None => dont_care.clone(),
+        });
+
+        self
+    }
+
+    /// Marks rows of the truth table over [QuineMcCluskey::variables] as
+    /// don't-cares, numbered in [binary order](crate::Order::Binary)
+    pub fn dont_care_rows(mut self, rows: impl IntoIterator<Item = u64>) -> Self {
+        self.dont_care_rows.extend(rows);
+        self
+    }
+
+    /// The variables minimized over, the first being bit 0 of a row number
+    pub fn variables(&self) -> &[Symbol] {
+        &self.variables
+    }
+
+    /// The prime implicants, as cubes over [QuineMcCluskey::variables].
+    /// Those covering nothing but don't-cares are left out, since no
+    /// minimal cover would use them.
+    pub fn prime_implicants(&self) -> Result<Vec<Cube>> {
+        let (on, dont_care) = self.table()?;
+
+        Ok(prime_implicants(&on, &dont_care, self.variables.len()))
+    }
+
+    /// A smallest set of prime implicants covering every row where the
+    /// expression is true: first the fewest implicants, then the fewest
+    /// literals
+    pub fn cover(&self) -> Result<Vec<Cube>> {
+        let (on, dont_care) = self.table()?;
+        let primes = prime_implicants(&on, &dont_care, self.variables.len());
+
+        let covering = |row: u64| {
+            (0..primes.len())
+                .filter(|i| primes[*i].covers(row))
+                .collect::<Vec<_>>()
+        };
+
+        // a row only one implicant covers needs that implicant
+        let mut chosen = Vec::new();
+
+        for row in &on {
+            if let [only] = covering(*row)[..]
+                && !chosen.contains(&only)
+            {
+                chosen.push(only);
+            }
+        }
+
+        let uncovered = on
+            .iter()
+            .filter(|row| !chosen.iter().any(|i| primes[*i].covers(**row)));
+
+        // Petrick's method: the ways of covering every row so far, each a
+        // sorted set of implicants. Multiplying in a row's implicants only
+        // extends the ways that don't already cover it, and ways containing
+        // another are dropped.
+        let mut ways = vec![Vec::new()];
+
+        for row in uncovered {
+            let options = covering(*row);
+            let mut next = Vec::new();
+
+            for way in ways {
+                if way.iter().any(|i| options.contains(i)) {
+                    next.push(way);
+                    continue;
+                }
+
+                for option in &options {
+                    let mut extended = way.clone();
+                    let pos = extended.binary_search(option).unwrap_err();
+                    extended.insert(pos, *option);
+
+                    next.push(extended);
+                }
+            }
+
+            ways = absorb(next);
+        }
+
+        let literals =
+            |way: &[usize]| -> u32 { way.iter().map(|i| primes[*i].literal_count()).sum() };
+
+        let best = ways
+            .into_iter()
+            .min_by_key(|way| (way.len(), literals(way)))
+            .expect("there's always a way to cover the rows");
+
+        chosen.extend(best);
+        chosen.sort();
+
+        Ok(chosen.into_iter().map(|i| primes[i]).collect())
+    }
+
+    /// The minimal sum of products, see [QuineMcCluskey::cover]
+    pub fn minimize(&self) -> Result<NormalForm> {
+        let terms = self
+            .cover()?
+            .iter()
+            .map(|cube| cube.literals(&self.variables))
+            .collect();
+
+        Ok(NormalForm {
+            form: Form::Disjunctive,
+            terms,
+        })
+    }
+
+    /// The rows where the expression is true and that aren't don't-cares,
+    /// and the don't-care rows
+    pub(crate) fn table(&self) -> Result<(Vec<u64>, Vec<u64>)> {
+        let count = self.variables.len();
+
+        if count > Self::MAX_VARIABLES {
+            return Err(EngineError::TooManyVariables {
+                count,
+                limit: Self::MAX_VARIABLES,
+            });
+        }
+
+        let rows = 1u64 << count;
+
+        if let Some(row) = self.dont_care_rows.iter().find(|row| **row >= rows) {
+            return Err(EngineError::RowOutOfRange { row: *row, rows });
+        }
+
+        let mut dont_care = vec![false; rows as usize];
+
+        if let Some(expr) = &self.dont_care {
+            for row in self.true_rows(expr)? {
+                dont_care[row as usize] = true;
+            }
+        }
+
+        for row in &self.dont_care_rows {
+            dont_care[*row as usize] = true;
+        }
+
+        let on = self
+            .true_rows(&self.expr)?
+            .into_iter()
+            .filter(|row| !dont_care[*row as usize])
+            .collect();
+
+        let dont_care = (0..rows).filter(|row| dont_care[*row as usize]).collect();
+
+        Ok((on, dont_care))
+    }
+
+    /// The rows where `expr` is true, in the table over our variables
+    fn true_rows(&self, expr: &ASTNode) -> Result<Vec<u64>> {
+        let mut evaluator = BlockEvaluator::new(Program::compile_with(expr, &self.variables)?);
+        let valid = evaluator.valid_mask();
+        let mut rows = Vec::new();
+
+        for block in 0..evaluator.blocks() {
+            let mut hits = evaluator.eval(block) & valid;
+
+            while hits != 0 {
+                rows.push(block * crate::BLOCK_ROWS + hits.trailing_zeros() as u64);
+                hits &= hits - 1;
+            }
+        }
+
+        Ok(rows)
+    }
+}
+
+/// The prime implicants of a table over `n` variables, leaving out those
+/// covering only don't-cares
+fn prime_implicants(on: &[u64], dont_care: &[u64], n: usize) -> Vec<Cube> {
+    let mut current = on
+        .iter()
+        .chain(dont_care)
+        .map(|row| Cube::row(*row, n))
+        .collect::<HashSet<_>>();
+    let mut primes: Vec<Cube> = Vec::new();
+
+    // cubes differing in a single variable combine into one without it.
+    // Whatever can't be combined any further is prime.
+    while !current.is_empty() {
+        let mut combined = HashSet::new();
+        let mut used = HashSet::new();
+
+        for cube in &current {
+            for i in 0..n {
+                let bit = 1 << i;
+
+                if cube.mask & bit == 0 || cube.bits & bit != 0 {
+                    continue;
+                }
+
+                let other = Cube {
+                    bits: cube.bits | bit,
+                    ..*cube
+                };
+
+                if current.contains(&other) {
+                    combined.insert(Cube {
+                        mask: cube.mask & !bit,
+                        bits: cube.bits,
+                    });
+                    used.extend([*cube, other]);
+                }
+            }
+        }
+
+        primes.extend(current.difference(&used));
+        current = combined;
+    }
+
+    primes.retain(|prime| on.iter().any(|row| prime.covers(*row)));
+    primes.sort_by_key(|prime| (prime.bits, prime.mask));
+
+    primes
+}
+
+/// Drops duplicate sets and any set containing another one, which can't be
+/// part of a smallest cover
+fn absorb(mut ways: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
+    ways.sort_by_key(Vec::len);
+    ways.dedup();
+
+    let mut kept: Vec<Vec<usize>> = Vec::new();
+
+    for way in ways {
+        let absorbed = kept
+            .iter()
+            .any(|smaller| smaller.iter().all(|i| way.binary_search(i).is_ok()));
+
+        if !absorbed {
+            kept.push(way);
+        }
+    }
+
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Engine, testing};
+
+    /// The expression true in exactly `rows` of the table over a, b, c and
+    /// so on, with `a` as bit 0
+    fn from_rows(rows: &[u64], variables: usize) -> ASTNode {
+        let names = ["a", "b", "c", "d"];
+
+        if rows.is_empty() {
+            return ASTNode::Literal(false);
+        }
+
+        let input = rows
+            .iter()
+            .map(|row| {
+                let literals = (0..variables)
+                    .map(|i| match (row >> i) & 1 {
+                        1 => names[i].to_string(),
+                        _ => format!("!{}", names[i]),
+                    })
+                    .collect::<Vec<_>>();
+
+                format!("({})", literals.join(" && "))
+            })
+            .collect::<Vec<_>>()
+            .join(" || ");
+
+        Engine::default().parse(&input).unwrap()
+    }
+
+    fn minimize(input: &str) -> String {
+        let expr = Engine::default().parse(input).unwrap();
+
+        QuineMcCluskey::new(&expr)
+            .minimize()
+            .unwrap()
+            .to_ast()
+            .to_string()
+    }
+
+    #[test]
+    fn test_minimize() {
+        assert_eq!(minimize("a && b || a && !b"), "a");
+        assert_eq!(minimize("a && b || !a && b || a && !b"), "a || b");
+        assert_eq!(minimize("(a => b) && (b => c) && a"), "a && b && c");
+        assert_eq!(minimize("p && !p"), "false");
+        assert_eq!(minimize("p || !p"), "true");
+    }
+
+    #[test]
+    fn test_cyclic_cover() {
+        // no implicant is essential here, so it's all down to Petrick's
+        // method: six implicants covering two rows each, three of which do
+        let expr = from_rows(&[0, 1, 2, 5, 6, 7], 3);
+        let minimizer = QuineMcCluskey::new(&expr);
+
+        assert_eq!(minimizer.prime_implicants().unwrap().len(), 6);
+
+        let sop = minimizer.minimize().unwrap();
+        assert_eq!(sop.terms.len(), 3);
+        assert!(
+            Engine::default()
+                .check_equivalent(&expr, &sop.to_ast())
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_minimal_against_brute_force() {
+        let engine = Engine::default();
+
+        // every function of three variables
+        for function in 0u64..256 {
+            let rows = (0..8)
+                .filter(|row| (function >> row) & 1 == 1)
+                .collect::<Vec<_>>();
+            let expr = from_rows(&rows, 3);
+
+            let minimizer = QuineMcCluskey::new(&expr);
+            let primes = minimizer.prime_implicants().unwrap();
+            let cover = minimizer.cover().unwrap();
+
+            let smallest = (0u32..1 << primes.len())
+                .filter(|subset| {
+                    rows.iter().all(|row| {
+                        (0..primes.len()).any(|i| (subset >> i) & 1 == 1 && primes[i].covers(*row))
+                    })
+                })
+                .map(u32::count_ones)
+                .min()
+                .unwrap();
+
+            assert_eq!(cover.len() as u32, smallest, "function {function:#010b}");
+
+            let sop = minimizer.minimize().unwrap().to_ast();
+            assert!(engine.check_equivalent(&expr, &sop).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_dont_cares() {
+        let engine = Engine::default();
+
+        // the textbook example, with a as the most significant bit
+        let reverse = |row: u64| row.reverse_bits() >> 60;
+        let on = [4, 8, 10, 11, 12, 15].map(reverse);
+        let dont_care = [9, 14].map(reverse);
+
+        let minimizer = QuineMcCluskey::new(&from_rows(&on, 4)).dont_care_rows(dont_care);
+        let cover = minimizer.cover().unwrap();
+
+        assert_eq!(cover.len(), 3);
+        assert_eq!(cover.iter().map(Cube::literal_count).sum::<u32>(), 7);
+
+        for row in on {
+            assert!(cover.iter().any(|cube| cube.covers(row)));
+        }
+
+        // and as a formula, bringing in a variable of its own
+        let expr = engine.parse("a && b && !c").unwrap();
+        let dont_care = engine.parse("a && b && c && !d || a && !b && !c").unwrap();
+
+        let minimizer = QuineMcCluskey::new(&expr).dont_care(&dont_care);
+        assert_eq!(minimizer.variables().len(), 4);
+        assert_eq!(
+            minimizer.minimize().unwrap().to_ast().to_string(),
+            "a && !c"
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        let engine = Engine::default();
+
+        let wide = testing::joined(20, "||");
+
+        assert_eq!(
+            QuineMcCluskey::new(&engine.parse(&wide).unwrap())
+                .minimize()
+                .err(),
+            Some(EngineError::TooManyVariables {
+                count: 20,
+                limit: QuineMcCluskey::MAX_VARIABLES
+            })
+        );
+
+        assert_eq!(
+            QuineMcCluskey::new(&engine.parse("a && b").unwrap())
+                .dont_care_rows([4])
+                .minimize()
+                .err(),
+            Some(EngineError::RowOutOfRange { row: 4, rows: 4 })
+        );
+    }
+}
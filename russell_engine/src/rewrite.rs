@@ -0,0 +1,598 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use russell_ast::{ASTNode, Symbol};
+use russell_parser::parse;
+
+use crate::{Derivation, EngineError, Result, Step};
+
+/// A rewrite rule. Every variable in `lhs` is a pattern variable, which
+/// matches any subexpression as long as each occurrence of the same variable
+/// matches the same thing. A match is replaced by `rhs`, with its pattern
+/// variables filled in.
+///
+/// `&&`, `||` and `==` are commutative, so a pattern using them also
+/// matches its operands the other way around.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub name: Cow<'static, str>,
+    lhs: ASTNode,
+    rhs: ASTNode,
+}
+
+impl Rule {
+    /// Fails if `rhs` uses a variable that isn't in `lhs`, as there would be
+    /// nothing to fill it in with
+    pub fn new(name: impl Into<Cow<'static, str>>, lhs: &ASTNode, rhs: &ASTNode) -> Result<Rule> {
+        let name = name.into();
+        let (lhs, rhs) = (lhs.strip_parens(), rhs.strip_parens());
+
+        let bound = variables(&lhs);
+
+        if let Some(unbound) = variables(&rhs).into_iter().find(|var| !bound.contains(var)) {
+            return Err(EngineError::InvalidRule {
+                name: name.into_owned(),
+                reason: format!("`{unbound}` only appears on the right hand side"),
+            });
+        }
+
+        Ok(Rule { name, lhs, rhs })
+    }
+
+    /// Reads a rule written as a law in russell syntax, `lhs == rhs`, like
+    /// the ones in `laws/`
+    pub fn parse(name: impl Into<Cow<'static, str>>, input: &str) -> Result<Rule> {
+        Rule::from_law(name, &parse(input)?)
+    }
+
+    /// Turns an equivalence into a rule rewriting its left side into its
+    /// right side
+    pub fn from_law(name: impl Into<Cow<'static, str>>, law: &ASTNode) -> Result<Rule> {
+        match law.strip_parens() {
+            ASTNode::Equivalent(lhs, rhs) => Rule::new(name, &lhs, &rhs),
+            _ => Err(EngineError::InvalidRule {
+                name: name.into().into_owned(),
+                reason: "laws must have the form `lhs == rhs`".to_owned(),
+            }),
+        }
+    }
+
+    pub fn lhs(&self) -> &ASTNode {
+        &self.lhs
+    }
+
+    pub fn rhs(&self) -> &ASTNode {
+        &self.rhs
+    }
+
+    /// The same law applied right to left
+    pub fn reversed(&self) -> Result<Rule> {
+        Rule::new(self.name.clone(), &self.rhs, &self.lhs)
+    }
+
+    /// Rewrites `expr` if it matches the rule as a whole. `expr` shouldn't
+    /// contain any parentheses.
+    pub fn apply(&self, expr: &ASTNode) -> Option<ASTNode> {
+        let mut bindings = HashMap::new();
+
+        matches(&self.lhs, expr, &mut bindings).then(|| substitute(&self.rhs, &bindings))
+    }
+}
+
+/// The variables of `expr`, in the order they first appear
+fn variables(expr: &ASTNode) -> Vec<Symbol> {
+    let mut vars = Vec::new();
+
+    for node in expr.preorder() {
+        if let ASTNode::Variable(var) = node
+            && !vars.contains(var)
+        {
+            vars.push(*var);
+        }
+    }
+
+    vars
+}
+
+/// Matches `expr` against `pattern`, adding to `bindings`. On failure
+/// `bindings` is left as it was.
+fn matches(pattern: &ASTNode, expr: &ASTNode, bindings: &mut HashMap<Symbol, ASTNode>) -> bool {
+    match (pattern, expr) {
+        (ASTNode::Variable(var), _) => match bindings.get(var) {
+            Some(bound) => bound == expr,
+            None => {
+                bindings.insert(*var, expr.clone());
+                true
+            }
+        },
+
+        (ASTNode::Literal(a), ASTNode::Literal(b)) => a == b,
+        (ASTNode::Not(p), ASTNode::Not(e)) => matches(p, e, bindings),
+        (ASTNode::Implies(p, q), ASTNode::Implies(a, b)) => matches_pair((p, q), (a, b), bindings),
+
+        (ASTNode::And(p, q), ASTNode::And(a, b))
+        | (ASTNode::Or(p, q), ASTNode::Or(a, b))
+        | (ASTNode::Equivalent(p, q), ASTNode::Equivalent(a, b)) => {
+            matches_pair((p, q), (a, b), bindings) || matches_pair((p, q), (b, a), bindings)
+        }
+
+        _ => false,
+    }
+}
+
+fn matches_pair(
+    (p, q): (&ASTNode, &ASTNode),
+    (a, b): (&ASTNode, &ASTNode),
+    bindings: &mut HashMap<Symbol, ASTNode>,
+) -> bool {
+    let mut attempt = bindings.clone();
+
+    if matches(p, a, &mut attempt) && matches(q, b, &mut attempt) {
+        *bindings = attempt;
+        true
+    } else {
+        false
+    }
+}
+
+/// Fills the pattern variables of `template` in from `bindings`
+fn substitute(template: &ASTNode, bindings: &HashMap<Symbol, ASTNode>) -> ASTNode {
+    let sub = |node: &ASTNode| Box::new(substitute(node, bindings));
+
+    match template {
+        ASTNode::Variable(var) => bindings[var].clone(),
+        ASTNode::Literal(_) => template.clone(),
+        ASTNode::Not(inner) => ASTNode::Not(sub(inner)),
+        ASTNode::And(p, q) => ASTNode::And(sub(p), sub(q)),
+        ASTNode::Or(p, q) => ASTNode::Or(sub(p), sub(q)),
+        ASTNode::Implies(p, q) => ASTNode::Implies(sub(p), sub(q)),
+        ASTNode::Equivalent(p, q) => ASTNode::Equivalent(sub(p), sub(q)),
+        ASTNode::Paren(inner) => substitute(inner, bindings),
+    }
+}
+
+/// `expr` with its `index`th child replaced
+fn with_child(expr: &ASTNode, index: usize, child: ASTNode) -> ASTNode {
+    let child = Box::new(child);
+
+    match (expr, index) {
+        (ASTNode::Not(_), 0) => ASTNode::Not(child),
+        (ASTNode::Paren(_), 0) => ASTNode::Paren(child),
+        (ASTNode::And(_, q), 0) => ASTNode::And(child, q.clone()),
+        (ASTNode::And(p, _), 1) => ASTNode::And(p.clone(), child),
+        (ASTNode::Or(_, q), 0) => ASTNode::Or(child, q.clone()),
+        (ASTNode::Or(p, _), 1) => ASTNode::Or(p.clone(), child),
+        (ASTNode::Implies(_, q), 0) => ASTNode::Implies(child, q.clone()),
+        (ASTNode::Implies(p, _), 1) => ASTNode::Implies(p.clone(), child),
+        (ASTNode::Equivalent(_, q), 0) => ASTNode::Equivalent(child, q.clone()),
+        (ASTNode::Equivalent(p, _), 1) => ASTNode::Equivalent(p.clone(), child),
+        _ => panic!("node has no child {index}"),
+    }
+}
+
+/// The default cost of an expression: how many operators, variables and
+/// constants it has
+pub fn size(expr: &ASTNode) -> usize {
+    expr.preorder()
+        .into_iter()
+        .filter(|node| !matches!(node, ASTNode::Paren(_)))
+        .count()
+}
+
+/// The laws [Rewriter::builtin] simplifies with, as `(name, law)`. The
+/// longer ones are read from `laws/`, which `examples/` has copies of that a
+/// test keeps the same.
+const BUILTIN_LAWS: &[(&str, &str)] = &[
+    ("Identity", "p && true == p"),
+    ("Identity", "p || false == p"),
+    ("Annihilation", "p && false == false"),
+    ("Annihilation", "p || true == true"),
+    ("Idempotence", "p && p == p"),
+    ("Idempotence", "p || p == p"),
+    ("Complement", "p && !p == false"),
+    ("Complement", "p || !p == true"),
+    ("Complement", "!true == false"),
+    ("Complement", "!false == true"),
+    ("Absorption", include_str!("../laws/absorption_laws_1.rsl")),
+    ("Absorption", include_str!("../laws/absorption_laws_2.rsl")),
+    (
+        "Double negation",
+        include_str!("../laws/double_negation.rsl"),
+    ),
+    ("De Morgan", include_str!("../laws/de_morgans_1.rsl")),
+    ("De Morgan", include_str!("../laws/de_morgans_2.rsl")),
+    (
+        "Implication elimination",
+        include_str!("../laws/implication.rsl"),
+    ),
+];
+
+/// Rewrites expressions with a set of [Rule]s until none of them apply.
+///
+/// Each step rewrites the outermost, leftmost subexpression some rule
+/// matches, trying rules in order. Rules don't have to make expressions
+/// smaller: De Morgan and implication elimination make them bigger, but can
+/// expose other simplifications. So rather than the last expression, the
+/// result is the cheapest one seen along the way by the cost function.
+#[derive(Debug, Clone)]
+pub struct Rewriter {
+    rules: Vec<Rule>,
+    cost: fn(&ASTNode) -> usize,
+
+    /// The most steps to take, since rules like commutativity can rewrite
+    /// forever
+    pub max_steps: usize,
+}
+
+impl Default for Rewriter {
+    fn default() -> Self {
+        Rewriter::builtin()
+    }
+}
+
+impl Rewriter {
+    pub const DEFAULT_MAX_STEPS: usize = 1000;
+
+    /// A rewriter with no rules, which leaves everything as it is
+    pub fn new() -> Self {
+        Rewriter {
+            rules: Vec::new(),
+            cost: size,
+            max_steps: Self::DEFAULT_MAX_STEPS,
+        }
+    }
+
+    /// A rewriter with the standard simplification laws: identity,
+    /// annihilation, idempotence, complement, absorption, double negation,
+    /// De Morgan and implication elimination
+    pub fn builtin() -> Self {
+        let rules = BUILTIN_LAWS
+            .iter()
+            .map(|(name, law)| Rule::parse(*name, law).expect("builtin laws are valid"));
+
+        Rewriter::new().with_rules(rules)
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Adds rules, which are tried after the existing ones
+    pub fn with_rules(mut self, rules: impl IntoIterator<Item = Rule>) -> Self {
+        self.rules.extend(rules);
+        self
+    }
+
+    /// Replaces the cost function, which defaults to [size]
+    pub fn with_cost(mut self, cost: fn(&ASTNode) -> usize) -> Self {
+        self.cost = cost;
+        self
+    }
+
+    pub fn cost(&self, expr: &ASTNode) -> usize {
+        (self.cost)(expr)
+    }
+
+    /// Rewrites `expr` until no rule applies, returning the cheapest
+    /// expression seen
+    pub fn simplify(&self, expr: &ASTNode) -> ASTNode {
+        self.derive(expr).result().clone()
+    }
+
+    /// Like [Rewriter::simplify], but returns every step taken to reach the
+    /// cheapest expression, along with the law it used
+    pub fn derive(&self, expr: &ASTNode) -> Derivation {
+        let mut derivation = self.trace(expr);
+
+        let costs = derivation.steps.iter().map(|step| self.cost(&step.result));
+        let mut best = (self.cost(&derivation.start), 0);
+
+        for (i, cost) in costs.enumerate() {
+            if cost < best.0 {
+                best = (cost, i + 1);
+            }
+        }
+
+        derivation.steps.truncate(best.1);
+        derivation
+    }
+
+    /// Tries to show `p` and `q` are equivalent by rewriting both until
+    /// they meet. The derivation goes from `p` to where they meet, and then
+    /// back up to `q` by applying `q`'s steps in reverse. [None] doesn't
+    /// mean they aren't equivalent, only that the rules didn't find a way.
+    pub fn prove_equivalent(&self, p: &ASTNode, q: &ASTNode) -> Option<Derivation> {
+        let p = self.trace(p);
+        let q = self.trace(q);
+
+        // where `q`'s trace first passes through each expression
+        let formulas = q.formulas().collect::<Vec<_>>();
+        let mut seen = HashMap::new();
+        for (i, expr) in formulas.iter().enumerate() {
+            seen.entry(*expr).or_insert(i);
+        }
+
+        let (meet, j) = p
+            .formulas()
+            .enumerate()
+            .find_map(|(i, expr)| seen.get(expr).map(|&j| (i, j)))?;
+
+        let mut steps = p.steps[..meet].to_vec();
+
+        // step `k` of `q`'s trace took formula `k` to formula `k + 1`, so
+        // undoing it leaves formula `k`
+        for k in (0..j).rev() {
+            steps.push(Step {
+                law: q.steps[k].law.clone(),
+                reversed: true,
+                position: q.steps[k].position.clone(),
+                result: formulas[k].clone(),
+            });
+        }
+
+        Some(Derivation {
+            start: p.start,
+            steps,
+        })
+    }
+
+    /// Rewrites `expr` until no rule applies or [Rewriter::max_steps] is
+    /// reached, keeping every step
+    fn trace(&self, expr: &ASTNode) -> Derivation {
+        let mut derivation = Derivation {
+            start: expr.strip_parens(),
+            steps: Vec::new(),
+        };
+
+        while derivation.steps.len() < self.max_steps {
+            let mut position = Vec::new();
+
+            let Some((rule, result)) = self.rewrite(derivation.result(), &mut position) else {
+                break;
+            };
+
+            derivation.steps.push(Step {
+                law: rule.name.clone(),
+                reversed: false,
+                position,
+                result,
+            });
+        }
+
+        derivation
+    }
+
+    /// Applies the first rule matching the outermost, leftmost subexpression
+    /// it can, returning the rule and the rewritten expression. `path` is
+    /// left holding the position of the subexpression, as child indices
+    /// from the root.
+    fn rewrite(&self, expr: &ASTNode, path: &mut Vec<usize>) -> Option<(&Rule, ASTNode)> {
+        for rule in &self.rules {
+            if let Some(result) = rule.apply(expr) {
+                return Some((rule, result));
+            }
+        }
+
+        for (index, child) in expr.children().into_iter().enumerate() {
+            path.push(index);
+
+            if let Some((rule, result)) = self.rewrite(child, path) {
+                return Some((rule, with_child(expr, index, result)));
+            }
+
+            path.pop();
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Engine;
+
+    fn simplify(input: &str) -> String {
+        Rewriter::builtin()
+            .simplify(&parse(input).unwrap())
+            .to_string()
+    }
+
+    #[test]
+    fn test_builtin_laws() {
+        assert_eq!(simplify("p && true"), "p");
+        assert_eq!(simplify("false || (q)"), "q");
+        assert_eq!(simplify("p && false"), "false");
+        assert_eq!(simplify("true || p"), "true");
+        assert_eq!(simplify("(p => q) && (p => q)"), "p => q");
+        assert_eq!(simplify("p && (p || q)"), "p");
+        assert_eq!(simplify("(q && p) || p"), "p");
+        assert_eq!(simplify("!!!p"), "!p");
+        assert_eq!(simplify("!(!p && !q)"), "p || q");
+        assert_eq!(simplify("!(p => !q) || false"), "p && q");
+    }
+
+    #[test]
+    fn test_simplification_is_equivalent() {
+        let engine = Engine::default();
+
+        for input in [
+            "!(p || !(q && true)) => r",
+            "(a && (a || b)) || !!(c && !c)",
+            "!(a && b) == (!a || !b)",
+        ] {
+            let expr = parse(input).unwrap();
+            let simplified = Rewriter::builtin().simplify(&expr);
+
+            assert!(engine.check_equivalent(&expr, &simplified).unwrap());
+            assert!(size(&simplified) <= size(&expr.strip_parens()));
+        }
+    }
+
+    #[test]
+    fn test_prove_equivalent() {
+        let rewriter = Rewriter::builtin();
+        let p = parse("!(a && b) || false").unwrap();
+        let q = parse("!a || !b").unwrap();
+
+        let derivation = rewriter.prove_equivalent(&p, &q).unwrap();
+        assert_eq!(derivation.result(), &q);
+        assert!(derivation.steps.iter().all(|step| !step.reversed));
+
+        // the other way around, the same steps are undone
+        let derivation = rewriter.prove_equivalent(&q, &p).unwrap();
+        assert_eq!(derivation.result(), &p.strip_parens());
+
+        let laws = derivation
+            .steps
+            .iter()
+            .map(|step| step.justification())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            laws,
+            [
+                "De Morgan (reversed) at root",
+                "Identity (reversed) at root"
+            ]
+        );
+
+        assert_eq!(
+            rewriter.prove_equivalent(&parse("a").unwrap(), &parse("b").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_pattern_variables_bind_consistently() {
+        let rule = Rule::parse("Idempotence", "p && p == p").unwrap();
+
+        let expr = parse("(a || b) && (a || b)").unwrap().strip_parens();
+        assert_eq!(rule.apply(&expr).unwrap().to_string(), "a || b");
+
+        let expr = parse("(a || b) && (b || c)").unwrap().strip_parens();
+        assert_eq!(rule.apply(&expr), None);
+    }
+
+    #[test]
+    fn test_builtin_law_files_are_rules() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/laws");
+        let mut files = 0;
+
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let law = std::fs::read_to_string(&path).unwrap();
+
+            assert!(
+                Rule::parse("law", &law).is_ok(),
+                "{} isn't a rule",
+                path.display()
+            );
+            assert!(
+                BUILTIN_LAWS.iter().any(|(_, builtin)| *builtin == law),
+                "{} isn't builtin",
+                path.display()
+            );
+
+            files += 1;
+        }
+
+        assert_eq!(files, 6);
+    }
+
+    #[test]
+    fn test_builtin_law_files_match_examples() {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/laws");
+        let examples = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../examples");
+
+        // the copies in examples/ must not drift from what simplify uses
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let copy = examples.join(path.file_name().unwrap());
+
+            assert_eq!(
+                std::fs::read(&path).unwrap(),
+                std::fs::read(&copy).unwrap(),
+                "{} differs from {}",
+                copy.display(),
+                path.display()
+            );
+        }
+    }
+
+    #[test]
+    fn test_user_rules_from_examples() {
+        let mut rules = Vec::new();
+
+        for entry in std::fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/../examples")).unwrap()
+        {
+            let path = entry.unwrap().path();
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            let law = std::fs::read_to_string(&path).unwrap();
+
+            rules.push(Rule::parse(name, &law).unwrap());
+        }
+
+        let distributive = rules
+            .iter()
+            .find(|rule| rule.name == "distributive_law_1")
+            .unwrap();
+        let expr = parse("x && (y || !z)").unwrap().strip_parens();
+
+        assert_eq!(
+            distributive.apply(&expr).unwrap().to_string(),
+            "x && y || x && !z"
+        );
+
+        // the laws make a rewriter of their own
+        let rewriter = Rewriter::new().with_rules(rules);
+        assert_eq!(
+            rewriter
+                .simplify(&parse("!!(a || a && b)").unwrap())
+                .to_string(),
+            "a"
+        );
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        assert!(matches!(
+            Rule::parse("Not a law", "p && q"),
+            Err(EngineError::InvalidRule { .. })
+        ));
+
+        // absorption can't be applied backwards, as nothing says what q is
+        let absorption = Rule::parse("Absorption", "p && (p || q) == p").unwrap();
+        assert_eq!(
+            absorption.reversed().unwrap_err().to_string(),
+            "invalid rule `Absorption`: `q` only appears on the right hand side"
+        );
+    }
+
+    #[test]
+    fn test_max_steps() {
+        // commutativity never runs out of rewrites
+        let commutativity = Rule::parse("Commutativity", "p && q == q && p").unwrap();
+        let rewriter = Rewriter::new().with_rules([commutativity]);
+
+        assert_eq!(
+            rewriter.simplify(&parse("a && b").unwrap()).to_string(),
+            "a && b"
+        );
+    }
+
+    #[test]
+    fn test_custom_cost() {
+        // preferring fewer negations, implication elimination never pays off
+        let negations = |expr: &ASTNode| {
+            expr.preorder()
+                .into_iter()
+                .filter(|node| matches!(node, ASTNode::Not(_)))
+                .count()
+        };
+
+        let rewriter = Rewriter::builtin().with_cost(negations);
+        assert_eq!(
+            rewriter.simplify(&parse("!(!p && q)").unwrap()).to_string(),
+            "p || !q"
+        );
+    }
+}
@@ -0,0 +1,731 @@
+use std::{fmt, mem, ops::Not};
+
+/// A variable of a [Solver], numbered from zero
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Var(pub u32);
+
+impl Var {
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+
+    /// The literal that's true when this variable is `value`
+    pub fn lit(self, value: bool) -> Lit {
+        Lit(self.0 << 1 | !value as u32)
+    }
+}
+
+/// A variable or its negation. The lowest bit is set for negations, so a
+/// literal and its negation are always next to each other when used as an
+/// index.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Lit(u32);
+
+impl Lit {
+    pub fn var(self) -> Var {
+        Var(self.0 >> 1)
+    }
+
+    /// Whether this is the variable itself rather than its negation
+    pub fn is_positive(self) -> bool {
+        self.0 & 1 == 0
+    }
+
+    fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Not for Lit {
+    type Output = Lit;
+
+    fn not(self) -> Lit {
+        Lit(self.0 ^ 1)
+    }
+}
+
+impl fmt::Debug for Lit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.is_positive() {
+            f.write_str("!")?;
+        }
+
+        write!(f, "x{}", self.var().0)
+    }
+}
+
+/// Counters describing how much work a [Solver] has done
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stats {
+    pub decisions: u64,
+    pub propagations: u64,
+    pub conflicts: u64,
+    pub restarts: u64,
+    pub learnt_clauses: u64,
+}
+
+/// How much a variable's activity grows relative to the rest on each
+/// conflict it's involved in. Older conflicts count for less and less.
+const ACTIVITY_DECAY: f64 = 0.95;
+
+/// Conflicts per unit of the Luby restart sequence
+const RESTART_BASE: u64 = 100;
+
+/// A conflict-driven clause learning SAT solver.
+///
+/// Clauses are added up front with [Solver::add_clause], and [Solver::solve]
+/// then searches for an assignment satisfying all of them. The search is the
+/// usual CDCL loop:
+///
+/// - unit propagation using two watched literals per clause, so only clauses
+///   watching a literal that just became false are ever looked at
+/// - on a conflict, a clause is learnt at the first unique implication point
+///   and the search backjumps to the level where that clause becomes unit
+/// - branching picks the unassigned variable with the highest VSIDS activity,
+///   using the value it last had (phase saving)
+/// - the search restarts after a number of conflicts that follows the Luby
+///   sequence, keeping everything learnt so far
+#[derive(Debug, Clone, Default)]
+pub struct Solver {
+    clauses: Vec<Vec<Lit>>,
+
+    /// The clauses watching each literal, indexed by [Lit]. Clauses watch
+    /// their first two literals.
+    watches: Vec<Vec<usize>>,
+
+    /// Whether the clauses added so far might still be satisfiable
+    unsat: bool,
+
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+
+    /// The clause that forced each variable's value, or [None] for decisions
+    /// and top level facts. A reason clause always has the literal it forced
+    /// first.
+    reasons: Vec<Option<usize>>,
+
+    /// Every assigned literal, in the order it was assigned
+    trail: Vec<Lit>,
+
+    /// Where on the trail each decision level starts
+    trail_limits: Vec<usize>,
+
+    /// How much of the trail has been propagated
+    propagated: usize,
+
+    activity: Vec<f64>,
+    activity_inc: f64,
+    order: VarHeap,
+
+    /// The value each variable had when it was last unassigned
+    phases: Vec<bool>,
+
+    /// Scratch space for conflict analysis
+    seen: Vec<bool>,
+
+    stats: Stats,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Solver {
+            activity_inc: 1.0,
+            ..Solver::default()
+        }
+    }
+
+    pub fn new_var(&mut self) -> Var {
+        let var = Var(self.values.len() as u32);
+
+        self.watches.push(Vec::new());
+        self.watches.push(Vec::new());
+        self.values.push(None);
+        self.levels.push(0);
+        self.reasons.push(None);
+        self.activity.push(0.0);
+        self.phases.push(false);
+        self.seen.push(false);
+        self.order.insert(var, &self.activity);
+
+        var
+    }
+
+    pub fn var_count(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.stats
+    }
+
+    /// Adds a clause, creating any variables it mentions that don't exist
+    /// yet. Returns `false` if the clauses are now known to be
+    /// unsatisfiable, which is always the case after adding an empty clause.
+    pub fn add_clause(&mut self, lits: &[Lit]) -> bool {
+        if self.unsat {
+            return false;
+        }
+
+        // clauses are only added at the top level, so anything assigned is
+        // a fact
+        self.cancel_until(0);
+
+        if let Some(max) = lits.iter().map(|lit| lit.var().index()).max() {
+            while self.var_count() <= max {
+                self.new_var();
+            }
+        }
+
+        let mut clause = lits.to_vec();
+        clause.sort();
+        clause.dedup();
+
+        // a clause with both a literal and its negation is always true, and
+        // so is one with a literal that's already true
+        let trivial = clause.windows(2).any(|pair| pair[0] == !pair[1])
+            || clause.iter().any(|lit| self.value(*lit) == Some(true));
+
+        if trivial {
+            return true;
+        }
+
+        clause.retain(|lit| self.value(*lit).is_none());
+
+        match clause.len() {
+            0 => self.unsat = true,
+            1 => {
+                self.assign(clause[0], None);
+                self.unsat = self.propagate().is_some();
+            }
+            _ => {
+                self.attach(clause);
+            }
+        }
+
+        !self.unsat
+    }
+
+    /// Searches for an assignment that satisfies every clause, returning the
+    /// value of each variable if there is one
+    pub fn solve(&mut self) -> Option<Vec<bool>> {
+        if self.unsat {
+            return None;
+        }
+
+        self.cancel_until(0);
+
+        let mut restarts = 0;
+        let mut conflicts = 0;
+
+        loop {
+            if let Some(conflict) = self.propagate() {
+                self.stats.conflicts += 1;
+                conflicts += 1;
+
+                if self.level() == 0 {
+                    self.unsat = true;
+                    return None;
+                }
+
+                let (learnt, level) = self.analyze(conflict);
+                self.cancel_until(level);
+
+                let asserting = learnt[0];
+                let reason = (learnt.len() > 1).then(|| self.attach(learnt));
+
+                self.assign(asserting, reason);
+                self.stats.learnt_clauses += 1;
+
+                self.activity_inc /= ACTIVITY_DECAY;
+
+                if conflicts >= luby(restarts) * RESTART_BASE {
+                    self.cancel_until(0);
+                    self.stats.restarts += 1;
+
+                    restarts += 1;
+                    conflicts = 0;
+                }
+            } else {
+                let Some(var) = self.pick_branch_var() else {
+                    let model = self.values.iter().map(|value| value.unwrap()).collect();
+                    return Some(model);
+                };
+
+                self.stats.decisions += 1;
+                self.trail_limits.push(self.trail.len());
+                self.assign(var.lit(self.phases[var.index()]), None);
+            }
+        }
+    }
+
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.values[lit.var().index()].map(|value| value == lit.is_positive())
+    }
+
+    fn level(&self) -> usize {
+        self.trail_limits.len()
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        let var = lit.var().index();
+
+        self.values[var] = Some(lit.is_positive());
+        self.levels[var] = self.level();
+        self.reasons[var] = reason;
+        self.trail.push(lit);
+    }
+
+    /// Adds a clause of at least two literals, watching the first two
+    fn attach(&mut self, clause: Vec<Lit>) -> usize {
+        let index = self.clauses.len();
+
+        self.watches[clause[0].index()].push(index);
+        self.watches[clause[1].index()].push(index);
+        self.clauses.push(clause);
+
+        index
+    }
+
+    /// Undoes every assignment made above decision level `level`
+    fn cancel_until(&mut self, level: usize) {
+        if self.level() <= level {
+            return;
+        }
+
+        let start = self.trail_limits[level];
+
+        for lit in self.trail.drain(start..) {
+            let var = lit.var();
+
+            self.values[var.index()] = None;
+            self.reasons[var.index()] = None;
+            self.phases[var.index()] = lit.is_positive();
+            self.order.insert(var, &self.activity);
+        }
+
+        self.trail_limits.truncate(level);
+        self.propagated = start;
+    }
+
+    /// Assigns every literal implied by the ones on the trail, returning the
+    /// clause that became false if there's a conflict
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let false_lit = !self.trail[self.propagated];
+            self.propagated += 1;
+            self.stats.propagations += 1;
+
+            let mut watching = mem::take(&mut self.watches[false_lit.index()]);
+            let mut kept = 0;
+            let mut conflict = None;
+
+            for i in 0..watching.len() {
+                let index = watching[i];
+
+                if conflict.is_some() {
+                    watching[kept] = index;
+                    kept += 1;
+                    continue;
+                }
+
+                // keep the literal that just became false second
+                let clause = &mut self.clauses[index];
+                if clause[0] == false_lit {
+                    clause.swap(0, 1);
+                }
+
+                let first = clause[0];
+                if self.values[first.var().index()] == Some(first.is_positive()) {
+                    watching[kept] = index;
+                    kept += 1;
+                    continue;
+                }
+
+                // look for another literal that isn't false to watch instead
+                let replacement = (2..clause.len()).find(|&k| {
+                    let lit = clause[k];
+                    self.values[lit.var().index()] != Some(!lit.is_positive())
+                });
+
+                if let Some(k) = replacement {
+                    clause.swap(1, k);
+                    let watch = clause[1];
+                    self.watches[watch.index()].push(index);
+                    continue;
+                }
+
+                // every other literal is false, so the first one has to be
+                // true
+                watching[kept] = index;
+                kept += 1;
+
+                match self.value(first) {
+                    Some(false) => conflict = Some(index),
+                    _ => self.assign(first, Some(index)),
+                }
+            }
+
+            watching.truncate(kept);
+            self.watches[false_lit.index()] = watching;
+
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+
+        None
+    }
+
+    /// Learns a clause from a conflict by resolving backwards along the
+    /// trail until only one literal from the current level is left, the
+    /// first unique implication point. Returns the clause, with the
+    /// literal it asserts first, and the level to backjump to.
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        // the asserting literal goes in the first slot once we know it
+        let mut learnt = vec![Lit(0)];
+
+        let mut pending = 0;
+        let mut clause = conflict;
+        let mut index = self.trail.len();
+        let mut resolved = None;
+
+        loop {
+            // a reason clause's first literal is the one being resolved on
+            let skip = resolved.is_some() as usize;
+
+            for k in skip..self.clauses[clause].len() {
+                let lit = self.clauses[clause][k];
+                let var = lit.var().index();
+
+                if self.seen[var] || self.levels[var] == 0 {
+                    continue;
+                }
+
+                self.seen[var] = true;
+                self.bump(lit.var());
+
+                if self.levels[var] == self.level() {
+                    pending += 1;
+                } else {
+                    learnt.push(lit);
+                }
+            }
+
+            // the next literal to resolve on is the latest one involved
+            let lit = loop {
+                index -= 1;
+
+                if self.seen[self.trail[index].var().index()] {
+                    break self.trail[index];
+                }
+            };
+
+            self.seen[lit.var().index()] = false;
+            resolved = Some(lit);
+            pending -= 1;
+
+            if pending == 0 {
+                break;
+            }
+
+            clause = self.reasons[lit.var().index()].expect("only decisions have no reason");
+        }
+
+        learnt[0] = !resolved.unwrap();
+
+        for lit in &learnt[1..] {
+            self.seen[lit.var().index()] = false;
+        }
+
+        // backjump to the level where the clause becomes unit, and make sure
+        // a literal from that level is watched
+        let mut level = 0;
+
+        if let Some(max) = (1..learnt.len()).max_by_key(|&k| self.levels[learnt[k].var().index()]) {
+            learnt.swap(1, max);
+            level = self.levels[learnt[1].var().index()];
+        }
+
+        (learnt, level)
+    }
+
+    fn bump(&mut self, var: Var) {
+        self.activity[var.index()] += self.activity_inc;
+
+        // keep activities from overflowing, without changing their order
+        if self.activity[var.index()] > 1e100 {
+            for activity in &mut self.activity {
+                *activity *= 1e-100;
+            }
+
+            self.activity_inc *= 1e-100;
+        }
+
+        self.order.increase(var, &self.activity);
+    }
+
+    fn pick_branch_var(&mut self) -> Option<Var> {
+        while let Some(var) = self.order.pop(&self.activity) {
+            if self.values[var.index()].is_none() {
+                return Some(var);
+            }
+        }
+
+        None
+    }
+}
+
+/// The `i`th element of the Luby sequence 1, 1, 2, 1, 1, 2, 4, 1, 1, 2, ...
+fn luby(mut i: u64) -> u64 {
+    // find the smallest complete subsequence containing i, then walk down
+    // into it
+    let mut size = 1;
+    let mut power = 0;
+
+    while size < i + 1 {
+        power += 1;
+        size = 2 * size + 1;
+    }
+
+    while size - 1 != i {
+        size = (size - 1) >> 1;
+        power -= 1;
+        i %= size;
+    }
+
+    1 << power
+}
+
+/// A max heap of variables ordered by activity, which can find where a
+/// variable is in order to move it up when its activity grows
+#[derive(Debug, Clone, Default)]
+struct VarHeap {
+    heap: Vec<Var>,
+
+    /// Where each variable is in `heap`, if it's there at all
+    positions: Vec<Option<usize>>,
+}
+
+impl VarHeap {
+    fn insert(&mut self, var: Var, activity: &[f64]) {
+        if var.index() >= self.positions.len() {
+            self.positions.resize(var.index() + 1, None);
+        }
+
+        if self.positions[var.index()].is_some() {
+            return;
+        }
+
+        self.positions[var.index()] = Some(self.heap.len());
+        self.heap.push(var);
+        self.sift_up(self.heap.len() - 1, activity);
+    }
+
+    /// Restores the heap order after `var`'s activity increased
+    fn increase(&mut self, var: Var, activity: &[f64]) {
+        if let Some(Some(pos)) = self.positions.get(var.index()) {
+            self.sift_up(*pos, activity);
+        }
+    }
+
+    fn pop(&mut self, activity: &[f64]) -> Option<Var> {
+        let top = *self.heap.first()?;
+        let last = self.heap.pop().unwrap();
+
+        self.positions[top.index()] = None;
+
+        if !self.heap.is_empty() {
+            self.heap[0] = last;
+            self.positions[last.index()] = Some(0);
+            self.sift_down(0, activity);
+        }
+
+        Some(top)
+    }
+
+    fn sift_up(&mut self, mut pos: usize, activity: &[f64]) {
+        while pos > 0 {
+            let parent = (pos - 1) / 2;
+
+            if activity[self.heap[parent].index()] >= activity[self.heap[pos].index()] {
+                break;
+            }
+
+            self.swap(pos, parent);
+            pos = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut pos: usize, activity: &[f64]) {
+        loop {
+            let mut largest = pos;
+
+            for child in [2 * pos + 1, 2 * pos + 2] {
+                if child < self.heap.len()
+                    && activity[self.heap[child].index()] > activity[self.heap[largest].index()]
+                {
+                    largest = child;
+                }
+            }
+
+            if largest == pos {
+                break;
+            }
+
+            self.swap(pos, largest);
+            pos = largest;
+        }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.positions[self.heap[a].index()] = Some(a);
+        self.positions[self.heap[b].index()] = Some(b);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Parses clauses written DIMACS style, with variables numbered from one
+    /// and negative numbers for negations
+    fn solver(clauses: &[&[i32]]) -> Solver {
+        let mut solver = Solver::new();
+
+        for clause in clauses {
+            let lits = clause
+                .iter()
+                .map(|&n| Var(n.unsigned_abs() - 1).lit(n > 0))
+                .collect::<Vec<_>>();
+
+            solver.add_clause(&lits);
+        }
+
+        solver
+    }
+
+    fn satisfies(model: &[bool], clauses: &[Vec<Lit>]) -> bool {
+        clauses.iter().all(|clause| {
+            clause
+                .iter()
+                .any(|lit| model[lit.var().index()] == lit.is_positive())
+        })
+    }
+
+    /// `pigeons` pigeons in `holes` holes, with at most one pigeon per hole
+    fn pigeonhole(pigeons: u32, holes: u32) -> Solver {
+        let mut solver = Solver::new();
+        let var = |pigeon: u32, hole: u32| Var(pigeon * holes + hole);
+
+        for pigeon in 0..pigeons {
+            let lits = (0..holes)
+                .map(|hole| var(pigeon, hole).lit(true))
+                .collect::<Vec<_>>();
+
+            solver.add_clause(&lits);
+        }
+
+        for hole in 0..holes {
+            for a in 0..pigeons {
+                for b in a + 1..pigeons {
+                    solver.add_clause(&[var(a, hole).lit(false), var(b, hole).lit(false)]);
+                }
+            }
+        }
+
+        solver
+    }
+
+    #[test]
+    fn test_luby() {
+        let sequence = (0..15).map(luby).collect::<Vec<_>>();
+
+        assert_eq!(sequence, [1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+    }
+
+    #[test]
+    fn test_satisfiable() {
+        let mut solver = solver(&[&[1, 2], &[-1, 3], &[-2, -3], &[2, 3]]);
+
+        let model = solver.solve().unwrap();
+        assert!(satisfies(&model, &solver.clauses));
+        assert!(model[0] != model[1]);
+    }
+
+    #[test]
+    fn test_unsatisfiable() {
+        let mut solver = solver(&[&[1, 2], &[-1, 2], &[1, -2], &[-1, -2]]);
+
+        assert_eq!(solver.solve(), None);
+    }
+
+    #[test]
+    fn test_empty_clause() {
+        let mut solver = Solver::new();
+
+        assert!(solver.add_clause(&[Var(0).lit(true), Var(0).lit(false)]));
+        assert!(!solver.add_clause(&[]));
+        assert_eq!(solver.solve(), None);
+    }
+
+    #[test]
+    fn test_unit_clauses() {
+        let mut solver = solver(&[&[1], &[-1, 2], &[-2, 3]]);
+
+        assert_eq!(solver.solve(), Some(vec![true, true, true]));
+        assert!(!solver.add_clause(&[Var(2).lit(false)]));
+    }
+
+    #[test]
+    fn test_pigeonhole() {
+        let mut solver = pigeonhole(6, 5);
+        assert_eq!(solver.solve(), None);
+
+        // this can't be refuted by propagation alone
+        assert!(solver.stats().conflicts > 0);
+        assert!(solver.stats().learnt_clauses > 0);
+
+        let mut solver = pigeonhole(5, 5);
+        assert!(solver.solve().is_some());
+    }
+
+    #[test]
+    fn test_random_3sat_matches_brute_force() {
+        // a small linear congruential generator, so the test is repeatable
+        let mut state = 0x2545_F491_4F6C_DD1D_u64;
+        let mut next = move |bound: u32| {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+
+            (state >> 33) as u32 % bound
+        };
+
+        for _ in 0..200 {
+            let vars = 10;
+            let clauses = (0..43)
+                .map(|_| {
+                    (0..3)
+                        .map(|_| Var(next(vars)).lit(next(2) == 0))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+
+            let mut solver = Solver::new();
+            for clause in &clauses {
+                solver.add_clause(clause);
+            }
+
+            let expected = (0..1u32 << vars).any(|row| {
+                let model = (0..vars).map(|v| row >> v & 1 == 1).collect::<Vec<_>>();
+                satisfies(&model, &clauses)
+            });
+
+            match solver.solve() {
+                Some(model) => assert!(satisfies(&model, &clauses)),
+                None => assert!(!expected, "missed a solution to {clauses:?}"),
+            }
+        }
+    }
+}
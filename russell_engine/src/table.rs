@@ -0,0 +1,529 @@
+use std::fmt::Write;
+
+use russell_ast::{ASTNode, Notation, Symbol};
+
+use crate::{BLOCK_ROWS, BlockEvaluator, EngineError, Program, Result, collect_variables};
+
+/// How a [Table] is written out
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TableFormat {
+    /// Aligned columns drawn with Unicode box drawing characters
+    #[default]
+    Unicode,
+    Markdown,
+    Csv,
+
+    /// A LaTeX `tabular` environment, with the formulas in math mode
+    Latex,
+    Html,
+}
+
+impl TableFormat {
+    /// The name of each format, as accepted by [TableFormat::from_name]
+    pub const NAMES: &[&str] = &["unicode", "markdown", "csv", "latex", "html"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "unicode" => Some(TableFormat::Unicode),
+            "markdown" => Some(TableFormat::Markdown),
+            "csv" => Some(TableFormat::Csv),
+            "latex" => Some(TableFormat::Latex),
+            "html" => Some(TableFormat::Html),
+            _ => None,
+        }
+    }
+}
+
+/// How truth values are written in a [Table]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ValueStyle {
+    /// `T` and `F`, as in logic textbooks
+    #[default]
+    Letters,
+
+    /// `1` and `0`, as in digital logic
+    Digits,
+}
+
+impl ValueStyle {
+    pub fn symbol(self, value: bool) -> &'static str {
+        match (self, value) {
+            (ValueStyle::Letters, true) => "T",
+            (ValueStyle::Letters, false) => "F",
+            (ValueStyle::Digits, true) => "1",
+            (ValueStyle::Digits, false) => "0",
+        }
+    }
+}
+
+/// Configures which columns a [Table] has. See [Table::builder].
+#[derive(Debug, Clone)]
+pub struct TableBuilder {
+    expr: ASTNode,
+    variables: Vec<Symbol>,
+    subformulas: bool,
+}
+
+impl TableBuilder {
+    /// Puts `order` first among the variable columns, with the rest of the
+    /// expression's variables after them in the order they appear. Variables
+    /// the expression doesn't use still get a column.
+    pub fn variables(mut self, order: impl IntoIterator<Item = Symbol>) -> Self {
+        let mut variables = Vec::new();
+
+        for var in order.into_iter().chain(self.variables) {
+            if !variables.contains(&var) {
+                variables.push(var);
+            }
+        }
+
+        self.variables = variables;
+        self
+    }
+
+    /// Whether to add a column for every subformula, innermost first, the
+    /// way textbooks work a table out. Otherwise only the whole expression
+    /// gets a column.
+    pub fn subformulas(mut self, subformulas: bool) -> Self {
+        self.subformulas = subformulas;
+        self
+    }
+
+    /// Evaluates every column, provided there are at most
+    /// [Table::MAX_VARIABLES] variables
+    pub fn build(self) -> Result<Table> {
+        let count = self.variables.len();
+
+        if count > Table::MAX_VARIABLES {
+            return Err(EngineError::TooManyVariables {
+                count,
+                limit: Table::MAX_VARIABLES,
+            });
+        }
+
+        let expr = self.expr.strip_parens();
+
+        let formulas = match self.subformulas {
+            true => {
+                let mut formulas = Vec::new();
+                subformulas(&expr, &mut formulas);
+
+                // a lone variable or constant has no compound subformulas,
+                // but still gets a column of its own
+                if formulas.last() != Some(&expr) {
+                    formulas.push(expr);
+                }
+
+                formulas
+            }
+
+            false => vec![expr],
+        };
+
+        let values = formulas
+            .iter()
+            .map(|formula| {
+                let mut evaluator =
+                    BlockEvaluator::new(Program::compile_with(formula, &self.variables)?);
+
+                Ok((0..evaluator.blocks())
+                    .map(|block| evaluator.eval(block))
+                    .collect())
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Table {
+            variables: self.variables,
+            formulas,
+            values,
+        })
+    }
+}
+
+/// The compound subformulas of `expr` in post-order, without duplicates,
+/// ending with `expr` itself
+fn subformulas(expr: &ASTNode, formulas: &mut Vec<ASTNode>) {
+    match expr {
+        ASTNode::Variable(_) | ASTNode::Literal(_) => return,
+
+        ASTNode::Not(inner) | ASTNode::Paren(inner) => subformulas(inner, formulas),
+
+        ASTNode::And(p, q)
+        | ASTNode::Or(p, q)
+        | ASTNode::Implies(p, q)
+        | ASTNode::Equivalent(p, q) => {
+            subformulas(p, formulas);
+            subformulas(q, formulas);
+        }
+    }
+
+    if !formulas.contains(expr) {
+        formulas.push(expr.clone());
+    }
+}
+
+/// A truth table laid out for printing: a column for each variable, then
+/// one for each formula, the last being the whole expression.
+///
+/// Rows count up in binary from every variable being false, with the
+/// leftmost variable as the most significant digit, as tables are usually
+/// written by hand. Note this is the reverse of
+/// [binary order](crate::Order::Binary), where the first variable changes
+/// fastest.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    variables: Vec<Symbol>,
+    formulas: Vec<ASTNode>,
+
+    /// For each formula, its results block by block in binary order
+    values: Vec<Vec<u64>>,
+}
+
+impl Table {
+    /// Rows are rendered one by one, so this is well below
+    /// [Engine::MAX_VARIABLES](crate::Engine::MAX_VARIABLES)
+    pub const MAX_VARIABLES: usize = 16;
+
+    /// A table with the variables in the order they appear and a column for
+    /// just the whole expression
+    pub fn builder(expr: &ASTNode) -> TableBuilder {
+        TableBuilder {
+            variables: collect_variables(expr),
+            expr: expr.clone(),
+            subformulas: false,
+        }
+    }
+
+    pub fn variables(&self) -> &[Symbol] {
+        &self.variables
+    }
+
+    /// The formulas after the variable columns
+    pub fn formulas(&self) -> &[ASTNode] {
+        &self.formulas
+    }
+
+    pub fn rows(&self) -> usize {
+        1 << self.variables.len()
+    }
+
+    /// The value of variable `index` in `row`
+    pub fn variable(&self, row: usize, index: usize) -> bool {
+        let n = self.variables.len();
+
+        (row >> (n - 1 - index)) & 1 == 1
+    }
+
+    /// The value of formula `index` in `row`
+    pub fn value(&self, row: usize, index: usize) -> bool {
+        // the evaluator's row has the first variable as its lowest bit
+        let n = self.variables.len() as u32;
+        let row = match n {
+            0 => 0,
+            _ => (row as u64).reverse_bits() >> (u64::BITS - n),
+        };
+
+        (self.values[index][(row / BLOCK_ROWS) as usize] >> (row % BLOCK_ROWS)) & 1 == 1
+    }
+
+    /// Every value in `row`, variables first
+    fn row(&self, row: usize) -> impl Iterator<Item = bool> + '_ {
+        let variables = (0..self.variables.len()).map(move |i| self.variable(row, i));
+        let formulas = (0..self.formulas.len()).map(move |i| self.value(row, i));
+
+        variables.chain(formulas)
+    }
+
+    /// The header of every column, variables first
+    fn headers(&self, notation: &Notation) -> Vec<String> {
+        let variables = self
+            .variables
+            .iter()
+            .map(|var| ASTNode::Variable(*var).display(notation).to_string());
+        let formulas = self
+            .formulas
+            .iter()
+            .map(|formula| formula.display(notation).to_string());
+
+        variables.chain(formulas).collect()
+    }
+
+    pub fn render(&self, format: TableFormat, style: ValueStyle) -> String {
+        match format {
+            TableFormat::Unicode => self.to_unicode(style),
+            TableFormat::Markdown => self.to_markdown(style),
+            TableFormat::Csv => self.to_csv(style),
+            TableFormat::Latex => self.to_latex(style),
+            TableFormat::Html => self.to_html(style),
+        }
+    }
+
+    /// The table drawn with box drawing characters, with every value
+    /// centred under its header
+    pub fn to_unicode(&self, style: ValueStyle) -> String {
+        let headers = self.headers(&Notation::UNICODE);
+        let widths = headers
+            .iter()
+            .map(|header| header.chars().count().max(1))
+            .collect::<Vec<_>>();
+
+        let rule = |left: &str, middle: &str, right: &str| {
+            let lines = widths
+                .iter()
+                .map(|width| "─".repeat(width + 2))
+                .collect::<Vec<_>>();
+
+            format!("{left}{}{right}\n", lines.join(middle))
+        };
+        let line = |cells: Vec<&str>| {
+            let cells = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!(" {cell:^width$} "))
+                .collect::<Vec<_>>();
+
+            format!("│{}│\n", cells.join("│"))
+        };
+
+        let mut out = rule("┌", "┬", "┐");
+        out += &line(headers.iter().map(String::as_str).collect());
+        out += &rule("├", "┼", "┤");
+
+        for row in 0..self.rows() {
+            out += &line(self.row(row).map(|value| style.symbol(value)).collect());
+        }
+
+        out + &rule("└", "┴", "┘")
+    }
+
+    /// A Markdown table, with `|` escaped in the headers
+    pub fn to_markdown(&self, style: ValueStyle) -> String {
+        let headers = self
+            .headers(&Notation::RUSSELL)
+            .iter()
+            .map(|header| format!("`{}`", header.replace('|', "\\|")))
+            .collect::<Vec<_>>();
+        let widths = headers
+            .iter()
+            .map(|header| header.len().max(3))
+            .collect::<Vec<_>>();
+
+        let line = |cells: Vec<&str>| {
+            let cells = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!(" {cell:^width$} "))
+                .collect::<Vec<_>>();
+
+            format!("|{}|\n", cells.join("|"))
+        };
+
+        let mut out = line(headers.iter().map(String::as_str).collect());
+
+        let rules = widths
+            .iter()
+            .map(|width| format!(":{}:", "-".repeat(*width)))
+            .collect::<Vec<_>>();
+        out += &format!("|{}|\n", rules.join("|"));
+
+        for row in 0..self.rows() {
+            out += &line(self.row(row).map(|value| style.symbol(value)).collect());
+        }
+
+        out
+    }
+
+    /// Comma separated values with a header line, quoting headers where
+    /// needed
+    pub fn to_csv(&self, style: ValueStyle) -> String {
+        let quote = |field: &String| match field.contains([',', '"', '\n']) {
+            true => format!("\"{}\"", field.replace('"', "\"\"")),
+            false => field.clone(),
+        };
+
+        let headers = self.headers(&Notation::RUSSELL);
+        let mut out = headers.iter().map(quote).collect::<Vec<_>>().join(",") + "\n";
+
+        for row in 0..self.rows() {
+            let values = self.row(row).map(|value| style.symbol(value));
+            out += &(values.collect::<Vec<_>>().join(",") + "\n");
+        }
+
+        out
+    }
+
+    /// A `tabular` environment, with a rule between the variables and the
+    /// formulas
+    pub fn to_latex(&self, style: ValueStyle) -> String {
+        let headers = self
+            .headers(&Notation::LATEX)
+            .iter()
+            .map(|header| format!("${header}$"))
+            .collect::<Vec<_>>();
+
+        let spec = format!(
+            "{}|{}",
+            "c".repeat(self.variables.len()),
+            "c".repeat(self.formulas.len())
+        );
+
+        let mut out = format!("\\begin{{tabular}}{{{spec}}}\n");
+        let _ = writeln!(out, "{} \\\\", headers.join(" & "));
+        out += "\\hline\n";
+
+        for row in 0..self.rows() {
+            let values = self.row(row).map(|value| style.symbol(value));
+            let _ = writeln!(out, "{} \\\\", values.collect::<Vec<_>>().join(" & "));
+        }
+
+        out + "\\end{tabular}\n"
+    }
+
+    /// An HTML `table`, with the headers in a `thead`
+    pub fn to_html(&self, style: ValueStyle) -> String {
+        let cells = |tag: &str, cells: Vec<String>| {
+            let cells = cells
+                .iter()
+                .map(|cell| format!("<{tag}>{}</{tag}>", escape(cell)))
+                .collect::<String>();
+
+            format!("    <tr>{cells}</tr>\n")
+        };
+
+        let mut out = "<table>\n  <thead>\n".to_string();
+        out += &cells("th", self.headers(&Notation::UNICODE));
+        out += "  </thead>\n  <tbody>\n";
+
+        for row in 0..self.rows() {
+            let values = self.row(row).map(|value| style.symbol(value).to_string());
+            out += &cells("td", values.collect());
+        }
+
+        out + "  </tbody>\n</table>\n"
+    }
+}
+
+/// Escapes text for HTML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Engine;
+
+    fn parse(input: &str) -> ASTNode {
+        Engine::default().parse(input).unwrap()
+    }
+
+    #[test]
+    fn test_subformulas() {
+        let table = Table::builder(&parse("!(p && q) == (!p || !q)"))
+            .subformulas(true)
+            .build()
+            .unwrap();
+
+        let formulas = table
+            .formulas()
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            formulas,
+            [
+                "p && q",
+                "!(p && q)",
+                "!p",
+                "!q",
+                "!p || !q",
+                "!(p && q) == !p || !q"
+            ]
+        );
+        assert!((0..table.rows()).all(|row| table.value(row, formulas.len() - 1)));
+
+        for atom in ["p", "true"] {
+            let table = Table::builder(&parse(atom))
+                .subformulas(true)
+                .build()
+                .unwrap();
+
+            assert_eq!(table.formulas(), [parse(atom)]);
+        }
+
+        let table = Table::builder(&parse("true"))
+            .subformulas(true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            table.to_markdown(ValueStyle::Letters),
+            "| `true` |\n|:------:|\n|   T    |\n"
+        );
+    }
+
+    #[test]
+    fn test_row_order() {
+        let table = Table::builder(&parse("p && !q"))
+            .variables([Symbol::intern("q")])
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            table.variables(),
+            [Symbol::intern("q"), Symbol::intern("p")]
+        );
+
+        // q p: 00, 01, 10, 11
+        let values = (0..4).map(|row| table.value(row, 0)).collect::<Vec<_>>();
+        assert_eq!(values, [false, true, false, false]);
+    }
+
+    #[test]
+    fn test_formats() {
+        let table = Table::builder(&parse("p || q")).build().unwrap();
+
+        assert_eq!(
+            table.to_unicode(ValueStyle::Letters),
+            "┌───┬───┬───────┐\n\
+             │ p │ q │ p ∨ q │\n\
+             ├───┼───┼───────┤\n\
+             │ F │ F │   F   │\n\
+             │ F │ T │   T   │\n\
+             │ T │ F │   T   │\n\
+             │ T │ T │   T   │\n\
+             └───┴───┴───────┘\n"
+        );
+        assert_eq!(
+            table.to_markdown(ValueStyle::Digits),
+            "| `p` | `q` | `p \\|\\| q` |\n\
+             |:---:|:---:|:----------:|\n\
+             |  0  |  0  |     0      |\n\
+             |  0  |  1  |     1      |\n\
+             |  1  |  0  |     1      |\n\
+             |  1  |  1  |     1      |\n"
+        );
+        assert_eq!(
+            table.to_csv(ValueStyle::Digits),
+            "p,q,p || q\n0,0,0\n0,1,1\n1,0,1\n1,1,1\n"
+        );
+        assert_eq!(
+            table.to_latex(ValueStyle::Letters),
+            "\\begin{tabular}{cc|c}\n\
+             $p$ & $q$ & $p \\lor q$ \\\\\n\
+             \\hline\n\
+             F & F & F \\\\\n\
+             F & T & T \\\\\n\
+             T & F & T \\\\\n\
+             T & T & T \\\\\n\
+             \\end{tabular}\n"
+        );
+        assert!(
+            table
+                .to_html(ValueStyle::Letters)
+                .contains("<tr><th>p</th><th>q</th><th>p ∨ q</th></tr>")
+        );
+    }
+}
@@ -0,0 +1,30 @@
+//! Inputs shared by the tests of several modules, and of the crates using
+//! the engine through the `testing` feature
+
+/// Formulas with constants, every operator, tautologies, contradictions and
+/// more variables than fit in a few rows, for checking that the different
+/// ways of evaluating and deciding agree with each other
+pub const FORMULAS: &[&str] = &[
+    "p",
+    "!p",
+    "true",
+    "!false && p",
+    "p || !p",
+    "p && !p",
+    "p && (p || q) == p",
+    "!(p && q) => r",
+    "(p => q) == (!q => !p)",
+    "a && b || c => d == e && !f",
+    "!(a == b) || (c => a && false) == (true || d)",
+    "(a || b) && (c || d) && (e || f) && (g || h) => !(i == j)",
+    "a1 && a2 && a3 && a4 && a5 && a6 && a7 && a8 && a9 && a10 && a11 && a12 || b",
+];
+
+/// The formula joining `count` variables, `v0` up to `v{count - 1}`, with
+/// `operator`, for inputs too wide to write out
+pub fn joined(count: usize, operator: &str) -> String {
+    (0..count)
+        .map(|i| format!("v{i}"))
+        .collect::<Vec<_>>()
+        .join(&format!(" {operator} "))
+}
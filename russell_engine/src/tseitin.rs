@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+
+use russell_ast::{ASTNode, Symbol};
+
+use crate::{
+    Assignments,
+    sat::{Lit, Solver, Var},
+};
+
+/// A formula in conjunctive normal form, over numbered variables.
+///
+/// The first [Cnf::variables] variables stand for the variables of the
+/// expression the formula was built from, in the order they first appear.
+/// Any after that are fresh ones introduced by the encoding.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cnf {
+    variables: Vec<Symbol>,
+    var_count: usize,
+    clauses: Vec<Vec<Lit>>,
+}
+
+impl Cnf {
+    /// Builds the Tseitin encoding of `expr` being `value`: a formula that's
+    /// satisfiable exactly when there's an assignment giving `expr` that
+    /// value, and whose models restricted to the named variables are those
+    /// assignments.
+    ///
+    /// Every operator gets a fresh variable defined to be equal to its
+    /// result, so the formula only grows linearly with `expr`, unlike
+    /// distributing the operators out. Constants are folded away first.
+    pub fn tseitin(expr: &ASTNode, value: bool) -> Cnf {
+        let mut encoder = Encoder::new(expr);
+
+        match encoder.encode(expr) {
+            Encoded::Lit(lit) => encoder.clauses.push(vec![if value { lit } else { !lit }]),
+            Encoded::Const(constant) if constant != value => encoder.clauses.push(vec![]),
+            Encoded::Const(_) => {}
+        }
+
+        Cnf {
+            variables: encoder.variables,
+            var_count: encoder.var_count as usize,
+            clauses: encoder.clauses,
+        }
+    }
+
+    /// The named variables, which are variables `0..variables().len()`
+    pub fn variables(&self) -> &[Symbol] {
+        &self.variables
+    }
+
+    /// How many variables there are, named and fresh
+    pub fn var_count(&self) -> usize {
+        self.var_count
+    }
+
+    pub fn clauses(&self) -> &[Vec<Lit>] {
+        &self.clauses
+    }
+
+    /// A solver loaded with every clause
+    pub fn solver(&self) -> Solver {
+        let mut solver = Solver::new();
+
+        for _ in 0..self.var_count {
+            solver.new_var();
+        }
+
+        for clause in &self.clauses {
+            solver.add_clause(clause);
+        }
+
+        solver
+    }
+
+    /// The values of the named variables in a model of the formula
+    pub fn assignments(&self, model: &[bool]) -> Assignments {
+        self.variables
+            .iter()
+            .zip(model)
+            .map(|(var, value)| (*var, *value))
+            .collect()
+    }
+}
+
+/// The result of encoding part of an expression: either a literal equal to
+/// it, or a constant if its value doesn't depend on any variable
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoded {
+    Lit(Lit),
+    Const(bool),
+}
+
+impl std::ops::Not for Encoded {
+    type Output = Encoded;
+
+    fn not(self) -> Encoded {
+        match self {
+            Encoded::Lit(lit) => Encoded::Lit(!lit),
+            Encoded::Const(value) => Encoded::Const(!value),
+        }
+    }
+}
+
+struct Encoder {
+    variables: Vec<Symbol>,
+    slots: HashMap<Symbol, Var>,
+    var_count: u32,
+    clauses: Vec<Vec<Lit>>,
+}
+
+impl Encoder {
+    /// Numbers the variables of `expr` before anything else, so fresh
+    /// variables all come after them
+    fn new(expr: &ASTNode) -> Self {
+        let mut variables = Vec::new();
+        let mut slots = HashMap::new();
+
+        for node in expr.preorder() {
+            if let ASTNode::Variable(symbol) = node {
+                slots.entry(*symbol).or_insert_with(|| {
+                    variables.push(*symbol);
+                    Var(variables.len() as u32 - 1)
+                });
+            }
+        }
+
+        Encoder {
+            var_count: variables.len() as u32,
+            variables,
+            slots,
+            clauses: Vec::new(),
+        }
+    }
+
+    /// Encodes `expr` bottom up. This uses an explicit stack rather than
+    /// recursion, since a long chain of operators nests as deeply as it is
+    /// long.
+    fn encode(&mut self, expr: &ASTNode) -> Encoded {
+        enum Task<'a> {
+            Visit(&'a ASTNode),
+
+            /// Combines the results of the node's operands, which are on top
+            /// of the stack
+            Combine(&'a ASTNode),
+        }
+
+        let mut tasks = vec![Task::Visit(expr)];
+        let mut results = Vec::new();
+
+        while let Some(task) = tasks.pop() {
+            match task {
+                Task::Visit(node) => match node {
+                    ASTNode::Variable(symbol) => {
+                        results.push(Encoded::Lit(self.slots[symbol].lit(true)));
+                    }
+                    ASTNode::Literal(value) => results.push(Encoded::Const(*value)),
+                    ASTNode::Paren(inner) => tasks.push(Task::Visit(inner)),
+                    ASTNode::Not(inner) => {
+                        tasks.push(Task::Combine(node));
+                        tasks.push(Task::Visit(inner));
+                    }
+                    ASTNode::And(p, q)
+                    | ASTNode::Or(p, q)
+                    | ASTNode::Implies(p, q)
+                    | ASTNode::Equivalent(p, q) => {
+                        tasks.push(Task::Combine(node));
+                        tasks.push(Task::Visit(q));
+                        tasks.push(Task::Visit(p));
+                    }
+                },
+
+                Task::Combine(ASTNode::Not(_)) => {
+                    let p = results.pop().unwrap();
+                    results.push(!p);
+                }
+
+                Task::Combine(node) => {
+                    let q = results.pop().unwrap();
+                    let p = results.pop().unwrap();
+
+                    let result = match node {
+                        ASTNode::And(..) => self.and(p, q),
+                        ASTNode::Or(..) => !self.and(!p, !q),
+                        ASTNode::Implies(..) => !self.and(p, !q),
+                        ASTNode::Equivalent(..) => self.equivalent(p, q),
+                        _ => unreachable!("only operators are combined"),
+                    };
+
+                    results.push(result);
+                }
+            }
+        }
+
+        results.pop().unwrap()
+    }
+
+    fn fresh(&mut self) -> Lit {
+        self.var_count += 1;
+
+        Var(self.var_count - 1).lit(true)
+    }
+
+    fn and(&mut self, p: Encoded, q: Encoded) -> Encoded {
+        let (p, q) = match (p, q) {
+            (Encoded::Const(false), _) | (_, Encoded::Const(false)) => {
+                return Encoded::Const(false);
+            }
+            (Encoded::Const(true), other) | (other, Encoded::Const(true)) => return other,
+            (Encoded::Lit(p), Encoded::Lit(q)) => (p, q),
+        };
+
+        // x == p && q
+        let x = self.fresh();
+        self.clauses.push(vec![!x, p]);
+        self.clauses.push(vec![!x, q]);
+        self.clauses.push(vec![x, !p, !q]);
+
+        Encoded::Lit(x)
+    }
+
+    fn equivalent(&mut self, p: Encoded, q: Encoded) -> Encoded {
+        let (p, q) = match (p, q) {
+            (Encoded::Const(value), other) | (other, Encoded::Const(value)) => {
+                return if value { other } else { !other };
+            }
+            (Encoded::Lit(p), Encoded::Lit(q)) => (p, q),
+        };
+
+        // x == (p == q)
+        let x = self.fresh();
+        self.clauses.push(vec![!x, !p, q]);
+        self.clauses.push(vec![!x, p, !q]);
+        self.clauses.push(vec![x, p, q]);
+        self.clauses.push(vec![x, !p, !q]);
+
+        Encoded::Lit(x)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Engine, testing::FORMULAS};
+
+    #[test]
+    fn test_models_match_truth_table() {
+        let engine = Engine::default();
+
+        for formula in FORMULAS {
+            let expr = engine.parse(formula).unwrap();
+
+            for value in [true, false] {
+                let cnf = Cnf::tseitin(&expr, value);
+
+                let expected = engine
+                    .truth_table(&expr)
+                    .unwrap()
+                    .any(|(_, result)| result == value);
+
+                match cnf.solver().solve() {
+                    Some(model) => {
+                        let assignments = cnf.assignments(&model);
+                        assert_eq!(engine.eval(&expr, &assignments), Ok(value), "{formula}");
+                    }
+                    None => assert!(!expected, "{formula} can be {value}"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_named_variables_first() {
+        let engine = Engine::default();
+        let expr = engine.parse("(p && q) || (q == r)").unwrap();
+
+        let cnf = Cnf::tseitin(&expr, true);
+
+        assert_eq!(
+            cnf.variables(),
+            ["p", "q", "r"].map(Symbol::intern).as_slice()
+        );
+
+        // one fresh variable for each binary operator
+        assert_eq!(cnf.var_count(), 3 + 3);
+    }
+
+    #[test]
+    fn test_constants_fold_away() {
+        let engine = Engine::default();
+
+        let cnf = Cnf::tseitin(&engine.parse("p && true || false").unwrap(), true);
+        assert_eq!(cnf.var_count(), 1);
+        assert_eq!(cnf.clauses(), [vec![Var(0).lit(true)]]);
+
+        // still mentions p, but can never be true
+        let cnf = Cnf::tseitin(&engine.parse("p && false").unwrap(), true);
+        assert_eq!(cnf.variables(), [Symbol::intern("p")]);
+        assert_eq!(cnf.clauses(), [vec![]]);
+    }
+}
@@ -1,9 +1,12 @@
 use std::collections::HashMap;
 
-use crate::{Assignments, Engine};
-use russell_ast::ASTNode;
+use crate::{Assignments, Engine, EngineError, ParseError, Report, Table, TableFormat, ValueStyle};
+use russell_ast::{ASTNode, Symbol};
 use wasm_bindgen::prelude::*;
 
+/// What the input is called in rendered diagnostics
+const INPUT_NAME: &str = "input";
+
 #[wasm_bindgen(start)]
 pub fn main() {
     // #[cfg(feature = "console_error_panic_hook")]
@@ -24,76 +27,110 @@ impl WasmEngine {
         }
     }
 
-    fn parse(&mut self, input: &str) -> Result<ASTNode, String> {
-        self.inner.parse(input).map_err(|e| format!("{:?}", e))
+    fn parse(&mut self, input: &str) -> Result<ASTNode, JsValue> {
+        self.inner
+            .parse(input)
+            .map_err(|errors| error_object(&errors.into(), input))
     }
 
+    /// Returns every parse error in `input` as an array of objects, so the
+    /// front end can underline them itself. The array is empty if `input`
+    /// parses.
     #[wasm_bindgen]
-    pub fn eval(&mut self, input: &str, assignments: JsValue) -> Result<bool, String> {
-        let assignments_map =
-            serde_wasm_bindgen::from_value::<HashMap<String, bool>>(assignments).unwrap();
+    pub fn diagnostics(&mut self, input: &str) -> JsValue {
+        let Err(errors) = self.inner.parse(input) else {
+            return js_sys::Array::new().into();
+        };
 
-        let assignments_map = assignments_map
+        errors
+            .iter()
+            .map(parse_error_object)
+            .collect::<js_sys::Array>()
+            .into()
+    }
+
+    #[wasm_bindgen]
+    pub fn eval(&mut self, input: &str, assignments: JsValue) -> Result<bool, JsValue> {
+        let assignments_map = serde_wasm_bindgen::from_value::<HashMap<String, bool>>(assignments)
+            .map_err(|e| argument_error(format!("invalid assignments: {e}")))?;
+
+        let assignments = assignments_map
             .into_iter()
-            .map(|(k, v)| (k.chars().next().unwrap(), v))
-            .collect::<HashMap<char, bool>>();
+            .map(|(k, v)| (Symbol::intern(&k), v))
+            .collect::<Assignments>();
 
         self.inner
-            .eval_str(input, &Assignments(assignments_map))
-            .map_err(|e| format!("{:?}", e))
+            .eval_str(input, &assignments)
+            .map_err(|e| error_object(&e, input))
     }
 
     #[wasm_bindgen]
-    pub fn check_tautology(&mut self, input: &str) -> Result<bool, String> {
+    pub fn check_tautology(&mut self, input: &str) -> Result<bool, JsValue> {
         let expr = self.parse(input)?;
 
         self.inner
             .check_tautology(&expr)
-            .map_err(|e| format!("{:?}", e))
+            .map_err(|e| error_object(&e, input))
     }
 
     #[wasm_bindgen]
-    pub fn check_contradiction(&mut self, input: &str) -> Result<bool, String> {
+    pub fn check_contradiction(&mut self, input: &str) -> Result<bool, JsValue> {
         let expr = self.parse(input)?;
 
         self.inner
             .check_contradiction(&expr)
-            .map_err(|e| format!("{:?}", e))
+            .map_err(|e| error_object(&e, input))
     }
 
     #[wasm_bindgen]
-    pub fn check_contingency(&mut self, input: &str) -> Result<bool, String> {
+    pub fn check_contingency(&mut self, input: &str) -> Result<bool, JsValue> {
         let expr = self.parse(input)?;
 
         self.inner
             .check_contingency(&expr)
-            .map_err(|e| format!("{:?}", e))
+            .map_err(|e| error_object(&e, input))
     }
 
+    /// Returns an assignment that makes `input` false as an object mapping
+    /// variable names to values, or `null` if it's a tautology
     #[wasm_bindgen]
-    pub fn compute_truth_table(&mut self, input: &str) -> Result<JsValue, String> {
+    pub fn find_counterexample(&mut self, input: &str) -> Result<JsValue, JsValue> {
         let expr = self.parse(input)?;
-        let variables = self.inner.collect_variables(&expr);
-        let rows = self.inner.compute_assignments(variables);
 
-        let table = js_sys::Array::new();
+        let counterexample = self
+            .inner
+            .find_counterexample(&expr)
+            .map_err(|e| error_object(&e, input))?;
+
+        Ok(counterexample.map_or(JsValue::NULL, |a| assignments_to_object(&a).into()))
+    }
 
-        for assignments in rows {
-            let result = self
-                .inner
-                .eval(&expr, &assignments)
-                .map_err(|e| format!("{:?}", e))?;
+    /// Returns an assignment that makes `input` true as an object mapping
+    /// variable names to values, or `null` if it's a contradiction
+    #[wasm_bindgen]
+    pub fn find_witness(&mut self, input: &str) -> Result<JsValue, JsValue> {
+        let expr = self.parse(input)?;
+
+        let witness = self
+            .inner
+            .find_witness(&expr)
+            .map_err(|e| error_object(&e, input))?;
+
+        Ok(witness.map_or(JsValue::NULL, |a| assignments_to_object(&a).into()))
+    }
+
+    #[wasm_bindgen]
+    pub fn compute_truth_table(&mut self, input: &str) -> Result<JsValue, JsValue> {
+        let expr = self.parse(input)?;
+        let rows = self
+            .inner
+            .truth_table(&expr)
+            .map_err(|e| error_object(&e, input))?;
 
-            let row = js_sys::Object::new();
+        let table = js_sys::Array::new();
 
-            for (var, value) in &assignments.0 {
-                js_sys::Reflect::set(
-                    &row,
-                    &JsValue::from_str(&var.to_string()),
-                    &JsValue::from_bool(*value),
-                )
-                .unwrap();
-            }
+        for (assignments, result) in rows {
+            let row = assignments_to_object(&assignments);
 
             js_sys::Reflect::set(
                 &row,
@@ -107,4 +144,225 @@ impl WasmEngine {
 
         Ok(table.into())
     }
+
+    /// Renders the truth table of `input` in one of [TableFormat::NAMES],
+    /// with `1`/`0` instead of `T`/`F` if `digits` is set and a column per
+    /// subformula if `subformulas` is
+    #[wasm_bindgen]
+    pub fn render_truth_table(
+        &mut self,
+        input: &str,
+        format: &str,
+        digits: bool,
+        subformulas: bool,
+    ) -> Result<String, JsValue> {
+        let expr = self.parse(input)?;
+
+        let format = TableFormat::from_name(format)
+            .ok_or_else(|| argument_error(format!("unknown table format `{format}`")))?;
+        let style = match digits {
+            true => ValueStyle::Digits,
+            false => ValueStyle::Letters,
+        };
+
+        let table = Table::builder(&expr)
+            .subformulas(subformulas)
+            .build()
+            .map_err(|e| error_object(&e, input))?;
+
+        Ok(table.render(format, style))
+    }
+
+    /// Returns the Karnaugh map of `input` as an object with the variables
+    /// along each axis, their Gray code labels, the cells row by row as
+    /// `"0"`, `"1"` or `"-"`, and a group of `[row, column]` cells for each
+    /// prime implicant
+    #[wasm_bindgen]
+    pub fn karnaugh_map(&mut self, input: &str) -> Result<JsValue, JsValue> {
+        let expr = self.parse(input)?;
+        let map = self
+            .inner
+            .karnaugh_map(&expr)
+            .map_err(|e| error_object(&e, input))?;
+
+        let strings = |strings: Vec<String>| {
+            strings
+                .iter()
+                .map(|string| JsValue::from_str(string))
+                .collect::<js_sys::Array>()
+        };
+        let names =
+            |vars: &[Symbol]| strings(vars.iter().map(|var| var.as_str().to_string()).collect());
+
+        let cells = (0..map.rows())
+            .map(|row| {
+                (0..map.columns())
+                    .map(|column| JsValue::from_str(&map.cell(row, column).symbol().to_string()))
+                    .collect::<js_sys::Array>()
+            })
+            .collect::<js_sys::Array>();
+
+        let groups = map
+            .groups()
+            .iter()
+            .map(|group| {
+                let cells = map
+                    .group_cells(group)
+                    .into_iter()
+                    .map(|(row, column)| {
+                        [row, column]
+                            .into_iter()
+                            .map(|index| JsValue::from_f64(index as f64))
+                            .collect::<js_sys::Array>()
+                    })
+                    .collect::<js_sys::Array>();
+
+                object([
+                    (
+                        "expression",
+                        JsValue::from_str(&map.group_ast(group).to_string()),
+                    ),
+                    ("inCover", JsValue::from_bool(group.in_cover)),
+                    ("cells", cells.into()),
+                ])
+            })
+            .collect::<js_sys::Array>();
+
+        let map = object([
+            ("rowVariables", names(map.row_variables()).into()),
+            ("columnVariables", names(map.column_variables()).into()),
+            (
+                "rowLabels",
+                strings((0..map.rows()).map(|row| map.row_label(row)).collect()).into(),
+            ),
+            (
+                "columnLabels",
+                strings((0..map.columns()).map(|c| map.column_label(c)).collect()).into(),
+            ),
+            ("cells", cells.into()),
+            ("groups", groups.into()),
+        ]);
+
+        Ok(map.into())
+    }
+}
+
+fn object<'a>(fields: impl IntoIterator<Item = (&'a str, JsValue)>) -> js_sys::Object {
+    let object = js_sys::Object::new();
+
+    for (key, value) in fields {
+        js_sys::Reflect::set(&object, &JsValue::from_str(key), &value).unwrap();
+    }
+
+    object
+}
+
+/// The `error` found in `input`, as an object the front end can tell apart
+/// by its `kind`, one of [EngineError::kind] or `invalid_argument`. It also
+/// has the `message`, the error `rendered` as annotated snippets, and its
+/// `diagnostics`, laid out like those [WasmEngine::diagnostics] returns,
+/// along with the variable `name` or the `count` and `limit` that went
+/// wrong where there are any.
+fn error_object(error: &EngineError, input: &str) -> JsValue {
+    let diagnostics: js_sys::Array = match error {
+        EngineError::Parse(errors) => errors.iter().map(parse_error_object).collect(),
+        _ => error.reports().iter().map(report_object).collect(),
+    };
+
+    let mut fields = vec![
+        ("kind", JsValue::from_str(error.kind())),
+        ("message", JsValue::from_str(&error.to_string())),
+        (
+            "rendered",
+            JsValue::from_str(&error.render(input, INPUT_NAME)),
+        ),
+        ("diagnostics", diagnostics.into()),
+    ];
+
+    match error {
+        EngineError::UnboundVariable { name, .. } | EngineError::Redefined { name, .. } => {
+            fields.push(("name", JsValue::from_str(name.as_str())));
+        }
+
+        EngineError::TooManyVariables { count, limit }
+        | EngineError::TooManyOutputs { count, limit } => {
+            fields.push(("count", JsValue::from_f64(*count as f64)));
+            fields.push(("limit", JsValue::from_f64(*limit as f64)));
+        }
+
+        EngineError::NormalFormTooLarge { limit } => {
+            fields.push(("limit", JsValue::from_f64(*limit as f64)));
+        }
+
+        EngineError::CountOverflow { variables } => {
+            fields.push(("count", JsValue::from_f64(*variables as f64)));
+        }
+
+        _ => {}
+    }
+
+    object(fields).into()
+}
+
+/// An error in the arguments rather than the input, such as a table format
+/// that doesn't exist
+fn argument_error(message: String) -> JsValue {
+    object([
+        ("kind", JsValue::from_str("invalid_argument")),
+        (
+            "rendered",
+            JsValue::from_str(&format!("error: {message}\n")),
+        ),
+        ("message", JsValue::from_str(&message)),
+        ("diagnostics", js_sys::Array::new().into()),
+    ])
+    .into()
+}
+
+fn parse_error_object(error: &ParseError) -> JsValue {
+    let expected = error
+        .expected
+        .iter()
+        .map(|expected| JsValue::from_str(expected))
+        .collect::<js_sys::Array>();
+
+    object([
+        ("start", JsValue::from_f64(error.span.start as f64)),
+        ("end", JsValue::from_f64(error.span.end as f64)),
+        ("message", JsValue::from_str(&error.to_string())),
+        ("expected", expected.into()),
+        ("found", optional_string(error.found.as_deref())),
+        ("help", optional_string(error.help.as_deref())),
+    ])
+    .into()
+}
+
+fn report_object(report: &Report) -> JsValue {
+    object([
+        ("start", JsValue::from_f64(report.span.start as f64)),
+        ("end", JsValue::from_f64(report.span.end as f64)),
+        ("message", JsValue::from_str(&report.message)),
+        ("label", optional_string(report.label.as_deref())),
+        ("help", optional_string(report.help.as_deref())),
+    ])
+    .into()
+}
+
+fn optional_string(string: Option<&str>) -> JsValue {
+    string.map_or(JsValue::NULL, JsValue::from_str)
+}
+
+fn assignments_to_object(assignments: &Assignments) -> js_sys::Object {
+    let object = js_sys::Object::new();
+
+    for (var, value) in assignments.iter() {
+        js_sys::Reflect::set(
+            &object,
+            &JsValue::from_str(var.as_str()),
+            &JsValue::from_bool(value),
+        )
+        .unwrap();
+    }
+
+    object
 }
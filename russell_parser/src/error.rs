@@ -0,0 +1,239 @@
+use std::fmt;
+
+use chumsky::error::{Rich, RichPattern, RichReason};
+use russell_ast::Span;
+
+use crate::report::Report;
+
+/// A single mistake found while parsing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Where in the input the mistake is
+    pub span: Span,
+
+    /// Descriptions of what would have been accepted at this position, such
+    /// as "identifier" or "`&&`"
+    pub expected: Vec<String>,
+
+    /// The text that was found instead, or [None] if the input ended early
+    pub found: Option<String>,
+
+    /// A suggestion on how to fix the mistake, if we have one
+    pub help: Option<String>,
+}
+
+impl ParseError {
+    pub(crate) fn from_rich(error: &Rich<'_, char>, input: &str) -> Self {
+        let span = error.span().into_range();
+
+        let (expected, found) = match error.reason() {
+            RichReason::ExpectedFound { expected, found } => {
+                let mut expected = expected.iter().filter_map(describe).collect::<Vec<_>>();
+
+                expected.sort();
+                expected.dedup();
+
+                (expected, found.as_ref().map(|c| c.to_string()))
+            }
+
+            RichReason::Custom(message) => (vec![message.clone()], None),
+        };
+
+        let help = suggest(input, &span, found.as_deref());
+
+        ParseError {
+            span,
+            expected,
+            found,
+            help,
+        }
+    }
+
+    /// Renders this error as an annotated snippet of `source`, which must be
+    /// the input it was produced from. `name` identifies the input (usually a
+    /// file path) in the output.
+    pub fn render(&self, source: &str, name: &str) -> String {
+        Report::from(self).render(source, name)
+    }
+
+    fn found_description(&self) -> String {
+        match &self.found {
+            Some(found) => format!("`{}`", found.escape_debug()),
+            None => "end of input".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.expected.as_slice() {
+            [] => write!(f, "unexpected {}", self.found_description()),
+            [expected] => write!(f, "expected {expected}, found {}", self.found_description()),
+            [init @ .., last] => write!(
+                f,
+                "expected {} or {last}, found {}",
+                init.join(", "),
+                self.found_description()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<&ParseError> for Report {
+    fn from(error: &ParseError) -> Self {
+        let label = match &error.found {
+            Some(_) => format!("unexpected {}", error.found_description()),
+            None => "input ends here".to_string(),
+        };
+
+        Report::error(error.to_string(), error.span.clone())
+            .with_label(label)
+            .with_help(error.help.clone())
+    }
+}
+
+/// Every mistake found in an input, in the order they appear
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseErrors(pub Vec<ParseError>);
+
+impl ParseErrors {
+    /// Renders every error, as with [ParseError::render]
+    pub fn render(&self, source: &str, name: &str) -> String {
+        self.0
+            .iter()
+            .map(|error| error.render(source, name))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &ParseError> {
+        self.0.iter()
+    }
+}
+
+impl fmt::Display for ParseErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages = self
+            .0
+            .iter()
+            .map(|error| format!("{}: {error}", error.span.start))
+            .collect::<Vec<_>>();
+
+        write!(f, "Parse error: {}", messages.join("; "))
+    }
+}
+
+impl std::error::Error for ParseErrors {}
+
+impl IntoIterator for ParseErrors {
+    type Item = ParseError;
+    type IntoIter = std::vec::IntoIter<ParseError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+fn describe(pattern: &RichPattern<'_, char>) -> Option<String> {
+    match pattern {
+        RichPattern::Token(c) => Some(format!("`{}`", c.escape_debug())),
+        RichPattern::Label(label) if *label == crate::PADDING => None,
+        RichPattern::Label(label) => Some(label.to_string()),
+        RichPattern::Identifier(ident) => Some(format!("`{ident}`")),
+        RichPattern::EndOfInput => Some("end of input".to_string()),
+
+        // these don't tell the user anything useful
+        RichPattern::Any | RichPattern::SomethingElse => None,
+    }
+}
+
+/// Guesses what the user meant, mostly by spotting operators from other
+/// languages and notations
+fn suggest(input: &str, span: &Span, found: Option<&str>) -> Option<String> {
+    let start = span.start.min(input.len());
+    let rest = &input[start..];
+
+    // a lone `&`, `|` or `=` makes the parser fail on the character after it,
+    // since it expected the rest of the operator
+    let operator_help = match input[..start].chars().last() {
+        Some('&') if !input[..start].ends_with("&&") => Some("use `&&` for conjunction"),
+        Some('|') if !input[..start].ends_with("||") => Some("use `||` for disjunction"),
+        Some('=') if !input[..start].ends_with("==") => {
+            Some("use `==` for equivalence, or `=>` for implication")
+        }
+        _ => None,
+    };
+
+    if let Some(help) = operator_help {
+        return Some(help.to_string());
+    }
+
+    let (depth, in_comment) = scan(&input[..start]);
+
+    let help = match found {
+        None if in_comment => "unterminated block comment, close it with `*/`",
+        None if input[..start].trim().is_empty() => {
+            "the input is empty, try an expression such as `p || !p`"
+        }
+        None => "the expression is incomplete, is an operand or `)` missing?",
+
+        Some("&") if !rest.starts_with("&&") => "use `&&` for conjunction",
+        Some("|") if !rest.starts_with("||") => "use `||` for disjunction",
+        Some("=") if !rest.starts_with("==") && !rest.starts_with("=>") => {
+            "use `==` for equivalence, or `=>` for implication"
+        }
+        Some("-") if rest.starts_with("->") => "use `=>` for implication",
+        Some("<") if rest.starts_with("<->") || rest.starts_with("<=>") => {
+            "use `==` for equivalence"
+        }
+        Some("~") | Some("¬") => "use `!` for negation",
+        Some("∧") => "use `&&` for conjunction",
+        Some("∨") => "use `||` for disjunction",
+        Some("→") | Some("⇒") => "use `=>` for implication",
+        Some("↔") | Some("⇔") | Some("≡") => "use `==` for equivalence",
+        Some(")") if depth <= 0 => "this `)` has no matching `(`",
+        Some(")") => "is an operand missing before this `)`?",
+        Some(c) if c.chars().all(|c| c.is_ascii_digit()) => {
+            "variable names must start with a letter or `_`"
+        }
+
+        _ => return None,
+    };
+
+    Some(help.to_string())
+}
+
+/// How many parentheses `text` leaves open, and whether it ends inside a
+/// block comment. Parentheses in comments don't count.
+fn scan(text: &str) -> (isize, bool) {
+    let mut depth = 0;
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(comment) = rest.strip_prefix("/*") {
+            let Some(end) = comment.find("*/") else {
+                return (depth, true);
+            };
+
+            rest = &comment[end + 2..];
+            continue;
+        }
+
+        if rest.starts_with('#') || rest.starts_with("//") {
+            rest = rest.find('\n').map_or("", |end| &rest[end..]);
+            continue;
+        }
+
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            _ => {}
+        }
+
+        rest = &rest[c.len_utf8()..];
+    }
+
+    (depth, false)
+}
@@ -1,97 +1,440 @@
+mod error;
+mod report;
+
 use chumsky::prelude::*;
-use russell_ast::ASTNode;
+use russell_ast::{
+    ASTNode, Comment, Document, Formula, Span, SpanTable, Statement, StatementKind, Symbol,
+};
+
+pub use error::{ParseError, ParseErrors};
+pub use report::{Level, Report, line_column};
 
 /// Parses an input into an [ASTNode]
-pub fn parse(input: &str) -> anyhow::Result<ASTNode> {
-    let parser = expr_parser();
+pub fn parse(input: &str) -> Result<ASTNode, ParseErrors> {
+    parse_spanned(input).map(|(ast, _)| ast)
+}
 
-    match parser.parse(input.trim()).into_result() {
-        Ok(ast) => Ok(ast),
-        Err(errors) => {
-            let error_msg = errors
-                .iter()
-                .map(|e| format!("{:?}", e))
-                .collect::<Vec<_>>()
-                .join(", ");
-            Err(anyhow::anyhow!("Parse error: {}", error_msg))
+/// Parses an input into an [ASTNode], along with the [Span] of every node in
+/// it. Spans are byte offsets into `input`.
+///
+/// The parser recovers from most mistakes, so every one of them is reported
+/// rather than just the first.
+pub fn parse_spanned(input: &str) -> Result<(ASTNode, SpanTable), ParseErrors> {
+    let parser = expr_parser().then_ignore(end()).padded_by(padding());
+
+    let spanned = parser
+        .parse(input)
+        .into_result()
+        .map_err(|errors| parse_errors(&errors, input))?;
+
+    Ok((spanned.node, spanned.spans.into()))
+}
+
+/// Parses a whole `.rsl` file into a [Document] of statements. See
+/// [Document] for the syntax.
+///
+/// If the input reads as a single formula up to its first mistake, the
+/// errors are those [parse_spanned] gives, since statements were most likely
+/// never meant.
+pub fn parse_document(input: &str) -> Result<Document, ParseErrors> {
+    let errors = match parse_statements(input) {
+        Ok(document) => return Ok(document),
+        Err(errors) => errors,
+    };
+
+    let first = |errors: &ParseErrors| errors.iter().next().map(|error| error.span.start);
+
+    match parse_spanned(input) {
+        Err(formula_errors) if first(&formula_errors) >= first(&errors) => Err(formula_errors),
+        _ => Err(errors),
+    }
+}
+
+fn parse_statements(input: &str) -> Result<Document, ParseErrors> {
+    let parser = document_parser();
+
+    let statements = parser
+        .parse(input)
+        .into_result()
+        .map_err(|errors| parse_errors(&errors, input))?;
+
+    let separated =
+        |pair: &[Statement]| input[pair[0].span.end..pair[1].span.start].contains(['\n', ';']);
+
+    // statements need something between them, or a missing operator would
+    // silently split a formula in two. A run of them is one mistake, so
+    // only its first gap is reported.
+    let errors = statements
+        .windows(2)
+        .enumerate()
+        .filter(|(i, pair)| !separated(pair) && (*i == 0 || separated(&statements[i - 1..=*i])))
+        .map(|(_, pair)| {
+            let start = pair[1].span.start;
+
+            ParseError {
+                span: start..start + 1,
+                expected: vec!["new line".to_string(), "`;`".to_string()],
+                found: input[start..].chars().next().map(String::from),
+                help: Some(
+                    "put each statement on a line of its own, or is an operator missing?"
+                        .to_string(),
+                ),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if !errors.is_empty() {
+        return Err(ParseErrors(errors));
+    }
+
+    Ok(Document {
+        statements,
+        comments: comments(input),
+    })
+}
+
+/// Every comment in `input`, a document that parsed. `include` paths are
+/// skipped, so a `#` in one isn't taken for a comment.
+fn comments(input: &str) -> Vec<Comment> {
+    let comment = line_comment()
+        .or(block_comment())
+        .map_with(|text: &str, e| {
+            Some(Comment {
+                text: text.to_string(),
+                span: SimpleSpan::into_range(e.span()),
+            })
+        });
+
+    let path = just('"')
+        .then(none_of("\"\n").repeated())
+        .then(just('"'))
+        .to(None);
+
+    choice((comment, path, any().to(None)))
+        .repeated()
+        .collect::<Vec<_>>()
+        .parse(input)
+        .into_output()
+        .unwrap_or_default()
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+fn parse_errors(errors: &[Rich<'_, char>], input: &str) -> ParseErrors {
+    let mut errors = errors
+        .iter()
+        .map(|e| ParseError::from_rich(e, input))
+        .collect::<Vec<_>>();
+
+    errors.sort_by_key(|e| e.span.start);
+
+    ParseErrors(errors)
+}
+
+/// A node along with the spans of its whole subtree, in preorder
+struct Spanned {
+    node: ASTNode,
+    spans: Vec<Span>,
+}
+
+impl Spanned {
+    fn leaf(node: ASTNode, span: Span) -> Self {
+        Spanned {
+            node,
+            spans: vec![span],
+        }
+    }
+
+    /// Stands in for a subexpression that failed to parse. It never makes it
+    /// out of [parse], since the errors are reported instead.
+    fn placeholder(span: Span) -> Self {
+        Spanned::leaf(ASTNode::Literal(false), span)
+    }
+
+    fn span(&self) -> Span {
+        self.spans[0].clone()
+    }
+
+    fn unary(op: fn(Box<ASTNode>) -> ASTNode, span: Span, inner: Spanned) -> Self {
+        let mut spans = Vec::with_capacity(1 + inner.spans.len());
+
+        spans.push(span);
+        spans.extend(inner.spans);
+
+        Spanned {
+            node: op(Box::new(inner.node)),
+            spans,
         }
     }
+
+    fn binary(
+        op: fn(Box<ASTNode>, Box<ASTNode>) -> ASTNode,
+        left: Spanned,
+        right: Spanned,
+    ) -> Self {
+        let mut spans = Vec::with_capacity(1 + left.spans.len() + right.spans.len());
+
+        spans.push(left.span().start..right.span().end);
+        spans.extend(left.spans);
+        spans.extend(right.spans);
+
+        Spanned {
+            node: op(Box::new(left.node), Box::new(right.node)),
+            spans,
+        }
+    }
+}
+
+/// The label for [padding], which error messages leave out since it can go
+/// anywhere
+pub(crate) const PADDING: &str = "padding";
+
+/// Whitespace, `# line comments`, `// line comments` and
+/// `/* block comments */`, which can go between any two tokens
+fn padding<'a>() -> impl Parser<'a, &'a str, (), extra::Err<Rich<'a, char>>> + Clone {
+    let whitespace = one_of(" \t\r\n").ignored();
+
+    choice((
+        whitespace,
+        line_comment().ignored(),
+        block_comment().ignored(),
+    ))
+    .labelled(PADDING)
+    .repeated()
+}
+
+/// A `#` or `//` comment, running to the end of the line, giving the text
+/// after the `#` or `//`
+fn line_comment<'a>() -> impl Parser<'a, &'a str, &'a str, extra::Err<Rich<'a, char>>> + Clone {
+    just("#")
+        .or(just("//"))
+        .ignore_then(any().and_is(just('\n').not()).repeated().to_slice())
+}
+
+/// A `/* */` comment, giving the text between the `/*` and `*/`
+fn block_comment<'a>() -> impl Parser<'a, &'a str, &'a str, extra::Err<Rich<'a, char>>> + Clone {
+    just("/*")
+        .ignore_then(any().and_is(just("*/").not()).repeated().to_slice())
+        .then_ignore(just("*/").labelled("`*/`"))
+}
+
+fn document_parser<'a>() -> impl Parser<'a, &'a str, Vec<Statement>, extra::Err<Rich<'a, char>>> {
+    let formula = expr_parser().map(|spanned| Formula {
+        expr: spanned.node,
+        spans: spanned.spans.into(),
+    });
+
+    let name = text::ascii::ident().padded_by(padding()).labelled("name");
+
+    let path = none_of("\"\n")
+        .repeated()
+        .to_slice()
+        .delimited_by(just('"'), just('"'))
+        .labelled("path in quotes");
+
+    let statement = choice((
+        keyword("let")
+            .ignore_then(name)
+            .then_ignore(just('=').padded_by(padding()))
+            .then(formula.clone())
+            .map(|(name, formula)| StatementKind::Let {
+                name: Symbol::intern(name),
+                formula,
+            }),
+        keyword("assume")
+            .ignore_then(formula.clone())
+            .map(StatementKind::Assume),
+        keyword("prove")
+            .ignore_then(formula.clone())
+            .map(StatementKind::Prove),
+        keyword("include")
+            .ignore_then(path)
+            .map(|path: &str| StatementKind::Include {
+                path: path.to_string(),
+            }),
+        formula.map(StatementKind::Formula),
+    ))
+    .map_with(|kind, e| {
+        let mut span = SimpleSpan::into_range(e.span());
+
+        // formulas take the padding after them along, which isn't part of
+        // the statement
+        if let StatementKind::Let { formula, .. }
+        | StatementKind::Assume(formula)
+        | StatementKind::Prove(formula)
+        | StatementKind::Formula(formula) = &kind
+            && let Some(formula) = formula.span()
+        {
+            span.end = formula.end;
+        }
+
+        Statement { kind, span }
+    })
+    .then_ignore(padding())
+    .then_ignore(just(';').padded_by(padding()).or_not());
+
+    padding()
+        .ignore_then(statement.repeated().collect())
+        .then_ignore(end())
+}
+
+/// A keyword, which can't be the start of a longer name
+fn keyword<'a>(
+    word: &'static str,
+) -> impl Parser<'a, &'a str, (), extra::Err<Rich<'a, char>>> + Clone {
+    text::ascii::ident()
+        .filter(move |name: &&str| *name == word)
+        .ignored()
+        .labelled(word)
+        .padded_by(padding())
 }
 
-fn expr_parser<'a>() -> impl Parser<'a, &'a str, ASTNode, extra::Err<Rich<'a, char>>> {
+/// An expression, along with any padding around it
+fn expr_parser<'a>() -> impl Parser<'a, &'a str, Spanned, extra::Err<Rich<'a, char>>> + Clone {
     recursive(|expr| {
-        // Variables: single lowercase letters
-        let variable = one_of('a'..='z').map(ASTNode::Variable);
+        // Identifiers: variables, or the literals true and false. Parsing
+        // them together means names like `trueish` aren't split in two
+        let ident = text::ascii::ident()
+            .map_with(|name: &str, e| {
+                let node = match name {
+                    "true" => ASTNode::Literal(true),
+                    "false" => ASTNode::Literal(false),
+                    _ => ASTNode::Variable(Symbol::intern(name)),
+                };
 
-        // Literals: true and false
-        let literal = just("true")
-            .to(ASTNode::Literal(true))
-            .or(just("false").to(ASTNode::Literal(false)));
+                Spanned::leaf(node, SimpleSpan::into_range(e.span()))
+            })
+            .labelled("identifier");
 
-        // Parenthesized expressions
+        // Parenthesized expressions. If the inside is broken we skip to the
+        // matching `)`, so mistakes after it are still found
         let parenthesized = expr
             .clone()
             .delimited_by(just('('), just(')'))
-            .map(|inner| ASTNode::Paren(Box::new(inner)));
+            .map_with(|inner, e| {
+                Spanned::unary(ASTNode::Paren, SimpleSpan::into_range(e.span()), inner)
+            })
+            .recover_with(via_parser(nested_delimiters(
+                '(',
+                ')',
+                [],
+                |span: SimpleSpan| Spanned::placeholder(span.into_range()),
+            )));
 
-        // Atoms: literals, variables, or parenthesized expressions (order matters!)
-        let atom = choice((literal, variable, parenthesized)).padded();
+        // Atoms: identifiers or parenthesized expressions. On a stray
+        // character (such as the second `&` in `p & & q`) we report it, skip
+        // it and try again
+        let atom = choice((ident, parenthesized))
+            .padded_by(padding())
+            .recover_with(skip_then_retry_until(
+                none_of("()").ignored(),
+                one_of(")").ignored().or(end()),
+            ));
 
         // Not operator (highest precedence, prefix)
         let not_expr = just('!')
+            .map_with(|_, e| SimpleSpan::into_range(e.span()))
             .repeated()
-            .foldr(atom, |_op, expr| ASTNode::Not(Box::new(expr)));
+            .foldr(atom, |op: Span, expr| {
+                Spanned::unary(ASTNode::Not, op.start..expr.span().end, expr)
+            });
 
         // And operator (left associative)
         let and_expr = not_expr.clone().foldl(
-            just("&&").padded().ignore_then(not_expr).repeated(),
-            |left, right| ASTNode::And(Box::new(left), Box::new(right)),
+            just("&&")
+                .labelled("`&&`")
+                .padded_by(padding())
+                .ignore_then(not_expr)
+                .repeated(),
+            |left, right| Spanned::binary(ASTNode::And, left, right),
         );
 
         // Or operator (left associative)
         let or_expr = and_expr.clone().foldl(
-            just("||").padded().ignore_then(and_expr).repeated(),
-            |left, right| ASTNode::Or(Box::new(left), Box::new(right)),
+            just("||")
+                .labelled("`||`")
+                .padded_by(padding())
+                .ignore_then(and_expr)
+                .repeated(),
+            |left, right| Spanned::binary(ASTNode::Or, left, right),
         );
 
         // Implies operator (right associative)
         let implies_expr = or_expr
             .clone()
-            .separated_by(just("=>").padded())
+            .separated_by(just("=>").labelled("`=>`").padded_by(padding()))
             .at_least(1)
             .collect::<Vec<_>>()
             .map(|mut exprs| {
                 // Right-fold manually for right associativity
                 let mut result = exprs.pop().unwrap();
                 while let Some(left) = exprs.pop() {
-                    result = ASTNode::Implies(Box::new(left), Box::new(result));
+                    result = Spanned::binary(ASTNode::Implies, left, result);
                 }
                 result
             });
 
         // Equivalent operator (left associative, lowest precedence)
-        let equiv_expr = implies_expr.clone().foldl(
-            just("==").padded().ignore_then(implies_expr).repeated(),
-            |left, right| ASTNode::Equivalent(Box::new(left), Box::new(right)),
-        );
-
-        equiv_expr
+        implies_expr.clone().foldl(
+            just("==")
+                .labelled("`==`")
+                .padded_by(padding())
+                .ignore_then(implies_expr)
+                .repeated(),
+            |left, right| Spanned::binary(ASTNode::Equivalent, left, right),
+        )
     })
-    .then_ignore(end())
-    .padded()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use russell_ast::NodeId;
 
     #[test]
     fn test_variables() {
         for c in 'a'..='z' {
-            assert_eq!(parse(c).unwrap(), ASTNode::Variable(c));
+            let name = c.to_string();
+            assert_eq!(
+                parse(&name).unwrap(),
+                ASTNode::Variable(name.as_str().into())
+            );
         }
     }
 
+    #[test]
+    fn test_multi_character_variables() {
+        assert_eq!(
+            parse("door_open && alarm_armed2").unwrap(),
+            ASTNode::And(
+                Box::new(ASTNode::Variable("door_open".into())),
+                Box::new(ASTNode::Variable("alarm_armed2".into()))
+            )
+        );
+
+        assert_eq!(parse("Door").unwrap(), ASTNode::Variable("Door".into()));
+        assert_eq!(parse("_x").unwrap(), ASTNode::Variable("_x".into()));
+    }
+
+    #[test]
+    fn test_variables_starting_with_literal_names() {
+        assert_eq!(
+            parse("trueish").unwrap(),
+            ASTNode::Variable("trueish".into())
+        );
+        assert_eq!(
+            parse("false_alarm").unwrap(),
+            ASTNode::Variable("false_alarm".into())
+        );
+    }
+
+    #[test]
+    fn test_invalid_identifiers() {
+        assert!(parse("1p").is_err());
+        assert!(parse("p q").is_err());
+    }
+
     #[test]
     fn test_literals() {
         assert_eq!(parse("true").unwrap(), ASTNode::Literal(true));
@@ -111,11 +454,13 @@ mod tests {
 
         assert_eq!(
             parse("(x)").unwrap(),
-            ASTNode::Paren(Box::new(ASTNode::Variable('x')))
+            ASTNode::Paren(Box::new(ASTNode::Variable("x".into())))
         );
         assert_eq!(
             parse("((x))").unwrap(),
-            ASTNode::Paren(Box::new(ASTNode::Paren(Box::new(ASTNode::Variable('x')))))
+            ASTNode::Paren(Box::new(ASTNode::Paren(Box::new(ASTNode::Variable(
+                "x".into()
+            )))))
         );
     }
 
@@ -123,12 +468,14 @@ mod tests {
     fn test_not() {
         assert_eq!(
             parse("!x").unwrap(),
-            ASTNode::Not(Box::new(ASTNode::Variable('x')))
+            ASTNode::Not(Box::new(ASTNode::Variable("x".into())))
         );
 
         assert_eq!(
             parse("!!x").unwrap(),
-            ASTNode::Not(Box::new(ASTNode::Not(Box::new(ASTNode::Variable('x')))))
+            ASTNode::Not(Box::new(ASTNode::Not(Box::new(ASTNode::Variable(
+                "x".into()
+            )))))
         );
     }
 
@@ -137,18 +484,18 @@ mod tests {
         assert_eq!(
             parse("x && y").unwrap(),
             ASTNode::And(
-                Box::new(ASTNode::Variable('x')),
-                Box::new(ASTNode::Variable('y'))
+                Box::new(ASTNode::Variable("x".into())),
+                Box::new(ASTNode::Variable("y".into()))
             )
         );
 
         assert_eq!(
             parse("x && (y && z)").unwrap(),
             ASTNode::And(
-                Box::new(ASTNode::Variable('x')),
+                Box::new(ASTNode::Variable("x".into())),
                 Box::new(ASTNode::Paren(Box::new(ASTNode::And(
-                    Box::new(ASTNode::Variable('y')),
-                    Box::new(ASTNode::Variable('z'))
+                    Box::new(ASTNode::Variable("y".into())),
+                    Box::new(ASTNode::Variable("z".into()))
                 ))))
             )
         );
@@ -159,18 +506,18 @@ mod tests {
         assert_eq!(
             parse("x || y").unwrap(),
             ASTNode::Or(
-                Box::new(ASTNode::Variable('x')),
-                Box::new(ASTNode::Variable('y'))
+                Box::new(ASTNode::Variable("x".into())),
+                Box::new(ASTNode::Variable("y".into()))
             )
         );
 
         assert_eq!(
             parse("x || (y || z)").unwrap(),
             ASTNode::Or(
-                Box::new(ASTNode::Variable('x')),
+                Box::new(ASTNode::Variable("x".into())),
                 Box::new(ASTNode::Paren(Box::new(ASTNode::Or(
-                    Box::new(ASTNode::Variable('y')),
-                    Box::new(ASTNode::Variable('z'))
+                    Box::new(ASTNode::Variable("y".into())),
+                    Box::new(ASTNode::Variable("z".into()))
                 ))))
             )
         );
@@ -181,18 +528,18 @@ mod tests {
         assert_eq!(
             parse("x => y").unwrap(),
             ASTNode::Implies(
-                Box::new(ASTNode::Variable('x')),
-                Box::new(ASTNode::Variable('y'))
+                Box::new(ASTNode::Variable("x".into())),
+                Box::new(ASTNode::Variable("y".into()))
             )
         );
 
         assert_eq!(
             parse("x => (y => z)").unwrap(),
             ASTNode::Implies(
-                Box::new(ASTNode::Variable('x')),
+                Box::new(ASTNode::Variable("x".into())),
                 Box::new(ASTNode::Paren(Box::new(ASTNode::Implies(
-                    Box::new(ASTNode::Variable('y')),
-                    Box::new(ASTNode::Variable('z'))
+                    Box::new(ASTNode::Variable("y".into())),
+                    Box::new(ASTNode::Variable("z".into()))
                 ))))
             )
         );
@@ -203,8 +550,8 @@ mod tests {
         assert_eq!(
             parse("!p && q").unwrap(),
             ASTNode::And(
-                Box::new(ASTNode::Not(Box::new(ASTNode::Variable('p')))),
-                Box::new(ASTNode::Variable('q'))
+                Box::new(ASTNode::Not(Box::new(ASTNode::Variable("p".into())))),
+                Box::new(ASTNode::Variable("q".into()))
             )
         );
     }
@@ -214,10 +561,10 @@ mod tests {
         assert_eq!(
             parse("p || q && r").unwrap(),
             ASTNode::Or(
-                Box::new(ASTNode::Variable('p')),
+                Box::new(ASTNode::Variable("p".into())),
                 Box::new(ASTNode::And(
-                    Box::new(ASTNode::Variable('q')),
-                    Box::new(ASTNode::Variable('r'))
+                    Box::new(ASTNode::Variable("q".into())),
+                    Box::new(ASTNode::Variable("r".into()))
                 ))
             )
         );
@@ -228,10 +575,10 @@ mod tests {
         assert_eq!(
             parse("p => q || r").unwrap(),
             ASTNode::Implies(
-                Box::new(ASTNode::Variable('p')),
+                Box::new(ASTNode::Variable("p".into())),
                 Box::new(ASTNode::Or(
-                    Box::new(ASTNode::Variable('q')),
-                    Box::new(ASTNode::Variable('r'))
+                    Box::new(ASTNode::Variable("q".into())),
+                    Box::new(ASTNode::Variable("r".into()))
                 ))
             )
         );
@@ -243,10 +590,10 @@ mod tests {
             parse("(p || q) && r").unwrap(),
             ASTNode::And(
                 Box::new(ASTNode::Paren(Box::new(ASTNode::Or(
-                    Box::new(ASTNode::Variable('p')),
-                    Box::new(ASTNode::Variable('q'))
+                    Box::new(ASTNode::Variable("p".into())),
+                    Box::new(ASTNode::Variable("q".into()))
                 )))),
-                Box::new(ASTNode::Variable('r'))
+                Box::new(ASTNode::Variable("r".into()))
             )
         );
     }
@@ -256,29 +603,169 @@ mod tests {
         assert_eq!(
             parse("  p   &&   q  ").unwrap(),
             ASTNode::And(
-                Box::new(ASTNode::Variable('p')),
-                Box::new(ASTNode::Variable('q'))
+                Box::new(ASTNode::Variable("p".into())),
+                Box::new(ASTNode::Variable("q".into()))
             )
         );
     }
 
+    #[test]
+    fn test_comments() {
+        let expected = parse("p && q").unwrap();
+
+        assert_eq!(parse("p && # the second one\n q").unwrap(), expected);
+        assert_eq!(parse("/* both */ p /* and */ && q /**/").unwrap(), expected);
+        assert_eq!(parse("p && q # done").unwrap(), expected);
+        assert_eq!(parse("p && // done\nq").unwrap(), expected);
+
+        let errors = parse("p && /* q").unwrap_err().0;
+        assert_eq!(errors[0].expected, vec!["`*/`"]);
+    }
+
+    #[test]
+    fn test_document() {
+        let input = "\
+# modus tollens
+let rule = p => q;
+assume rule /* the rule */
+assume !q
+prove !p
+
+include \"laws.rsl\"
+p || !p";
+
+        let document = parse_document(input).unwrap();
+        let kinds = document
+            .statements
+            .iter()
+            .map(|statement| &statement.kind)
+            .collect::<Vec<_>>();
+
+        let formula = |text: &str| {
+            let (expr, spans) = parse_spanned(text).unwrap();
+            (expr, spans.root().unwrap().len())
+        };
+
+        let StatementKind::Let {
+            name,
+            formula: rule,
+        } = kinds[0]
+        else {
+            panic!("expected a definition, got {:?}", kinds[0]);
+        };
+        assert_eq!(name.as_str(), "rule");
+        assert_eq!(rule.expr, formula("p => q").0);
+        assert_eq!(&input[rule.span().unwrap()], "p => q");
+
+        assert!(matches!(kinds[1], StatementKind::Assume(f) if f.expr == formula("rule").0));
+        assert!(matches!(kinds[2], StatementKind::Assume(f) if f.expr == formula("!q").0));
+        assert!(matches!(kinds[3], StatementKind::Prove(f) if f.expr == formula("!p").0));
+        assert_eq!(
+            kinds[4],
+            &StatementKind::Include {
+                path: "laws.rsl".to_string()
+            }
+        );
+        assert!(matches!(kinds[5], StatementKind::Formula(f) if f.expr == formula("p || !p").0));
+
+        assert_eq!(&input[document.statements[3].span.clone()], "prove !p");
+    }
+
+    #[test]
+    fn test_document_comments() {
+        let input = "# first\np /* second */ && q // third\ninclude \"not # a comment\"";
+        let document = parse_document(input).unwrap();
+
+        let comments = document
+            .comments
+            .iter()
+            .map(|comment| (comment.text.as_str(), &input[comment.span.clone()]))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            comments,
+            [
+                (" first", "# first"),
+                (" second ", "/* second */"),
+                (" third", "// third"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_document_keywords_only_start_statements() {
+        let document = parse_document("letter => proven; assume_it").unwrap();
+
+        assert_eq!(document.statements.len(), 2);
+        assert!(
+            document
+                .statements
+                .iter()
+                .all(|statement| matches!(statement.kind, StatementKind::Formula(_)))
+        );
+
+        assert!(parse_document("# nothing\n").unwrap().statements.is_empty());
+        assert!(parse_document("let = p").is_err());
+        assert!(parse_document("include laws.rsl").is_err());
+    }
+
+    #[test]
+    fn test_document_statements_need_separating() {
+        let errors = parse_document("p || !p q").unwrap_err().0;
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span, 8..9);
+        assert_eq!(errors[0].found.as_deref(), Some("q"));
+
+        assert!(parse_document("(p => q) (q => p)").is_err());
+        assert!(parse_document("prove p /* same line */ prove q").is_err());
+
+        assert_eq!(parse_document("p; q\nr # ok").unwrap().statements.len(), 3);
+
+        // a run of statements without separators is one mistake
+        let errors = parse_document("prove p; a b c\nq").unwrap_err().0;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span, 11..12);
+        assert_eq!(errors[0].expected, ["new line", "`;`"]);
+    }
+
+    #[test]
+    fn test_document_errors_for_a_single_formula() {
+        // what reads as one formula gets the errors a formula would
+        for input in ["p and q", "p ∧ q", "p &&\n  q ∧ r"] {
+            assert_eq!(
+                parse_document(input),
+                Err(parse(input).unwrap_err()),
+                "{input}"
+            );
+        }
+
+        let errors = parse_document("p ∧ q").unwrap_err().0;
+        assert!(!errors[0].expected.contains(&"let".to_string()));
+        assert_eq!(errors[0].help.as_deref(), Some("use `&&` for conjunction"));
+
+        // but not once it's clearly a document
+        let errors = parse_document("let a = p\na ∧ q").unwrap_err().0;
+        assert!(errors[0].expected.contains(&"let".to_string()));
+    }
+
     #[test]
     fn test_equivalent() {
         assert_eq!(
             parse("x == y").unwrap(),
             ASTNode::Equivalent(
-                Box::new(ASTNode::Variable('x')),
-                Box::new(ASTNode::Variable('y'))
+                Box::new(ASTNode::Variable("x".into())),
+                Box::new(ASTNode::Variable("y".into()))
             )
         );
 
         assert_eq!(
             parse("x == (y == z)").unwrap(),
             ASTNode::Equivalent(
-                Box::new(ASTNode::Variable('x')),
+                Box::new(ASTNode::Variable("x".into())),
                 Box::new(ASTNode::Paren(Box::new(ASTNode::Equivalent(
-                    Box::new(ASTNode::Variable('y')),
-                    Box::new(ASTNode::Variable('z'))
+                    Box::new(ASTNode::Variable("y".into())),
+                    Box::new(ASTNode::Variable("z".into()))
                 ))))
             )
         );
@@ -290,12 +777,12 @@ mod tests {
             parse("p => q == r => s").unwrap(),
             ASTNode::Equivalent(
                 Box::new(ASTNode::Implies(
-                    Box::new(ASTNode::Variable('p')),
-                    Box::new(ASTNode::Variable('q'))
+                    Box::new(ASTNode::Variable("p".into())),
+                    Box::new(ASTNode::Variable("q".into()))
                 )),
                 Box::new(ASTNode::Implies(
-                    Box::new(ASTNode::Variable('r')),
-                    Box::new(ASTNode::Variable('s'))
+                    Box::new(ASTNode::Variable("r".into())),
+                    Box::new(ASTNode::Variable("s".into()))
                 ))
             )
         );
@@ -307,12 +794,12 @@ mod tests {
             parse("p || q == r || s").unwrap(),
             ASTNode::Equivalent(
                 Box::new(ASTNode::Or(
-                    Box::new(ASTNode::Variable('p')),
-                    Box::new(ASTNode::Variable('q'))
+                    Box::new(ASTNode::Variable("p".into())),
+                    Box::new(ASTNode::Variable("q".into()))
                 )),
                 Box::new(ASTNode::Or(
-                    Box::new(ASTNode::Variable('r')),
-                    Box::new(ASTNode::Variable('s'))
+                    Box::new(ASTNode::Variable("r".into())),
+                    Box::new(ASTNode::Variable("s".into()))
                 ))
             )
         );
@@ -325,13 +812,13 @@ mod tests {
             ASTNode::Equivalent(
                 Box::new(ASTNode::Not(Box::new(ASTNode::Paren(Box::new(
                     ASTNode::Implies(
-                        Box::new(ASTNode::Variable('p')),
-                        Box::new(ASTNode::Variable('q'))
+                        Box::new(ASTNode::Variable("p".into())),
+                        Box::new(ASTNode::Variable("q".into()))
                     )
                 ))))),
                 Box::new(ASTNode::Or(
-                    Box::new(ASTNode::Variable('p')),
-                    Box::new(ASTNode::Not(Box::new(ASTNode::Variable('q'))))
+                    Box::new(ASTNode::Variable("p".into())),
+                    Box::new(ASTNode::Not(Box::new(ASTNode::Variable("q".into()))))
                 ))
             )
         );
@@ -341,7 +828,9 @@ mod tests {
     fn test_nested_parentheses() {
         assert_eq!(
             parse("((x))").unwrap(),
-            ASTNode::Paren(Box::new(ASTNode::Paren(Box::new(ASTNode::Variable('x')))))
+            ASTNode::Paren(Box::new(ASTNode::Paren(Box::new(ASTNode::Variable(
+                "x".into()
+            )))))
         );
     }
 
@@ -350,7 +839,7 @@ mod tests {
         assert_eq!(
             parse("!!!x").unwrap(),
             ASTNode::Not(Box::new(ASTNode::Not(Box::new(ASTNode::Not(Box::new(
-                ASTNode::Variable('x')
+                ASTNode::Variable("x".into())
             ))))))
         );
     }
@@ -360,8 +849,8 @@ mod tests {
         assert_eq!(
             parse("(p == q)").unwrap(),
             ASTNode::Paren(Box::new(ASTNode::Equivalent(
-                Box::new(ASTNode::Variable('p')),
-                Box::new(ASTNode::Variable('q'))
+                Box::new(ASTNode::Variable("p".into())),
+                Box::new(ASTNode::Variable("q".into()))
             )))
         );
     }
@@ -372,10 +861,10 @@ mod tests {
             parse("p && q && r").unwrap(),
             ASTNode::And(
                 Box::new(ASTNode::And(
-                    Box::new(ASTNode::Variable('p')),
-                    Box::new(ASTNode::Variable('q'))
+                    Box::new(ASTNode::Variable("p".into())),
+                    Box::new(ASTNode::Variable("q".into()))
                 )),
-                Box::new(ASTNode::Variable('r'))
+                Box::new(ASTNode::Variable("r".into()))
             )
         );
     }
@@ -386,10 +875,10 @@ mod tests {
             parse("p || q || r").unwrap(),
             ASTNode::Or(
                 Box::new(ASTNode::Or(
-                    Box::new(ASTNode::Variable('p')),
-                    Box::new(ASTNode::Variable('q'))
+                    Box::new(ASTNode::Variable("p".into())),
+                    Box::new(ASTNode::Variable("q".into()))
                 )),
-                Box::new(ASTNode::Variable('r'))
+                Box::new(ASTNode::Variable("r".into()))
             )
         );
     }
@@ -399,10 +888,10 @@ mod tests {
         assert_eq!(
             parse("p => q => r").unwrap(),
             ASTNode::Implies(
-                Box::new(ASTNode::Variable('p')),
+                Box::new(ASTNode::Variable("p".into())),
                 Box::new(ASTNode::Implies(
-                    Box::new(ASTNode::Variable('q')),
-                    Box::new(ASTNode::Variable('r'))
+                    Box::new(ASTNode::Variable("q".into())),
+                    Box::new(ASTNode::Variable("r".into()))
                 ))
             )
         );
@@ -414,17 +903,17 @@ mod tests {
             parse("p == q == r").unwrap(),
             ASTNode::Equivalent(
                 Box::new(ASTNode::Equivalent(
-                    Box::new(ASTNode::Variable('p')),
-                    Box::new(ASTNode::Variable('q'))
+                    Box::new(ASTNode::Variable("p".into())),
+                    Box::new(ASTNode::Variable("q".into()))
                 )),
-                Box::new(ASTNode::Variable('r'))
+                Box::new(ASTNode::Variable("r".into()))
             )
         );
     }
 
     #[test]
     fn test_single_variable() {
-        assert_eq!(parse("x").unwrap(), ASTNode::Variable('x'));
+        assert_eq!(parse("x").unwrap(), ASTNode::Variable("x".into()));
     }
 
     #[test]
@@ -441,7 +930,9 @@ mod tests {
     fn test_not_with_parentheses() {
         assert_eq!(
             parse("!(x)").unwrap(),
-            ASTNode::Not(Box::new(ASTNode::Paren(Box::new(ASTNode::Variable('x')))))
+            ASTNode::Not(Box::new(ASTNode::Paren(Box::new(ASTNode::Variable(
+                "x".into()
+            )))))
         );
     }
 
@@ -464,14 +955,14 @@ mod tests {
                 Box::new(ASTNode::Implies(
                     Box::new(ASTNode::Or(
                         Box::new(ASTNode::And(
-                            Box::new(ASTNode::Not(Box::new(ASTNode::Variable('p')))),
-                            Box::new(ASTNode::Variable('q'))
+                            Box::new(ASTNode::Not(Box::new(ASTNode::Variable("p".into())))),
+                            Box::new(ASTNode::Variable("q".into()))
                         )),
-                        Box::new(ASTNode::Variable('r'))
+                        Box::new(ASTNode::Variable("r".into()))
                     )),
-                    Box::new(ASTNode::Variable('s'))
+                    Box::new(ASTNode::Variable("s".into()))
                 )),
-                Box::new(ASTNode::Variable('t'))
+                Box::new(ASTNode::Variable("t".into()))
             )
         );
     }
@@ -483,16 +974,134 @@ mod tests {
             ASTNode::Equivalent(
                 Box::new(ASTNode::Or(
                     Box::new(ASTNode::Paren(Box::new(ASTNode::And(
-                        Box::new(ASTNode::Variable('p')),
-                        Box::new(ASTNode::Variable('q'))
+                        Box::new(ASTNode::Variable("p".into())),
+                        Box::new(ASTNode::Variable("q".into()))
                     )))),
                     Box::new(ASTNode::Paren(Box::new(ASTNode::Implies(
-                        Box::new(ASTNode::Variable('r')),
-                        Box::new(ASTNode::Variable('s'))
+                        Box::new(ASTNode::Variable("r".into())),
+                        Box::new(ASTNode::Variable("s".into()))
                     ))))
                 )),
-                Box::new(ASTNode::Paren(Box::new(ASTNode::Variable('t'))))
+                Box::new(ASTNode::Paren(Box::new(ASTNode::Variable("t".into()))))
             )
         );
     }
+
+    #[test]
+    fn test_spans() {
+        let (ast, spans) = parse_spanned("!(p && q) || r").unwrap();
+
+        let texts = ast
+            .preorder()
+            .iter()
+            .enumerate()
+            .map(|(id, _)| &"!(p && q) || r"[spans.get(NodeId(id)).unwrap()])
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            texts,
+            vec![
+                "!(p && q) || r",
+                "!(p && q)",
+                "(p && q)",
+                "p && q",
+                "p",
+                "q",
+                "r"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_spans_are_relative_to_untrimmed_input() {
+        let input = "  \n door_open  =>  !!alarm ";
+        let (ast, spans) = parse_spanned(input).unwrap();
+
+        assert_eq!(spans.len(), ast.preorder().len());
+        assert_eq!(&input[spans.root().unwrap()], "door_open  =>  !!alarm");
+
+        let alarm = ast.find_all(|node| *node == ASTNode::Variable("alarm".into()));
+        assert_eq!(&input[spans.get(alarm[0]).unwrap()], "alarm");
+    }
+
+    #[test]
+    fn test_error_details() {
+        let errors = parse("p && & q").unwrap_err().0;
+
+        assert_eq!(
+            errors,
+            vec![ParseError {
+                span: 5..6,
+                expected: vec!["`!`".into(), "`(`".into(), "identifier".into()],
+                found: Some("&".into()),
+                help: Some("use `&&` for conjunction".into()),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_error_at_end_of_input() {
+        let errors = parse("p =>").unwrap_err().0;
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span, 4..4);
+        assert_eq!(errors[0].found, None);
+    }
+
+    #[test]
+    fn test_error_recovery_reports_every_mistake() {
+        let errors = parse("(p &&) || (q ||) || ~r").unwrap_err().0;
+
+        let starts = errors.iter().map(|e| e.span.start).collect::<Vec<_>>();
+        assert_eq!(starts, vec![5, 15, 20]);
+    }
+
+    #[test]
+    fn test_error_help_for_foreign_operators() {
+        let help = |input| parse(input).unwrap_err().0[0].help.clone().unwrap();
+
+        assert_eq!(help("p -> q"), "use `=>` for implication");
+        assert_eq!(help("p | q"), "use `||` for disjunction");
+        assert_eq!(
+            help("p = q"),
+            "use `==` for equivalence, or `=>` for implication"
+        );
+        assert_eq!(help("~p"), "use `!` for negation");
+    }
+
+    #[test]
+    fn test_error_help_for_parentheses_and_comments() {
+        let help = |input| parse(input).unwrap_err().0[0].help.clone().unwrap();
+
+        assert_eq!(help("p && q)"), "this `)` has no matching `(`");
+        assert_eq!(help("(p /* ( */ && q))"), "this `)` has no matching `(`");
+        assert_eq!(help("(r &&)"), "is an operand missing before this `)`?");
+
+        assert_eq!(
+            help("p /* unterminated"),
+            "unterminated block comment, close it with `*/`"
+        );
+        assert_eq!(
+            help("p && /* done */"),
+            "the expression is incomplete, is an operand or `)` missing?"
+        );
+    }
+
+    #[test]
+    fn test_display_round_trips() {
+        for input in [
+            "p",
+            "!!p",
+            "!(p && q) == !p || !q",
+            "p => q => r",
+            "(p => q) => r",
+            "p && (q || r) == (p == q) == r",
+            "a && b && c || !d && (e => f)",
+        ] {
+            let expr = parse(input).unwrap();
+
+            assert_eq!(expr.to_string(), input);
+            assert_eq!(parse(&expr.to_string()).unwrap(), expr);
+        }
+    }
 }
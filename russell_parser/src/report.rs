@@ -0,0 +1,195 @@
+use std::fmt::Write;
+
+use russell_ast::Span;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+/// A message about a piece of source text, which can be rendered as an
+/// annotated snippet:
+///
+/// ```text
+/// error: expected identifier or `(`, found `&`
+///  --> <stdin>:1:6
+///   |
+/// 1 | p && & q
+///   |      ^ unexpected `&`
+///   |
+///   = help: use `&&` for conjunction
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub level: Level,
+    pub message: String,
+    pub span: Span,
+    pub label: Option<String>,
+    pub help: Option<String>,
+}
+
+impl Report {
+    pub fn error(message: impl Into<String>, span: Span) -> Self {
+        Report {
+            level: Level::Error,
+            message: message.into(),
+            span,
+            label: None,
+            help: None,
+        }
+    }
+
+    pub fn warning(message: impl Into<String>, span: Span) -> Self {
+        Report {
+            level: Level::Warning,
+            ..Report::error(message, span)
+        }
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: Option<String>) -> Self {
+        self.help = help;
+        self
+    }
+
+    /// Renders the report against `source`, which `span` points into. `name`
+    /// identifies the source (usually a file path) in the output.
+    pub fn render(&self, source: &str, name: &str) -> String {
+        let (line_no, line_start) = locate(source, self.span.start);
+
+        let line = source[line_start..]
+            .split('\n')
+            .next()
+            .unwrap_or_default()
+            .trim_end_matches('\r');
+
+        // columns are counted in characters rather than bytes, so underlines
+        // line up with non-ascii text
+        let start = self.span.start.clamp(line_start, line_start + line.len());
+        let end = self.span.end.clamp(start, line_start + line.len());
+
+        let column = source[line_start..start].chars().count();
+        let width = source[start..end].chars().count().max(1);
+
+        let gutter = " ".repeat(line_no.to_string().len());
+        let level = match self.level {
+            Level::Error => "error",
+            Level::Warning => "warning",
+        };
+
+        let mut out = String::new();
+
+        writeln!(out, "{level}: {}", self.message).unwrap();
+        writeln!(out, "{gutter}--> {name}:{line_no}:{}", column + 1).unwrap();
+        writeln!(out, "{gutter} |").unwrap();
+        writeln!(out, "{line_no} | {line}").unwrap();
+        write!(
+            out,
+            "{gutter} | {}{}",
+            " ".repeat(column),
+            "^".repeat(width)
+        )
+        .unwrap();
+
+        if let Some(label) = &self.label {
+            write!(out, " {label}").unwrap();
+        }
+
+        out.push('\n');
+
+        if let Some(help) = &self.help {
+            writeln!(out, "{gutter} |").unwrap();
+            writeln!(out, "{gutter} = help: {help}").unwrap();
+        }
+
+        out
+    }
+}
+
+/// The line and column a byte offset in `source` is at, both counted from 1
+/// and columns in characters, as diagnostics show them
+pub fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(source.len());
+    let (line_no, line_start) = locate(source, offset);
+
+    (line_no, source[line_start..offset].chars().count() + 1)
+}
+
+/// Finds the (1-based) line number of a byte offset, and the offset the line
+/// starts at
+fn locate(source: &str, offset: usize) -> (usize, usize) {
+    let offset = offset.min(source.len());
+    let before = &source[..offset];
+
+    let line_no = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+
+    (line_no, line_start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_column() {
+        let source = "p && q\n¬r || s";
+
+        assert_eq!(line_column(source, 0), (1, 1));
+        assert_eq!(line_column(source, 5), (1, 6));
+        assert_eq!(line_column(source, 7), (2, 1));
+        assert_eq!(line_column(source, 10), (2, 3));
+        assert_eq!(line_column(source, 100), (2, 8));
+    }
+
+    #[test]
+    fn test_render() {
+        let report = Report::error("something is wrong", 7..8)
+            .with_label("here")
+            .with_help(Some("fix it".to_string()));
+
+        assert_eq!(
+            report.render("p && q\nr || ~s", "laws.rsl"),
+            "error: something is wrong\n \
+             --> laws.rsl:2:1\n  \
+             |\n\
+             2 | r || ~s\n  \
+             | ^ here\n  \
+             |\n  \
+             = help: fix it\n"
+        );
+    }
+
+    #[test]
+    fn test_render_counts_characters() {
+        let report = Report::warning("odd", 4..7);
+
+        assert_eq!(
+            report.render("¬p ∧ q", "<stdin>"),
+            "warning: odd\n \
+             --> <stdin>:1:4\n  \
+             |\n\
+             1 | ¬p ∧ q\n  \
+             |    ^\n"
+        );
+    }
+
+    #[test]
+    fn test_render_at_end_of_input() {
+        let report = Report::error("incomplete", 4..4);
+
+        assert_eq!(
+            report.render("p &&", "<stdin>"),
+            "error: incomplete\n \
+             --> <stdin>:1:5\n  \
+             |\n\
+             1 | p &&\n  \
+             |     ^\n"
+        );
+    }
+}